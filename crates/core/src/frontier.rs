@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::{Board, Cell, solver::convolve_all};
+
+/// A revealed number, as a limit on how many of its frontier cells can be bombs
+struct Limit {
+    low: u8,
+    high: u8,
+    /// Index of its first frontier cell
+    first: usize,
+    /// Index of its last frontier cell
+    last: usize,
+}
+
+/// Counts the ways to assign bombs to frontier cells that every revealed number allows,
+/// and draws from them uniformly, without listing them.
+/// Cells that share no number are counted separately and the counts multiplied.
+pub struct Frontier {
+    components: Vec<Component>,
+    /// Indices into the frontier of each component's cells
+    cells: Vec<Vec<usize>>,
+    /// Assignments of each component and the ones after it, by how many bombs they take
+    suffixes: Vec<Vec<f64>>,
+    max_bombs: usize,
+}
+
+impl Frontier {
+    /// Count assignments of `cells`, which must be unassigned, with at most `max_bombs` bombs.
+    /// `None` if that takes more than `budget` partial assignments.
+    pub fn new(
+        board: &Board,
+        cells: &[(usize, usize)],
+        max_bombs: usize,
+        budget: usize,
+    ) -> Option<Self> {
+        let groups = components(board, cells);
+        let mut components = Vec::with_capacity(groups.len());
+        let mut used = 0;
+        for group in &groups {
+            let group_cells = group.iter().map(|i| cells[*i]).collect::<Vec<_>>();
+            let component = Component::new(board, &group_cells, max_bombs, budget - used)?;
+            used += component.table.len();
+            components.push(component);
+        }
+        let mut suffixes = vec![vec![1.]];
+        for component in components.iter().rev() {
+            let mut suffix = convolve_all([&component.counts, &suffixes[0]].into_iter());
+            suffix.truncate(max_bombs + 1);
+            suffixes.insert(0, suffix);
+        }
+        Some(Self {
+            components,
+            cells: groups,
+            suffixes,
+            max_bombs,
+        })
+    }
+
+    /// How many assignments there are
+    pub fn count(&self) -> f64 {
+        self.suffixes[0].iter().sum()
+    }
+
+    /// How many partial assignments it took to count them
+    pub fn size(&self) -> usize {
+        self.components.iter().map(|c| c.table.len()).sum()
+    }
+
+    /// Pick one of the assignments, each as likely as the others
+    pub fn sample(&mut self, rng: &mut impl Rng) -> Vec<bool> {
+        let mut state = vec![false; self.cells.iter().map(Vec::len).sum()];
+        let mut bombs = self.max_bombs;
+        for (k, component) in self.components.iter_mut().enumerate() {
+            // Weigh each bomb count by how many ways the later components can take the rest
+            let rest = &self.suffixes[k + 1];
+            let weights = component
+                .counts
+                .iter()
+                .take(bombs + 1)
+                .enumerate()
+                .map(|(j, n)| n * rest.iter().take(bombs - j + 1).sum::<f64>())
+                .collect::<Vec<_>>();
+            let mut pick = rng.random::<f64>() * weights.iter().sum::<f64>();
+            let taken = weights
+                .iter()
+                .position(|w| {
+                    pick -= w;
+                    pick < 0.
+                })
+                .unwrap_or(weights.len().saturating_sub(1));
+            for (i, bomb) in self.cells[k].iter().zip(component.sample(rng, taken)) {
+                state[*i] = bomb;
+            }
+            bombs -= taken;
+        }
+        state
+    }
+}
+
+/// Group `cells` by the numbers they share, as sorted indices into `cells`
+fn components(board: &Board, cells: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let index = cells
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (*p, i))
+        .collect::<HashMap<_, _>>();
+    let mut linked = vec![Vec::<usize>::new(); cells.len()];
+    for (x, y) in board.points() {
+        if !matches!(board[(x, y)], Cell::Discovered(Some(_))) {
+            continue;
+        }
+        let members = board
+            .neighbors(x, y)
+            .filter_map(|(nx, ny, _)| index.get(&(nx, ny)).copied())
+            .collect::<Vec<_>>();
+        for i in &members {
+            linked[*i].extend(&members);
+        }
+    }
+    let mut seen = vec![false; cells.len()];
+    let mut components = vec![];
+    for start in 0..cells.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut component = vec![];
+        let mut queue = vec![start];
+        while let Some(cell) = queue.pop() {
+            component.push(cell);
+            for next in &linked[cell] {
+                if !seen[*next] {
+                    seen[*next] = true;
+                    queue.push(*next);
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
+
+/// Counts the assignments of one group of cells.
+/// Cells get assigned in order, and assignments that leave every half-assigned number with
+/// the same partial sum are counted once, by how many bombs the rest of the cells take.
+struct Component {
+    /// Which limits each cell counts towards, and how many of their cells come after it
+    touches: Vec<Vec<(usize, u8)>>,
+    limits: Vec<Limit>,
+    /// Before each cell, the half-assigned limits, in order
+    open: Vec<Vec<usize>>,
+    max_bombs: usize,
+    /// The most entries `table` may hold
+    budget: usize,
+    /// Completions of a partial assignment, indexed by how many bombs they take
+    table: HashMap<(usize, Vec<u8>), Vec<f64>>,
+    /// Assignments of every cell, by how many bombs they take
+    counts: Vec<f64>,
+}
+
+impl Component {
+    fn new(
+        board: &Board,
+        cells: &[(usize, usize)],
+        max_bombs: usize,
+        budget: usize,
+    ) -> Option<Self> {
+        let index = cells
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (*p, i))
+            .collect::<HashMap<_, _>>();
+        let mut touches = vec![Vec::new(); cells.len()];
+        let mut limits = Vec::new();
+        let mut impossible = false;
+        for (x, y) in board.points() {
+            let Cell::Discovered(Some(n)) = board[(x, y)] else {
+                continue;
+            };
+            let mut members = Vec::new();
+            let (mut fixed_low, mut fixed_high) = (0, 0);
+            for (nx, ny, c) in board.neighbors(x, y) {
+                if let Some(i) = index.get(&(nx, ny)) {
+                    members.push(*i);
+                } else {
+                    fixed_low += c.bomb_count().start();
+                    fixed_high += c.bomb_count().end();
+                }
+            }
+            members.sort_unstable();
+            let (Some(&first), Some(&last)) = (members.first(), members.last()) else {
+                continue;
+            };
+            // The frontier has to make up whatever the rest can't
+            let Ok(high) = u8::try_from(n - fixed_low) else {
+                impossible = true;
+                continue;
+            };
+            for (k, i) in members.iter().enumerate() {
+                #[allow(clippy::cast_possible_truncation)]
+                touches[*i].push((limits.len(), (members.len() - k - 1) as u8));
+            }
+            limits.push(Limit {
+                low: u8::try_from((n - fixed_high).max(0)).unwrap_or(high),
+                high,
+                first,
+                last,
+            });
+        }
+        let open = (0..=cells.len())
+            .map(|i| {
+                (0..limits.len())
+                    .filter(|l| limits[*l].first < i && limits[*l].last >= i)
+                    .collect()
+            })
+            .collect();
+        let mut component = Self {
+            touches,
+            limits,
+            open,
+            max_bombs,
+            budget,
+            table: HashMap::new(),
+            counts: Vec::new(),
+        };
+        if !impossible {
+            component.counts = component.completions(0, Vec::new())?;
+        }
+        Some(component)
+    }
+
+    /// Pick one of the assignments with exactly `bombs` bombs, each as likely as the others
+    fn sample(&mut self, rng: &mut impl Rng, mut bombs: usize) -> Vec<bool> {
+        let mut sums = Vec::new();
+        let mut state = Vec::with_capacity(self.touches.len());
+        for i in 0..self.touches.len() {
+            let safe = self.next(i, &sums, false);
+            let bomb = self.next(i, &sums, true).filter(|_| bombs > 0);
+            let safe_weight = self.weight(i + 1, safe.clone(), bombs);
+            let bomb_weight = self.weight(i + 1, bomb.clone(), bombs.saturating_sub(1));
+            let is_bomb = rng.random::<f64>() * (safe_weight + bomb_weight) >= safe_weight;
+            let Some(next) = (if is_bomb { bomb } else { safe }) else {
+                // Only when there was nothing to pick from
+                return state;
+            };
+            bombs -= usize::from(is_bomb);
+            sums = next;
+            state.push(is_bomb);
+        }
+        state
+    }
+
+    /// Assignments from cell `i` on, given the partial sums, with exactly `bombs` bombs
+    fn weight(&mut self, i: usize, sums: Option<Vec<u8>>, bombs: usize) -> f64 {
+        let Some(sums) = sums else {
+            return 0.;
+        };
+        self.completions(i, sums)
+            .and_then(|poly| poly.get(bombs).copied())
+            .unwrap_or(0.)
+    }
+
+    /// The partial sums after cell `i`, or `None` if `bomb` breaks a limit
+    fn next(&self, i: usize, sums: &[u8], bomb: bool) -> Option<Vec<u8>> {
+        let before = |l: usize| self.open[i].binary_search(&l).map_or(0, |at| sums[at]);
+        let touches = |l: usize| self.touches[i].iter().any(|(t, _)| *t == l);
+        for &(l, left) in &self.touches[i] {
+            let limit = &self.limits[l];
+            let sum = before(l) + u8::from(bomb);
+            if sum > limit.high || sum + left < limit.low {
+                return None;
+            }
+        }
+        Some(
+            self.open[i + 1]
+                .iter()
+                .map(|&l| before(l) + u8::from(bomb && touches(l)))
+                .collect(),
+        )
+    }
+
+    /// Completions from cell `i` on, by bombs taken, or `None` once the table is over budget
+    fn completions(&mut self, i: usize, sums: Vec<u8>) -> Option<Vec<f64>> {
+        if i == self.touches.len() {
+            return Some(vec![1.]);
+        }
+        let key = (i, sums);
+        if let Some(poly) = self.table.get(&key) {
+            return Some(poly.clone());
+        }
+        if self.table.len() >= self.budget {
+            return None;
+        }
+        let mut poly = Vec::new();
+        for bomb in [false, true] {
+            let Some(next) = self.next(i, &key.1, bomb) else {
+                continue;
+            };
+            let rest = self.completions(i + 1, next)?;
+            let shift = usize::from(bomb);
+            let len = (rest.len() + shift).min(self.max_bombs + 1);
+            poly.resize(poly.len().max(len), 0.);
+            for (k, n) in rest.iter().enumerate().take(len.saturating_sub(shift)) {
+                poly[k + shift] += n;
+            }
+        }
+        self.table.insert(key, poly.clone());
+        Some(poly)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        Neighborhood,
+        solver::{BruteForce, cross_check},
+    };
+
+    /// `shape` partway through a game, as the player sees it: numbers on some of the safe
+    /// cells and nothing decided about the rest. Returns it with how many bombs it holds.
+    fn partway(shape: &Board, rng: &mut StdRng) -> (Board, usize) {
+        let mut real = shape.clone();
+        for p in shape.points() {
+            real[p] = Cell::Concrete(i8::from(rng.random_bool(0.3)));
+        }
+        let mut seen = shape.clone();
+        for p in shape.points() {
+            seen[p] = if !real[p].is_bomb() && rng.random_bool(0.4) {
+                let n = real
+                    .neighbors(p.0, p.1)
+                    .filter(|(_, _, c)| c.is_bomb())
+                    .count();
+                Cell::Discovered(Some(i16::try_from(n).unwrap()))
+            } else {
+                Cell::Quantum(None)
+            };
+        }
+        let bombs = real.points().filter(|p| real[*p].is_bomb()).count();
+        (seen, bombs)
+    }
+
+    #[test]
+    fn small_boards_agree_with_brute_force() {
+        let stencil = Board::from_stencil("##.##\n#####\n.###.\n##.##\n").unwrap();
+        let shapes = [
+            Board::new(5, 4),
+            Board::new(4, 4).with_wrap(true),
+            Board::new(5, 4).with_neighborhood(Neighborhood::Knight),
+            Board::new(4, 5)
+                .with_neighborhood(Neighborhood::VonNeumann)
+                .with_wrap(true),
+            Board::new(3, 6).with_layers(2),
+            stencil.clone(),
+            stencil.with_neighborhood(Neighborhood::Knight),
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        for shape in &shapes {
+            assert!(shape.points().count() <= 20);
+            for _ in 0..25 {
+                let (board, bombs) = partway(shape, &mut rng);
+                let problems = cross_check(&board, bombs);
+                assert!(problems.is_empty(), "{problems:?} on\n{board:?}");
+                let worlds = BruteForce::new(&board, bombs).unwrap().worlds();
+                let cells = board
+                    .points()
+                    .filter(|p| matches!(board[*p], Cell::Quantum(None)))
+                    .collect_vec();
+                let frontier = Frontier::new(&board, &cells, bombs, usize::MAX).unwrap();
+                #[allow(clippy::cast_precision_loss)]
+                let expected = worlds as f64;
+                let got = frontier.suffixes[0].get(bombs).copied().unwrap_or(0.);
+                assert!(
+                    (got - expected).abs() < 1e-9,
+                    "{got} states with {bombs} bombs, should be {expected}, on\n{board:?}"
+                );
+            }
+        }
+    }
+}
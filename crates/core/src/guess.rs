@@ -0,0 +1,114 @@
+//! Ways to pick a cell to guess once the numbers prove nothing, for hints
+
+#[cfg(feature = "onnx")]
+use std::path::Path;
+
+use crate::Game;
+
+/// Ranks the cells worth guessing, for [`Game::hint_or_guess`]
+pub trait GuessPolicy: Send + Sync {
+    /// Hidden cells of `game` to guess, best first
+    ///
+    /// # Errors
+    /// If the policy couldn't rank them, saying why
+    fn rank(&self, game: &Game) -> Result<Vec<(usize, usize)>, String>;
+}
+
+/// The cell least likely to be a bomb as the solver works it out, ties broken by how much
+/// its number would tell
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Safest;
+
+impl GuessPolicy for Safest {
+    fn rank(&self, game: &Game) -> Result<Vec<(usize, usize)>, String> {
+        Ok(game
+            .solver()
+            .ok_or_else(|| format!("The solver can't read {} numbers", game.ruleset.name()))?
+            .best_guesses(&game.seen())
+            .into_iter()
+            .map(|g| g.cell)
+            .collect())
+    }
+}
+
+/// A small neural network ranking the cells. It takes what [`crate::training::observe`]
+/// sees with a batch axis in front, shaped (1, channel, row, column), and gives a score for
+/// every cell a row at a time, higher for a better guess. A model taking one channel more
+/// than [`crate::training::CHANNELS`] gets the solver's bomb chances too.
+#[cfg(feature = "onnx")]
+pub struct Onnx {
+    model: tract_onnx::prelude::InferenceModel,
+    probabilities: bool,
+}
+
+#[cfg(feature = "onnx")]
+impl Onnx {
+    /// Load an ONNX model
+    ///
+    /// # Errors
+    /// If the file can't be read or isn't a model with one input
+    pub fn load(path: &Path) -> Result<Self, String> {
+        use tract_onnx::{prelude::*, tract_hir::infer::Factoid};
+
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .map_err(|e| e.to_string())?;
+        let input = model.input_fact(0).map_err(|e| e.to_string())?;
+        let channels = input
+            .shape
+            .dims()
+            .nth(1)
+            .and_then(Factoid::concretize)
+            .and_then(|d| usize::try_from(d.to_i64().ok()?).ok());
+        Ok(Self {
+            model,
+            probabilities: channels == Some(crate::training::CHANNELS + 1),
+        })
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl GuessPolicy for Onnx {
+    /// Optimizes the model for the board's size on every call, which takes a moment but
+    /// copes with boards of any size
+    fn rank(&self, game: &Game) -> Result<Vec<(usize, usize)>, String> {
+        use tract_onnx::prelude::*;
+
+        let observation = crate::training::observe(game, self.probabilities);
+        let (channels, height, width) = observation.dim();
+        let plan = self
+            .model
+            .clone()
+            .with_input_fact(0, f32::fact([1, channels, height, width]).into())
+            // Whatever output size the model names, it follows from the input's
+            .and_then(|model| model.with_output_fact(0, InferenceFact::default()))
+            .and_then(InferenceModel::into_optimized)
+            .and_then(TypedModel::into_runnable)
+            .map_err(|e| e.to_string())?;
+        let input = Tensor::from_shape(
+            &[1, channels, height, width],
+            &observation.iter().copied().collect::<Vec<_>>(),
+        )
+        .map_err(|e| e.to_string())?;
+        let outputs = plan.run(tvec!(input.into())).map_err(|e| e.to_string())?;
+        let scores = outputs[0].as_slice::<f32>().map_err(|e| e.to_string())?;
+        if scores.len() != width * height {
+            return Err(format!(
+                "{} scores for {width}x{height} cells",
+                scores.len()
+            ));
+        }
+        let mut cells = game
+            .board
+            .points()
+            .filter(|p| {
+                matches!(
+                    game.board[*p],
+                    crate::Cell::Quantum(_) | crate::Cell::Concrete(_)
+                )
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by(|a, b| scores[b.1 * width + b.0].total_cmp(&scores[a.1 * width + a.0]));
+        Ok(cells)
+    }
+}
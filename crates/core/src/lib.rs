@@ -0,0 +1,2348 @@
+//! The board, collapses and solver behind Bastard Minesweeper, without the GUI, for other
+//! minesweeper projects to build on. Everything public follows semver, so a breaking change
+//! never arrives in a version Cargo would pick up on its own.
+#![warn(clippy::pedantic)]
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    ops::{Deref, DerefMut, Range, RangeInclusive},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use itertools::Itertools;
+use ndarray::{Array2, Axis};
+use rand::{Rng, SeedableRng, rng, rngs::StdRng};
+use rayon::prelude::*;
+
+use clap::ValueEnum;
+use frontier::Frontier;
+use policy::{Choosing, CollapsePolicy, Policy, State};
+use rules::{Rule, Ruleset};
+use spread::Spread;
+use web_time::Instant;
+
+mod frontier;
+pub mod guess;
+pub mod opening;
+pub mod pack;
+pub mod policy;
+pub mod render;
+pub mod rules;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod selfcheck;
+pub mod simulate;
+pub mod solver;
+pub mod spread;
+pub mod stats;
+pub mod training;
+pub mod worker;
+
+/// Above this many partial assignments, `collapse` searches for states instead of counting them
+pub const ENUMERATION_BUDGET: f64 = 1e5;
+/// How many random states to draw
+const SAMPLE_COUNT: usize = 10_000;
+/// How many assignments a single random search may try before giving up
+const SAMPLE_STEP_LIMIT: usize = 100_000;
+
+/// Limits that trade `collapse` quality for speed, defaulting to the constants above,
+/// and the [`Policy`] it plays by
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollapseConfig {
+    /// Above this many partial assignments, search for states instead of counting them
+    pub enumeration_budget: f64,
+    /// How many random states to draw
+    pub sample_count: usize,
+    /// How many assignments a single random search may try before giving up
+    pub sample_step_limit: usize,
+    /// How long to spend looking for the most common set of numbers
+    pub signature_time: Duration,
+    /// Threads to collapse on, 0 for one per core
+    pub threads: usize,
+    /// Run collapse threads at a lower scheduling priority
+    pub low_priority: bool,
+    /// How collapses pick what to keep
+    pub policy: Policy,
+    /// Once the numbers are picked, leave the cells they don't settle undecided, to be decided
+    /// when they're revealed
+    pub lazy: bool,
+    /// Favor states whose bombs sit relative to each other like this
+    pub spread: Spread,
+}
+
+impl Default for CollapseConfig {
+    fn default() -> Self {
+        Self {
+            enumeration_budget: ENUMERATION_BUDGET,
+            sample_count: SAMPLE_COUNT,
+            sample_step_limit: SAMPLE_STEP_LIMIT,
+            signature_time: Duration::from_secs(2),
+            threads: 0,
+            low_priority: false,
+            policy: Policy::Bastard,
+            lazy: false,
+            spread: Spread::Random,
+        }
+    }
+}
+
+impl CollapseConfig {
+    /// Read `key = value` lines, keeping the default for anything missing or malformed.
+    /// Also gives back the lines it ignored, for the frontend to warn about.
+    #[must_use]
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let mut ignored = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                ignored.push(line.to_string());
+                continue;
+            };
+            let value = value.trim();
+            let ok = match key.trim() {
+                "enumeration_budget" => {
+                    value.parse().map(|v| config.enumeration_budget = v).is_ok()
+                }
+                "sample_count" => value.parse().map(|v| config.sample_count = v).is_ok(),
+                "sample_step_limit" => value.parse().map(|v| config.sample_step_limit = v).is_ok(),
+                "signature_secs" => value
+                    .parse()
+                    .map(|v| config.signature_time = Duration::from_secs_f64(v))
+                    .is_ok(),
+                // Left over from enumerating states one by one
+                "parallel_period" => true,
+                "threads" => value.parse().map(|v| config.threads = v).is_ok(),
+                "low_priority" => value.parse().map(|v| config.low_priority = v).is_ok(),
+                "policy" => Policy::from_str(value, true)
+                    .map(|v| config.policy = v)
+                    .is_ok(),
+                "lazy" => value.parse().map(|v| config.lazy = v).is_ok(),
+                "spread" => Spread::from_str(value, true)
+                    .map(|v| config.spread = v)
+                    .is_ok(),
+                _ => false,
+            };
+            if !ok {
+                ignored.push(line.to_string());
+            }
+        }
+        (config, ignored)
+    }
+
+    /// Format as `key = value` lines that [`CollapseConfig::parse`] reads back
+    #[must_use]
+    pub fn to_config_string(&self) -> String {
+        format!(
+            "enumeration_budget = {}\nsample_count = {}\nsample_step_limit = {}\nsignature_secs = {}\nthreads = {}\nlow_priority = {}\npolicy = {}\nlazy = {}\nspread = {}\n",
+            self.enumeration_budget,
+            self.sample_count,
+            self.sample_step_limit,
+            self.signature_time.as_secs_f64(),
+            self.threads,
+            self.low_priority,
+            self.policy
+                .to_possible_value()
+                .map_or_else(String::new, |v| v.get_name().to_string()),
+            self.lazy,
+            self.spread
+                .to_possible_value()
+                .map_or_else(String::new, |v| v.get_name().to_string()),
+        )
+    }
+
+    /// Cheaper limits for running on battery: smaller budgets, two low priority threads
+    #[must_use]
+    pub fn energy_saver(self) -> Self {
+        Self {
+            enumeration_budget: self.enumeration_budget / 10.,
+            sample_count: self.sample_count / 4,
+            signature_time: self.signature_time / 4,
+            threads: if self.threads == 0 {
+                2
+            } else {
+                self.threads.min(2)
+            },
+            low_priority: true,
+            ..self
+        }
+    }
+
+    /// No time limit on picking the numbers to keep, so a seeded collapse comes out the
+    /// same on any machine
+    #[must_use]
+    pub fn reproducible(self) -> Self {
+        Self {
+            signature_time: Duration::MAX,
+            ..self
+        }
+    }
+
+    /// A thread pool for one collapse, sized and prioritized by this config
+    ///
+    /// # Panics
+    /// If the operating system won't start threads
+    #[must_use]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool(&self) -> Pool {
+        let low_priority = self.low_priority;
+        Pool(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .start_handler(move |_| {
+                    if low_priority {
+                        lower_priority();
+                    }
+                })
+                .build()
+                .unwrap(),
+        )
+    }
+
+    /// The calling thread, as browsers can't start more
+    #[must_use]
+    #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::unused_self)]
+    pub fn pool(&self) -> Pool {
+        Pool()
+    }
+
+    /// Benchmark this machine and pick limits that keep a collapse to roughly `target`
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn tune(target: Duration) -> Tuning {
+        let mut config = Self::default();
+        // A first click's worth of numbers, with the ring around them left open
+        let mut board = Board::new(16, 16);
+        for p in (5..10).cartesian_product(5..10) {
+            board[p] = Cell::Discovered(None);
+        }
+        board.collapse_with(&config, 8, None);
+        board.fill_discovered();
+        let cells = board
+            .points()
+            .filter(|p| matches!(board[*p], Cell::Quantum(_)))
+            .filter(|(x, y)| {
+                board
+                    .neighbors(*x, *y)
+                    .any(|(_, _, c)| matches!(c, Cell::Discovered(_)))
+            })
+            .collect_vec();
+        for p in board.points().collect_vec() {
+            if matches!(board[p], Cell::Quantum(_)) {
+                board[p] = Cell::Quantum(None);
+            }
+        }
+
+        let began = Instant::now();
+        let mut entries = 0;
+        while began.elapsed() < Duration::from_millis(200) {
+            entries += Frontier::new(&board, &cells, 8, usize::MAX).map_or(0, |f| f.size());
+        }
+        let per_entry = began.elapsed().as_secs_f64() / entries.max(1) as f64;
+        config.enumeration_budget = (target.as_secs_f64() / per_entry).round();
+
+        let began = Instant::now();
+        let mut sampled = 0;
+        let probe = Self {
+            sample_count: 100,
+            ..config
+        };
+        while began.elapsed() < Duration::from_millis(200) {
+            board.sample_states(&cells, 8, &probe, &mut rng(), || false);
+            sampled += probe.sample_count;
+        }
+        let per_sample = began.elapsed().as_secs_f64() / sampled as f64;
+        config.sample_count =
+            ((target.as_secs_f64() / per_sample) as usize).clamp(1_000, 1_000_000);
+        config.signature_time = target;
+        Tuning {
+            config,
+            assignments_per_sec: 1. / per_entry,
+            samples_per_sec: 1. / per_sample,
+        }
+    }
+}
+
+/// What [`CollapseConfig::tune`] measured, and the limits it picked from that
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tuning {
+    pub config: CollapseConfig,
+    /// Partial assignments enumerated a second
+    pub assignments_per_sec: f64,
+    /// States sampled a second
+    pub samples_per_sec: f64,
+}
+
+/// Where one collapse does its parallel work. Browsers can't start threads, so on the web
+/// that's rayon's global pool, which runs everything on the calling thread.
+pub struct Pool(#[cfg(not(target_arch = "wasm32"))] rayon::ThreadPool);
+
+impl Pool {
+    /// Run `op` with its parallel iterators in this pool
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        self.0.install(op)
+    }
+
+    /// Run `op` with its parallel iterators in this pool
+    #[cfg(target_arch = "wasm32")]
+    #[allow(clippy::unused_self)]
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        op()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cell {
+    Quantum(Option<bool>),
+    /// Revealed, with its number once it's counted, which anti-mines can take below zero
+    Discovered(Option<i16>),
+    /// Placed up front, with this many bombs: one or none, or up to a few in multimines, and
+    /// an anti-mine below zero
+    Concrete(#[cfg_attr(feature = "serde", serde(deserialize_with = "save::bombs"))] i8),
+    /// A hole in a shaped board, which isn't played and has no neighbors
+    Void,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::Quantum(None)
+    }
+}
+
+impl Cell {
+    #[must_use]
+    pub fn bomb_count(&self) -> RangeInclusive<i16> {
+        match self {
+            Cell::Quantum(None) => 0..=1,
+            Cell::Quantum(Some(b)) => i16::from(*b)..=i16::from(*b),
+            Cell::Concrete(n) => i16::from(*n)..=i16::from(*n),
+            Cell::Discovered(_) | Cell::Void => 0..=0,
+        }
+    }
+
+    /// Whether revealing the cell could lose, so anti-mines count too
+    #[must_use]
+    pub fn is_bomb(&self) -> bool {
+        match self {
+            Cell::Quantum(Some(b)) => *b,
+            Cell::Concrete(n) => *n != 0,
+            Cell::Quantum(None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Which cells a number counts, relative to its own
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighborhood {
+    /// The eight cells around it, as in classic minesweeper
+    #[default]
+    Moore,
+    /// The four cells sharing an edge
+    VonNeumann,
+    /// The eight cells a chess knight's move away
+    Knight,
+}
+
+impl Neighborhood {
+    /// How far away along either axis a neighbor can be
+    #[must_use]
+    pub fn reach(self) -> usize {
+        match self {
+            Neighborhood::Moore | Neighborhood::VonNeumann => 1,
+            Neighborhood::Knight => 2,
+        }
+    }
+    /// Where the neighbors are, relative to the cell
+    pub fn offsets(self) -> impl Iterator<Item = (isize, isize)> {
+        let reach = self.reach().cast_signed();
+        (-reach..=reach)
+            .cartesian_product(-reach..=reach)
+            .filter(move |&(dx, dy)| match self {
+                Neighborhood::Moore => (dx, dy) != (0, 0),
+                Neighborhood::VonNeumann => dx.abs() + dy.abs() == 1,
+                Neighborhood::Knight => dx.abs() * dy.abs() == 2,
+            })
+    }
+}
+
+impl std::str::FromStr for Neighborhood {
+    type Err = String;
+
+    /// `moore`, `von-neumann` or `knight`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moore" => Ok(Neighborhood::Moore),
+            "von-neumann" => Ok(Neighborhood::VonNeumann),
+            "knight" => Ok(Neighborhood::Knight),
+            _ => Err(format!("{s:?} isn't moore, von-neumann or knight")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "save::BoardData", try_from = "save::BoardData")
+)]
+pub struct Board {
+    cells: Array2<Cell>,
+    /// Whether the edges wrap around, so the board is a torus
+    wrap: bool,
+    neighborhood: Neighborhood,
+    /// Layers the rows are split into, stacked top to bottom, for a board in three dimensions
+    layers: usize,
+}
+
+/// Facts about a whole board with its bombs placed, that don't give away where any bomb is
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoardStatistics {
+    /// Connected areas of cells with no bombs around them, each cleared by a single click
+    pub openings: usize,
+    /// Connected groups of safe cells that no opening reveals
+    pub islands: usize,
+    /// Cells the biggest opening reveals, counting the numbers around its edge
+    pub largest_opening: usize,
+    /// Fewest clicks that clear the board: one per opening, plus each safe cell no opening reveals
+    pub three_bv: usize,
+    /// Share of the bombs that are on the edge of the board
+    pub edge_mines: f64,
+    /// Coin flips a player solving by deduction runs into, starting from the biggest opening
+    pub fifty_fifties: usize,
+}
+
+impl Deref for Board {
+    type Target = Array2<Cell>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cells
+    }
+}
+
+impl DerefMut for Board {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.cells
+    }
+}
+
+impl Board {
+    #[must_use]
+    pub fn new(w: usize, h: usize) -> Self {
+        Self {
+            cells: Array2::default((w, h)),
+            wrap: false,
+            neighborhood: Neighborhood::default(),
+            layers: 1,
+        }
+    }
+    /// Make the edges wrap around or not. Wrapping boards need to be more than twice the
+    /// neighborhood's reach across, or cells would neighbor themselves.
+    #[must_use]
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+    /// Whether the edges wrap around
+    #[must_use]
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+    /// Count `neighborhood` around each number instead of the eight cells around it
+    #[must_use]
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+    #[must_use]
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+    /// Split the rows into `layers` layers stacked on top of each other, so each cell also
+    /// neighbors the cells in the same places in the layers above and below, and the one
+    /// right above or below it. With the usual neighborhood that's the 26 cells around it.
+    /// The height should be a multiple of `layers`.
+    #[must_use]
+    pub fn with_layers(mut self, layers: usize) -> Self {
+        self.layers = layers;
+        self
+    }
+    #[must_use]
+    pub fn layers(&self) -> usize {
+        self.layers
+    }
+    /// Rows in each layer
+    #[must_use]
+    pub fn layer_height(&self) -> usize {
+        self.dim().1 / self.layers
+    }
+    /// Whether every cell's neighbors are right next to it, without wrapping or other
+    /// layers, so collapses can stay in a rectangle around a click
+    #[must_use]
+    pub fn is_local(&self) -> bool {
+        !self.wrap && self.neighborhood == Neighborhood::default() && self.layers == 1
+    }
+    /// Every cell that's played, so not the holes in a shaped board
+    pub fn points(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = self.dim();
+        (0..width)
+            .cartesian_product(0..height)
+            .filter(|p| !matches!(self[*p], Cell::Void))
+    }
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.kernel()
+            .filter_map(move |(d, dz)| self.offset_layers((x, y), d, dz))
+            .map(|(x, y)| (x, y, &self[(x, y)]))
+    }
+    /// Where each neighbor is, as an offset in its layer and how many layers down
+    fn kernel(&self) -> impl Iterator<Item = ((isize, isize), isize)> {
+        let depth = isize::from(self.layers > 1);
+        (-depth..=depth).flat_map(move |dz| {
+            self.neighborhood
+                .offsets()
+                .chain((dz != 0).then_some((0, 0)))
+                .map(move |d| (d, dz))
+        })
+    }
+    /// The cell `(dx, dy)` away from `(x, y)` in its layer, going around the edges of a
+    /// wrapping board, or `None` if it's off the board or a hole in it
+    #[must_use]
+    pub fn offset(&self, point: (usize, usize), d: (isize, isize)) -> Option<(usize, usize)> {
+        self.offset_layers(point, d, 0)
+    }
+    /// The cell `(dx, dy)` away from `(x, y)` and `dz` layers down, like [`Board::offset`]
+    #[must_use]
+    pub fn offset_layers(
+        &self,
+        (x, y): (usize, usize),
+        (dx, dy): (isize, isize),
+        dz: isize,
+    ) -> Option<(usize, usize)> {
+        let (width, height) = (self.dim().0, self.layer_height());
+        let layer = (y / height)
+            .checked_add_signed(dz)
+            .filter(|&layer| layer < self.layers)?;
+        let y = y % height;
+        let point = if self.wrap {
+            (
+                (x + width).checked_add_signed(dx)? % width,
+                (y + height).checked_add_signed(dy)? % height,
+            )
+        } else {
+            let point = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            (point.0 < width && point.1 < height).then_some(point)?
+        };
+        let point = (point.0, point.1 + layer * height);
+        (!matches!(self[point], Cell::Void)).then_some(point)
+    }
+    /// Check whether assigning a particular value to a cell would violate any existing discovered cells
+    #[must_use]
+    pub fn assignment_is_legal(&self, x: usize, y: usize, value: bool) -> bool {
+        let new_value = i16::from(value);
+        let current_value = self[(x, y)].bomb_count();
+        self.neighbors(x, y)
+            .filter_map(|(x, y, c)| {
+                if let Cell::Discovered(Some(n)) = c {
+                    Some((x, y, *n))
+                } else {
+                    None
+                }
+            })
+            .all(|(x, y, wants_bombs)| {
+                let range = self.count_neighboring_bombs(x, y);
+                let range = (range.start() - current_value.start() + new_value)
+                    ..=(range.end() - current_value.end() + new_value);
+                range.contains(&wants_bombs)
+            })
+    }
+    /// Count the number of bombs neighboring a cell
+    #[must_use]
+    pub fn count_neighboring_bombs(&self, x: usize, y: usize) -> RangeInclusive<i16> {
+        self.neighbors(x, y)
+            .map(|(_, _, c)| c.bomb_count())
+            .fold(0..=0, |acc, el| {
+                (acc.start() + el.start())..=(acc.end() + el.end())
+            })
+    }
+    /// Clear a cell, returning false if this cell was a bomb
+    pub fn clear_cell(&mut self, x: usize, y: usize) -> bool {
+        let c = self[(x, y)];
+
+        match c {
+            Cell::Quantum(Some(false)) | Cell::Concrete(0) => {
+                self[(x, y)] = Cell::Discovered(None);
+                true
+            }
+            Cell::Discovered(_) => true,
+            _ => false,
+        }
+    }
+    /// Open `(x, y)` and, as in classic minesweeper, everything zeros open after it, in one pass.
+    /// Returns the cells it opened, nearest first, for animating. Only cells whose number is
+    /// already settled open, so in bastard mode the rest is left to collapses.
+    pub fn reveal_cascade(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut opened = Vec::new();
+        let mut queue = VecDeque::from([(x, y)]);
+        while let Some((x, y)) = queue.pop_front() {
+            if !matches!(self[(x, y)], Cell::Quantum(Some(false)) | Cell::Concrete(0)) {
+                continue;
+            }
+            let range = self.count_neighboring_bombs(x, y);
+            if range.start() != range.end() {
+                continue;
+            }
+            let n = *range.start();
+            self[(x, y)] = Cell::Discovered(Some(n));
+            opened.push((x, y));
+            // A mine and an anti-mine cancel out, so a zero only opens what's around it when
+            // there's nothing there
+            if n == 0
+                && self
+                    .neighbors(x, y)
+                    .all(|(_, _, c)| c.bomb_count() == (0..=0))
+            {
+                queue.extend(self.neighbors(x, y).map(|(x, y, _)| (x, y)));
+            }
+        }
+        opened
+    }
+    /// One line of text per row: `?` undecided, `q`/`Q` decided safe/bomb, `.`/`*` concrete
+    /// safe/bomb, `a` to `h` for concrete cells of 2 to 9 bombs, `!` for anti-mines, digits
+    /// for revealed numbers, `z` down to `r` for -1 to -9 and `-` for revealed but not yet
+    /// counted
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let (width, height) = self.dim();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| match self[(x, y)] {
+                        Cell::Quantum(None) => '?',
+                        Cell::Quantum(Some(false)) => 'q',
+                        Cell::Quantum(Some(true)) => 'Q',
+                        Cell::Concrete(0) => '.',
+                        Cell::Concrete(1) => '*',
+                        Cell::Concrete(..=-1) => '!',
+                        Cell::Concrete(n) => char::from(b'a' + n.unsigned_abs() - 2),
+                        Cell::Discovered(Some(n)) => digit(n),
+                        Cell::Discovered(None) => '-',
+                        Cell::Void => '_',
+                    })
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    /// The board under every rotation and reflection, starting with itself
+    #[must_use]
+    pub fn symmetries(&self) -> Vec<Self> {
+        let mut boards = Vec::new();
+        // Turning a stack of layers on its side would mix the layers up
+        let views = if self.layers == 1 {
+            vec![self.cells.view(), self.cells.t()]
+        } else {
+            vec![self.cells.view()]
+        };
+        for view in views {
+            for (flip_x, flip_y) in [(false, false), (true, false), (false, true), (true, true)] {
+                let mut view = view;
+                if flip_x {
+                    view.invert_axis(Axis(0));
+                }
+                if flip_y {
+                    view.invert_axis(Axis(1));
+                }
+                boards.push(Self {
+                    cells: view.to_owned(),
+                    wrap: self.wrap,
+                    neighborhood: self.neighborhood,
+                    layers: self.layers,
+                });
+            }
+        }
+        boards
+    }
+
+    /// The same board for every rotation and reflection of this one, so boards can be
+    /// compared up to symmetry
+    #[must_use]
+    pub fn canonical_form(&self) -> Self {
+        self.symmetries()
+            .into_iter()
+            .min_by_key(|b| (b.dim(), b.to_text()))
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// Read back the output of [`Board::to_text`]
+    #[must_use]
+    pub fn from_text(text: &str) -> Option<Self> {
+        let rows = text.lines().collect_vec();
+        let width = rows.first()?.chars().count();
+        let mut board = Board::new(width, rows.len());
+        for (y, row) in rows.into_iter().enumerate() {
+            if row.chars().count() != width {
+                return None;
+            }
+            for (x, c) in row.chars().enumerate() {
+                board[(x, y)] = match c {
+                    '?' => Cell::Quantum(None),
+                    'q' => Cell::Quantum(Some(false)),
+                    'Q' => Cell::Quantum(Some(true)),
+                    '.' => Cell::Concrete(0),
+                    '*' => Cell::Concrete(1),
+                    #[allow(clippy::cast_possible_truncation)]
+                    'a'..='h' => Cell::Concrete((c as u8 - b'a' + 2).cast_signed()),
+                    '!' => Cell::Concrete(-1),
+                    #[allow(clippy::cast_possible_truncation)]
+                    'r'..='z' => Cell::Discovered(Some(i16::from(c as u8) - i16::from(b'z') - 1)),
+                    '-' => Cell::Discovered(None),
+                    '_' => Cell::Void,
+                    #[allow(clippy::cast_possible_truncation)]
+                    '0'..='8' => Cell::Discovered(Some(c.to_digit(10)? as i16)),
+                    _ => return None,
+                };
+            }
+        }
+        Some(board)
+    }
+
+    /// A board in the shape `stencil` draws, one row per line: `.` and spaces are holes and
+    /// anything else is a cell. Short lines end in holes. `None` if there are no cells.
+    #[must_use]
+    pub fn from_stencil(stencil: &str) -> Option<Self> {
+        let rows = stencil.trim_end().lines().collect_vec();
+        let width = rows.iter().map(|row| row.chars().count()).max()?;
+        let mut board = Board::new(width, rows.len());
+        board.fill(Cell::Void);
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if !matches!(c, '.' | ' ') {
+                    board[(x, y)] = Cell::Quantum(None);
+                }
+            }
+        }
+        let playable = board.points().next().is_some();
+        playable.then_some(board)
+    }
+
+    /// How likely each hidden cell is to be a bomb, over every placement of `bombs` bombs that
+    /// fits the revealed numbers. Revealed cells are 0. Anything more, like which cells are
+    /// certain, is on [`solver::Solver`].
+    #[must_use]
+    pub fn bomb_probabilities(&self, bombs: usize) -> Array2<f64> {
+        solver::Solver::new(self, bombs).probabilities().clone()
+    }
+
+    /// Openings, islands and the like, for a board whose bombs are all placed
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn statistics(&self) -> BoardStatistics {
+        let bombs = self.points().filter(|p| self[*p].is_bomb()).collect_vec();
+        let zero = |(x, y): (usize, usize)| {
+            !self[(x, y)].is_bomb() && !self.neighbors(x, y).any(|(_, _, c)| c.is_bomb())
+        };
+        // Cells connected to `start` through cells that pass `include`
+        let flood = |start: (usize, usize), include: &dyn Fn((usize, usize)) -> bool| {
+            let mut seen = HashSet::from([start]);
+            let mut queue = vec![start];
+            while let Some((x, y)) = queue.pop() {
+                for (nx, ny, _) in self.neighbors(x, y) {
+                    if include((nx, ny)) && seen.insert((nx, ny)) {
+                        queue.push((nx, ny));
+                    }
+                }
+            }
+            seen
+        };
+
+        let mut statistics = BoardStatistics::default();
+        let mut revealed = HashSet::new();
+        let mut start = None;
+        for p in self.points() {
+            if !zero(p) || revealed.contains(&p) {
+                continue;
+            }
+            let opening = flood(p, &zero);
+            let cells = opening
+                .iter()
+                .flat_map(|(x, y)| self.neighbors(*x, *y).map(|(x, y, _)| (x, y)))
+                .chain(opening.iter().copied())
+                .collect::<HashSet<_>>();
+            statistics.openings += 1;
+            if cells.len() > statistics.largest_opening {
+                statistics.largest_opening = cells.len();
+                start = Some(p);
+            }
+            revealed.extend(cells);
+        }
+        let hidden = |p: (usize, usize)| !self[p].is_bomb() && !revealed.contains(&p);
+        let mut islanded = HashSet::new();
+        for p in self.points() {
+            if hidden(p) && !islanded.contains(&p) {
+                statistics.islands += 1;
+                islanded.extend(flood(p, &hidden));
+            }
+        }
+        statistics.three_bv = statistics.openings + islanded.len();
+
+        if !bombs.is_empty() {
+            // Bombs missing some neighbors, to the edge or a hole
+            let full = self.kernel().count();
+            let edge = bombs
+                .iter()
+                .filter(|(x, y)| self.neighbors(*x, *y).count() < full)
+                .count();
+            statistics.edge_mines = edge as f64 / bombs.len() as f64;
+        }
+        if let Some(start) = start.or_else(|| self.points().find(|p| !self[*p].is_bomb())) {
+            statistics.fifty_fifties = solver::fifty_fifties(self, bombs.len(), start);
+        }
+        statistics
+    }
+
+    /// Find the values for all discovered cells
+    #[must_use]
+    pub fn find_discovered_counts(&self) -> Vec<((usize, usize), i16)> {
+        let (width, height) = self.dim();
+        (0..width)
+            .cartesian_product(0..height)
+            .filter_map(|(x, y)| {
+                if !matches!(self[(x, y)], Cell::Discovered(None)) {
+                    return None;
+                }
+                let range = self.count_neighboring_bombs(x, y);
+                debug_assert_eq!(range.start(), range.end());
+                Some(((x, y), *range.start()))
+            })
+            .collect()
+    }
+    /// Fill in discovered cells with their counts. Numbers already there stay as they are
+    /// when a lazy collapse has left the cells around them undecided.
+    pub fn fill_discovered(&mut self) {
+        let (width, height) = self.dim();
+        (0..width)
+            .cartesian_product(0..height)
+            .filter_map(|(x, y)| {
+                let known = match self[(x, y)] {
+                    Cell::Discovered(n) => n.is_some(),
+                    _ => return None,
+                };
+                let range = self.count_neighboring_bombs(x, y);
+                if known && range.start() != range.end() {
+                    return None;
+                }
+                debug_assert_eq!(range.start(), range.end());
+                Some(((x, y), *range.start()))
+            })
+            .collect_vec()
+            .into_iter()
+            .for_each(|(c, v)| {
+                if let Cell::Discovered(Some(r)) = self[c] {
+                    debug_assert_eq!(v, r);
+                }
+                self[c] = Cell::Discovered(Some(v));
+            });
+    }
+    /// Render the discovered-number constraints and the frontier cells they bind as a Graphviz graph
+    #[must_use]
+    pub fn constraint_graph_dot(&self) -> String {
+        let mut lines = vec!["graph constraints {".to_string()];
+        let mut frontier = HashSet::new();
+        for (x, y) in self.points() {
+            let Cell::Discovered(Some(n)) = self[(x, y)] else {
+                continue;
+            };
+            let unknowns = self
+                .neighbors(x, y)
+                .filter(|(_, _, c)| !matches!(c, Cell::Discovered(_)))
+                .map(|(x, y, _)| (x, y))
+                .collect_vec();
+            if unknowns.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "  \"{x},{y}\" [shape=box, label=\"{n}\\n({x}, {y})\"];"
+            ));
+            for (nx, ny) in unknowns {
+                lines.push(format!("  \"{x},{y}\" -- \"{nx},{ny}\";"));
+                frontier.insert((nx, ny));
+            }
+        }
+        for (x, y) in frontier.into_iter().sorted() {
+            let state = match self[(x, y)] {
+                Cell::Quantum(None) => "?",
+                Cell::Quantum(Some(true)) => "q*",
+                Cell::Quantum(Some(false)) => "q.",
+                Cell::Concrete(..=-1 | 1..) => "*",
+                Cell::Concrete(0) | Cell::Discovered(_) | Cell::Void => ".",
+            };
+            lines.push(format!("  \"{x},{y}\" [label=\"{state}\\n({x}, {y})\"];"));
+        }
+        lines.push("}\n".to_string());
+        lines.join("\n")
+    }
+    /// Collapse all quantum cells
+    pub fn collapse(
+        &mut self,
+        max_bombs: usize,
+        allowed_range: Option<Range<(usize, usize)>>,
+    ) -> CollapseTrace {
+        self.collapse_with(&CollapseConfig::default(), max_bombs, allowed_range)
+    }
+
+    /// [`Board::collapse`] with custom limits
+    pub fn collapse_with(
+        &mut self,
+        config: &CollapseConfig,
+        max_bombs: usize,
+        allowed_range: Option<Range<(usize, usize)>>,
+    ) -> CollapseTrace {
+        self.collapse_with_rng(config, max_bombs, allowed_range, &mut rng())
+    }
+
+    /// [`Board::collapse_with`] taking its randomness from `rng`. The same `rng` state gives
+    /// the same collapse, as long as it doesn't run out of `config.signature_time`; see
+    /// [`CollapseConfig::reproducible`].
+    pub fn collapse_with_rng(
+        &mut self,
+        config: &CollapseConfig,
+        max_bombs: usize,
+        allowed_range: Option<Range<(usize, usize)>>,
+        rng: &mut impl Rng,
+    ) -> CollapseTrace {
+        self.collapse_observed(config, max_bombs, allowed_range, rng, &mut ())
+    }
+
+    /// [`Board::collapse_with_rng`], telling `observer` how it's going.
+    /// Stops early once `observer` asks to cancel, leaving the board half collapsed.
+    pub fn collapse_observed(
+        &mut self,
+        config: &CollapseConfig,
+        max_bombs: usize,
+        allowed_range: Option<Range<(usize, usize)>>,
+        rng: &mut impl Rng,
+        observer: &mut impl CollapseObserver,
+    ) -> CollapseTrace {
+        let mut policy = config.policy;
+        self.collapse_by(config, max_bombs, allowed_range, rng, observer, &mut policy)
+    }
+
+    /// [`Board::collapse_observed`], keeping whichever state `policy` chooses instead of
+    /// following `config.policy`
+    #[allow(clippy::too_many_lines)]
+    pub fn collapse_by(
+        &mut self,
+        config: &CollapseConfig,
+        mut max_bombs: usize,
+        allowed_range: Option<Range<(usize, usize)>>,
+        rng: &mut impl Rng,
+        observer: &mut impl CollapseObserver,
+        policy: &mut impl CollapsePolicy,
+    ) -> CollapseTrace {
+        let mut trace = CollapseTrace::default();
+        let (width, height) = self.dim();
+        // Undecided cells anywhere still need their share of the bombs, so lazy collapses
+        // take in the whole frontier
+        let allowed_range = allowed_range
+            .filter(|_| !config.lazy)
+            .unwrap_or((0, 0)..(width, height));
+        let mut quantum_cells = (0..width)
+            .cartesian_product(0..height)
+            .filter(|(x, y)| {
+                matches!(self[(*x, *y)], Cell::Quantum(_))
+                    && self
+                        .neighbors(*x, *y)
+                        .any(|(_, _, n)| matches!(n, Cell::Discovered(_)))
+            })
+            .collect_vec();
+        {
+            let mut true_check_board = self.clone();
+            true_check_board.iter_mut().for_each(|c| {
+                if matches!(c, Cell::Quantum(Some(false))) {
+                    *c = Cell::Quantum(None);
+                }
+            });
+            quantum_cells.retain(|(x, y)| match self[(*x, *y)] {
+                Cell::Quantum(Some(true)) => true_check_board.assignment_is_legal(*x, *y, false),
+                Cell::Quantum(_) => true,
+                _ => false,
+            });
+            let mut false_check_board = self.clone();
+            false_check_board.iter_mut().for_each(|c| {
+                if matches!(c, Cell::Quantum(Some(true))) {
+                    *c = Cell::Quantum(None);
+                }
+            });
+            quantum_cells.retain(|(x, y)| match self[(*x, *y)] {
+                Cell::Quantum(Some(false)) => false_check_board.assignment_is_legal(*x, *y, true),
+                Cell::Quantum(_) => true,
+                _ => false,
+            });
+
+            quantum_cells.retain(|(x, y)| {
+                (allowed_range.start.0..allowed_range.end.0).contains(x)
+                    && (allowed_range.start.1..allowed_range.end.1).contains(y)
+            });
+        }
+
+        for p in &quantum_cells {
+            self[*p] = Cell::Quantum(None);
+        }
+
+        max_bombs = max_bombs.saturating_sub(
+            self.iter()
+                .map(|c| usize::from(c.bomb_count().start().unsigned_abs()))
+                .sum(),
+        );
+
+        trace.max_bombs = max_bombs;
+        trace.frontier.clone_from(&quantum_cells);
+        observer.on_start(quantum_cells.len(), max_bombs);
+
+        if max_bombs == 0 {
+            // Out of bombs, so everything is safe
+            for c in &quantum_cells {
+                self[*c] = Cell::Quantum(Some(false));
+            }
+            trace.chosen = vec![false; quantum_cells.len()];
+            trace.outcome = CollapseOutcome::OutOfBombs;
+            observer.on_done(&trace);
+            return trace;
+        }
+        if quantum_cells.is_empty() {
+            observer.on_done(&trace);
+            return trace;
+        }
+        quantum_cells.sort_by_key(|(x, y)| x + y);
+        trace.frontier.clone_from(&quantum_cells);
+        for c in &quantum_cells {
+            self[*c] = Cell::Quantum(None);
+        }
+        let began = Instant::now();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let frontier = Frontier::new(
+            self,
+            &quantum_cells,
+            max_bombs,
+            config.enumeration_budget as usize,
+        );
+        trace.approximate = frontier.is_none();
+        let states = if let Some(mut frontier) = frontier {
+            let count = frontier.count();
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            {
+                trace.states = count as usize;
+            }
+            if trace.states == 0 {
+                Vec::new()
+            } else {
+                (0..trace.states.min(config.sample_count))
+                    .take_while(|_| !observer.cancelled())
+                    .map(|_| frontier.sample(rng))
+                    .collect_vec()
+            }
+        } else {
+            let states = self.sample_states(&quantum_cells, max_bombs, config, rng, || {
+                observer.cancelled()
+            });
+            trace.states = states.len();
+            states
+        };
+        let states = config.spread.resample(self, &quantum_cells, states, rng);
+        trace.enumerate_time = began.elapsed();
+        observer.on_states_found(trace.states, trace.approximate);
+        if !states.is_empty() {
+            let chosen = policy.choose(
+                &states,
+                self,
+                &mut Choosing {
+                    frontier: &quantum_cells,
+                    config,
+                    rng,
+                    observer,
+                    trace: &mut trace,
+                },
+            );
+            let Some(quanta) = chosen.and_then(|i| states.get(i)) else {
+                trace.outcome = CollapseOutcome::Cancelled;
+                observer.on_done(&trace);
+                return trace;
+            };
+            quantum_cells
+                .iter()
+                .zip(quanta.iter())
+                .for_each(|(c, v)| self[*c] = Cell::Quantum(Some(*v)));
+            if config.lazy {
+                self.unsettle(&quantum_cells, &states, quanta, config);
+            }
+            trace.chosen.clone_from(quanta);
+            trace.outcome = CollapseOutcome::Chosen;
+        } else if observer.cancelled() {
+            trace.outcome = CollapseOutcome::Cancelled;
+        } else {
+            trace.outcome = CollapseOutcome::DeadEnd;
+        }
+        observer.on_done(&trace);
+        trace
+    }
+    /// Write down the numbers `chosen` gives, then put the cells of `frontier` that `states`
+    /// giving those same numbers disagree on back into superposition
+    fn unsettle(
+        &mut self,
+        frontier: &[(usize, usize)],
+        states: &[State],
+        chosen: &State,
+        config: &CollapseConfig,
+    ) {
+        let numbers = self.find_discovered_counts();
+        let agreeing = config.pool().install(|| {
+            states
+                .par_iter()
+                .map_init(
+                    || self.clone(),
+                    |board, state| {
+                        for (c, b) in frontier.iter().zip(state) {
+                            board[*c] = Cell::Quantum(Some(*b));
+                        }
+                        (board.find_discovered_counts() == numbers).then_some(state)
+                    },
+                )
+                .flatten()
+                .collect::<Vec<_>>()
+        });
+        self.fill_discovered();
+        for (i, c) in frontier.iter().enumerate() {
+            if agreeing.iter().any(|state| state[i] != chosen[i]) {
+                self[*c] = Cell::Quantum(None);
+            }
+        }
+    }
+    /// Make `cell` a bomb or not, as `bomb` says, collapsing the whole frontier again to fit.
+    /// Returns false, leaving the board alone, if no placement of bombs allows it.
+    pub fn pin(
+        &mut self,
+        (x, y): (usize, usize),
+        bomb: bool,
+        config: &CollapseConfig,
+        max_bombs: usize,
+        rng: &mut impl Rng,
+    ) -> bool {
+        match self[(x, y)] {
+            Cell::Quantum(Some(b)) if b == bomb => return true,
+            Cell::Quantum(_) => {}
+            _ => return false,
+        }
+        if !self
+            .neighbors(x, y)
+            .any(|(_, _, c)| matches!(c, Cell::Discovered(_)))
+        {
+            // No number sees it, so it can be anything
+            self[(x, y)] = Cell::Quantum(Some(bomb));
+            return true;
+        }
+        // Start over from every layout the numbers allow, not just ones close to this one, with
+        // the cell held still as if it were concrete
+        let mut pinned = self.clone();
+        for c in pinned.iter_mut() {
+            if matches!(c, Cell::Quantum(_)) {
+                *c = Cell::Quantum(None);
+            }
+        }
+        pinned[(x, y)] = Cell::Concrete(i8::from(bomb));
+        let trace = pinned.collapse_with_rng(config, max_bombs, None, rng);
+        pinned[(x, y)] = Cell::Quantum(Some(bomb));
+        let fits = matches!(
+            trace.outcome,
+            CollapseOutcome::Chosen
+                | CollapseOutcome::OutOfBombs
+                | CollapseOutcome::NothingToAssign
+        );
+        if fits {
+            *self = pinned;
+        }
+        fits
+    }
+    /// Estimate how many consistent assignments with at most `max_bombs` bombs exist for `cells`,
+    /// which must be unassigned. Exact when no two discovered numbers share an unassigned neighbor.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn estimate_state_count(&self, cells: &[(usize, usize)], max_bombs: usize) -> f64 {
+        let cell_set = cells.iter().copied().collect::<HashSet<_>>();
+        let constraints = cells
+            .iter()
+            .flat_map(|(x, y)| self.neighbors(*x, *y))
+            .filter_map(|(x, y, c)| match c {
+                Cell::Discovered(Some(n)) => Some(((x, y), *n)),
+                _ => None,
+            })
+            .unique()
+            .map(|((x, y), n)| {
+                let members = self
+                    .neighbors(x, y)
+                    .map(|(x, y, _)| (x, y))
+                    .filter(|p| cell_set.contains(p))
+                    .collect_vec();
+                let wanted = n - *self.count_neighboring_bombs(x, y).start();
+                (members, usize::try_from(wanted).unwrap_or(0))
+            })
+            .collect_vec();
+        let mut memberships = HashMap::<_, usize>::new();
+        for p in constraints.iter().flat_map(|(members, _)| members) {
+            *memberships.entry(*p).or_default() += 1;
+        }
+        // Each constraint contributes its choices, discounted by how much it shares its cells
+        let constrained = constraints
+            .iter()
+            .map(|(members, wanted)| {
+                let share = members
+                    .iter()
+                    .map(|p| 1. / memberships[p] as f64)
+                    .sum::<f64>()
+                    / members.len() as f64;
+                ln_choose(members.len(), *wanted) * share
+            })
+            .sum::<f64>();
+        let free = cells.len() - memberships.len();
+        let free = (0..=free.min(max_bombs))
+            .map(|k| ln_choose(free, k).exp())
+            .sum::<f64>()
+            .ln();
+        (constrained + free).exp()
+    }
+    /// Check whether collapsing `cells` would take too long to count
+    #[must_use]
+    pub fn is_pathological(&self, cells: &[(usize, usize)], max_bombs: usize) -> bool {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Frontier::new(self, cells, max_bombs, ENUMERATION_BUDGET as usize).is_none()
+    }
+    /// Randomly search for consistent assignments of `cells`, for when there are too many to enumerate.
+    /// Stops searching once `cancelled` is true.
+    fn sample_states(
+        &self,
+        cells: &[(usize, usize)],
+        max_bombs: usize,
+        config: &CollapseConfig,
+        rng: &mut impl Rng,
+        cancelled: impl Fn() -> bool,
+    ) -> Vec<Vec<bool>> {
+        let mut board = self.clone();
+        let mut states = vec![];
+        for _ in 0..config.sample_count {
+            if cancelled() {
+                break;
+            }
+            let mut state = vec![];
+            let mut steps = config.sample_step_limit;
+            if board.sample_inner(cells, max_bombs, rng, &mut steps, &mut state) {
+                states.push(state);
+            }
+            for c in cells {
+                board[*c] = Cell::Quantum(None);
+            }
+        }
+        states
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn sample_inner(
+        &mut self,
+        cells: &[(usize, usize)],
+        max_bombs: usize,
+        rng: &mut impl Rng,
+        steps: &mut usize,
+        state: &mut Vec<bool>,
+    ) -> bool {
+        let Some(&(x, y)) = cells.get(state.len()) else {
+            return true;
+        };
+        if *steps == 0 {
+            return false;
+        }
+        *steps -= 1;
+        // Spread the bombs out over the remaining cells rather than front-loading them
+        let density = max_bombs as f64 / (cells.len() - state.len()) as f64;
+        let first = rng.random_bool(density.min(0.5));
+        for value in [first, !first] {
+            if (value && max_bombs == 0) || !self.assignment_is_legal(x, y, value) {
+                continue;
+            }
+            self[(x, y)] = Cell::Quantum(Some(value));
+            state.push(value);
+            if self.sample_inner(cells, max_bombs - usize::from(value), rng, steps, state) {
+                return true;
+            }
+            state.pop();
+        }
+        self[(x, y)] = Cell::Quantum(None);
+        false
+    }
+}
+
+/// Hears how a collapse is going, to show progress. Every method does nothing by default.
+pub trait CollapseObserver {
+    /// About to assign `cells` frontier cells, with at most `max_bombs` bombs between them
+    fn on_start(&mut self, _cells: usize, _max_bombs: usize) {}
+    /// There are `states` consistent assignments, or that many were found if `approximate`
+    fn on_states_found(&mut self, _states: usize, _approximate: bool) {}
+    /// `done` of the `total` drawn assignments have had their numbers worked out
+    fn on_progress(&mut self, _done: usize, _total: usize) {}
+    /// The collapse is over
+    fn on_done(&mut self, _trace: &CollapseTrace) {}
+    /// Checked every so often; once this is true the collapse gives up
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Ignores everything
+impl CollapseObserver for () {}
+
+/// A flag for giving up on a collapse from another thread
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl CollapseObserver for CancellationToken {
+    fn cancelled(&self) -> bool {
+        self.is_cancelled()
+    }
+}
+
+/// How a `collapse` ended
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CollapseOutcome {
+    /// No hidden cell next to a number could change
+    #[default]
+    NothingToAssign,
+    /// Out of bombs, so every cell on the frontier became safe
+    OutOfBombs,
+    /// A set of numbers was picked by the [`Policy`]
+    Chosen,
+    /// No placement of bombs fit the numbers, so the frontier was left unassigned
+    DeadEnd,
+    /// The observer cancelled it part way through
+    Cancelled,
+}
+
+/// Record of the decisions made by a single `collapse`, for offline analysis
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollapseTrace {
+    pub outcome: CollapseOutcome,
+    /// The built-in policy that chose, if one did
+    pub policy: Option<Policy>,
+    /// Cells that were (re)assigned, in assignment order
+    pub frontier: Vec<(usize, usize)>,
+    /// Bombs left to place after accounting for already-assigned ones
+    pub max_bombs: usize,
+    /// Whether states were sampled rather than enumerated
+    pub approximate: bool,
+    /// Number of consistent states found
+    pub states: usize,
+    /// How many sampled states produced each distinct set of numbers, largest first
+    pub signature_counts: Vec<usize>,
+    /// How many of those gave the numbers that were kept, 0 if the policy didn't count them
+    pub chosen_states: usize,
+    /// The assignment chosen for each frontier cell
+    pub chosen: Vec<bool>,
+    #[cfg_attr(feature = "serde", serde(rename = "enumerate_secs", with = "secs"))]
+    pub enumerate_time: Duration,
+    #[cfg_attr(feature = "serde", serde(rename = "sample_secs", with = "secs"))]
+    pub sample_time: Duration,
+}
+
+/// Durations as a number of seconds, as traces are written
+#[cfg(feature = "serde")]
+mod secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Duration::try_from_secs_f64(f64::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl CollapseTrace {
+    /// How many different sets of numbers the scored states gave
+    #[must_use]
+    pub fn unique_number_sets(&self) -> usize {
+        self.signature_counts.len()
+    }
+
+    /// Time spent finding and scoring states
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.enumerate_time + self.sample_time
+    }
+
+    /// What the collapse did, in words for a new player
+    #[must_use]
+    pub fn explain(&self) -> String {
+        match self.outcome {
+            CollapseOutcome::NothingToAssign => {
+                return "No hidden cell next to your numbers could change.".to_string();
+            }
+            CollapseOutcome::OutOfBombs => {
+                return format!(
+                    "Out of bombs, so all {} hidden cells next to your numbers are safe.",
+                    self.frontier.len()
+                );
+            }
+            CollapseOutcome::DeadEnd => {
+                return "No way of placing bombs fit your numbers, so nothing changed.".to_string();
+            }
+            CollapseOutcome::Cancelled => return "The collapse was aborted.".to_string(),
+            CollapseOutcome::Chosen => {}
+        }
+        let found = format!(
+            "{}{} ways to place bombs around your numbers",
+            if self.approximate { "Sampled " } else { "" },
+            self.states,
+        );
+        let (kept, tells) = match self.policy {
+            Some(Policy::Bastard) => ("most common", "little"),
+            Some(Policy::Angel) => ("rarest", "much"),
+            Some(Policy::Entropy) => ("most uncertain", "little"),
+            Some(Policy::Random) => return format!("{found}. One was kept at random."),
+            None => return format!("{found}. A custom policy kept one."),
+        };
+        format!(
+            "{found}, giving {} different sets of new numbers. \
+             The {kept} set, from {} of them, was kept so your next numbers tell you as {tells} as possible.",
+            self.unique_number_sets(),
+            self.chosen_states,
+        )
+    }
+
+    /// Format as a single line of JSON
+    ///
+    /// # Panics
+    /// Never, since every field has a JSON form
+    #[cfg(feature = "serde")]
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Format as a single line of JSON, the same as serde would
+    #[cfg(not(feature = "serde"))]
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"outcome\":\"{:?}\",\"policy\":{},\"frontier\":[{}],\"max_bombs\":{},\"approximate\":{},\"states\":{},\"signature_counts\":[{}],\"chosen_states\":{},\"chosen\":[{}],\"enumerate_secs\":{},\"sample_secs\":{}}}",
+            self.outcome,
+            self.policy
+                .map_or_else(|| "null".to_string(), |p| format!("\"{p:?}\"")),
+            self.frontier
+                .iter()
+                .map(|(x, y)| format!("[{x},{y}]"))
+                .join(","),
+            self.max_bombs,
+            self.approximate,
+            self.states,
+            self.signature_counts.iter().join(","),
+            self.chosen_states,
+            self.chosen.iter().join(","),
+            self.enumerate_time.as_secs_f64(),
+            self.sample_time.as_secs_f64(),
+        )
+    }
+}
+
+/// Where a [`Game`] stands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameState {
+    Playing,
+    Won,
+    /// Lost by revealing this cell
+    Lost((usize, usize)),
+}
+
+/// A move the numbers prove is right, or the best guess once they prove nothing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// This cell can't be a bomb
+    Reveal((usize, usize)),
+    /// This cell must be a bomb and isn't flagged yet
+    Flag((usize, usize)),
+    /// Nothing is certain, and this cell is the one to guess
+    Guess((usize, usize)),
+}
+
+/// The rules of minesweeper, bastard or not, for any frontend
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Game {
+    pub board: Board,
+    pub flags: HashSet<(usize, usize)>,
+    pub max_bombs: usize,
+    pub bastard: bool,
+    /// Nothing has been revealed yet, so the first reveal opens up an area
+    pub first_click: bool,
+    pub win: bool,
+    pub lose: Option<(usize, usize)>,
+    /// Limits for the collapses [`Game::reveal`] runs
+    pub config: CollapseConfig,
+    /// What [`Game::with_seed`] was given
+    pub seed: Option<u64>,
+    /// Where collapses get their randomness in a seeded game
+    pub rng: Option<StdRng>,
+    /// Whether reveals can be taken back with [`Game::undo`]
+    pub undo_enabled: bool,
+    /// Kaboom rules for quantum games: a revealed cell is a bomb only if every layout makes it
+    /// one, unless the player passed up a cell the numbers prove safe, in which case it's a bomb
+    /// whenever some layout allows it. Chords play by the usual rules.
+    pub kaboom: bool,
+    /// Sudden collapses: once the player goes this long without a move, the bastard starts
+    /// every hidden cell over, see [`Game::idle_collapse`]
+    pub sudden_collapse: Option<Duration>,
+    /// When the player last moved, or last stopped waiting on a collapse
+    last_move: Option<Instant>,
+    /// What the numbers mean and how the game is won. Swapping it for one that lies less
+    /// mid-game keeps the lies already told.
+    pub ruleset: Arc<dyn Ruleset>,
+    /// How far off each counted number is shown, see [`Game::shown`]
+    pub lies: HashMap<(usize, usize), i16>,
+    /// How things stood before each reveal, latest last
+    undo: Vec<Position>,
+    /// Positions taken back by [`Game::undo`], latest last
+    redo: Vec<Position>,
+    /// What the game's variant adds to the rules, in the order they hear about events
+    pub rules: Vec<Arc<dyn Rule>>,
+    /// Time spent on finished collapses, which the player couldn't play through
+    pub thinking: Duration,
+    /// When the collapse running now started
+    thinking_since: Option<Instant>,
+}
+
+/// Everything a reveal can change, to go back to
+#[derive(Clone, Debug)]
+struct Position {
+    board: Board,
+    max_bombs: usize,
+    flags: HashSet<(usize, usize)>,
+    first_click: bool,
+    win: bool,
+    lose: Option<(usize, usize)>,
+    rng: Option<StdRng>,
+    lies: HashMap<(usize, usize), i16>,
+}
+
+impl Game {
+    /// A game on `board`, which should be all quantum cells in bastard mode and all
+    /// concrete cells otherwise
+    #[must_use]
+    pub fn new(board: Board, max_bombs: usize, bastard: bool) -> Self {
+        let neighborhood = board.neighborhood;
+        Self {
+            board,
+            flags: HashSet::new(),
+            max_bombs,
+            bastard,
+            first_click: true,
+            win: false,
+            lose: None,
+            config: CollapseConfig::default(),
+            seed: None,
+            rng: None,
+            undo_enabled: true,
+            kaboom: false,
+            sudden_collapse: None,
+            last_move: None,
+            ruleset: Arc::new(rules::Classic { neighborhood }),
+            lies: HashMap::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            rules: Vec::new(),
+            thinking: Duration::ZERO,
+            thinking_since: None,
+        }
+    }
+
+    /// A [`Reveal`] from this game started running, so the player has to wait for it
+    pub fn collapse_started(&mut self) {
+        self.thinking_since.get_or_insert_with(Instant::now);
+    }
+
+    /// The running [`Reveal`] finished or was given up
+    pub fn collapse_finished(&mut self) {
+        if let Some(since) = self.thinking_since.take() {
+            self.thinking += since.elapsed();
+        }
+        self.last_move = Some(Instant::now());
+    }
+
+    /// Time spent waiting on collapses, including one still running
+    #[must_use]
+    pub fn thinking_time(&self) -> Duration {
+        self.thinking
+            + self
+                .thinking_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// How much longer the player can idle before a sudden collapse, if one is coming.
+    /// The timer starts with the first move and stops while a collapse runs.
+    #[must_use]
+    pub fn idle_left(&self) -> Option<Duration> {
+        let limit = self.sudden_collapse.filter(|_| {
+            self.bastard && self.state() == GameState::Playing && self.thinking_since.is_none()
+        })?;
+        Some(limit.saturating_sub(self.last_move?.elapsed()))
+    }
+
+    /// Once the player has idled for [`Game::sudden_collapse`], forget which hidden cells are
+    /// bombs and collapse them again, as far as the numbers allow. Flags can end up on safe
+    /// cells, so the player has to work the board out again.
+    pub fn idle_collapse(&mut self) -> Option<Reveal> {
+        if !self.idle_left()?.is_zero() {
+            return None;
+        }
+        for cell in self.board.iter_mut() {
+            if let Cell::Quantum(Some(_)) = cell {
+                *cell = Cell::Quantum(None);
+            }
+        }
+        self.last_move = Some(Instant::now());
+        let mut job = self.job(self.max_bombs, (0, 0)..self.board.dim());
+        job.reshuffle = true;
+        Some(job)
+    }
+
+    /// Tell every rule about an event with `hook`
+    fn apply_rules(&mut self, hook: impl Fn(&dyn Rule, &mut Self)) {
+        for rule in self.rules.clone() {
+            hook(rule.as_ref(), self);
+        }
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            board: self.board.clone(),
+            max_bombs: self.max_bombs,
+            flags: self.flags.clone(),
+            first_click: self.first_click,
+            win: self.win,
+            lose: self.lose,
+            rng: self.rng.clone(),
+            lies: self.lies.clone(),
+        }
+    }
+
+    fn go_to(&mut self, position: Position) {
+        self.board = position.board;
+        self.max_bombs = position.max_bombs;
+        self.flags = position.flags;
+        self.first_click = position.first_click;
+        self.win = position.win;
+        self.lose = position.lose;
+        self.rng = position.rng;
+        self.lies = position.lies;
+    }
+
+    /// Take back the last reveal, along with every collapse it took.
+    /// Returns false if there's nothing to take back or undo is disabled.
+    /// Don't call this while a [`Reveal`] from [`Game::start_reveal`] is still running.
+    pub fn undo(&mut self) -> bool {
+        if !self.undo_enabled {
+            return false;
+        }
+        let Some(position) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.position());
+        self.go_to(position);
+        true
+    }
+
+    /// Put back the last reveal [`Game::undo`] took back, exactly as it went.
+    /// Returns false if there's nothing to put back.
+    pub fn redo(&mut self) -> bool {
+        if !self.undo_enabled {
+            return false;
+        }
+        let Some(position) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.position());
+        self.go_to(position);
+        true
+    }
+
+    /// How many moves [`Game::undo`] could take back
+    #[must_use]
+    pub fn undo_depth(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Play by `ruleset`, counting numbers over its neighborhood
+    #[must_use]
+    pub fn with_ruleset(mut self, ruleset: Arc<dyn Ruleset>) -> Self {
+        self.board.neighborhood = ruleset.neighborhood();
+        self.ruleset = ruleset;
+        self
+    }
+
+    /// The same game will collapse the same way every time it's played the same way
+    #[must_use]
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            rng: Some(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+
+    /// A job to work out the numbers on the board as it is, with its own share of the seed
+    fn job(&mut self, max_bombs: usize, range: Range<(usize, usize)>) -> Reveal {
+        Reveal {
+            board: self.board.clone(),
+            bastard: self.bastard,
+            max_bombs,
+            // Numbers across a wrapped edge or a long way off are out of the rectangle
+            range: self.board.is_local().then_some(range),
+            reshuffle: false,
+            rng: self
+                .rng
+                .as_mut()
+                .map(|rng| StdRng::seed_from_u64(rng.random())),
+        }
+    }
+
+    #[must_use]
+    pub fn state(&self) -> GameState {
+        match self.lose {
+            Some(cell) => GameState::Lost(cell),
+            None if self.win => GameState::Won,
+            None => GameState::Playing,
+        }
+    }
+
+    /// Reveal a cell and everything around any zeros it uncovers, running every collapse
+    /// that takes. Returns the collapses.
+    pub fn reveal(&mut self, x: usize, y: usize) -> Vec<CollapseTrace> {
+        let job = self.start_reveal(x, y);
+        self.settle(job)
+    }
+
+    /// Chord on a number, as [`Game::start_chord`] describes, running every collapse that
+    /// takes. Returns the collapses.
+    pub fn chord(&mut self, x: usize, y: usize) -> Vec<CollapseTrace> {
+        let job = self.start_chord(x, y);
+        self.settle(job)
+    }
+
+    /// Run `job` and every cascade after it
+    fn settle(&mut self, mut job: Option<Reveal>) -> Vec<CollapseTrace> {
+        let config = self.config;
+        let mut traces = Vec::new();
+        while let Some(reveal) = job {
+            self.collapse_started();
+            let done = reveal.run(&config, &mut ());
+            self.collapse_finished();
+            // Nothing cancels it
+            let Some((board, new)) = done else {
+                break;
+            };
+            self.finish(board);
+            traces.extend(new);
+            job = self.cascade();
+        }
+        traces
+    }
+
+    /// The part of [`Game::reveal`] that's quick: open the cell, losing if it's a bomb.
+    /// Returns the numbers still to work out, to be run with [`Reveal::run`] and handed
+    /// to [`Game::finish`]. Nothing happens to flagged cells or once the game is over.
+    pub fn start_reveal(&mut self, x: usize, y: usize) -> Option<Reveal> {
+        if self.state() != GameState::Playing
+            || self.flags.contains(&(x, y))
+            || matches!(self.board[(x, y)], Cell::Void)
+        {
+            return None;
+        }
+        self.last_move = Some(Instant::now());
+        if self.undo_enabled && matches!(self.board[(x, y)], Cell::Quantum(_) | Cell::Concrete(_)) {
+            self.undo.push(self.position());
+            self.redo.clear();
+        }
+        if self.first_click {
+            if self.bastard {
+                for d in (-2..=2).cartesian_product(-2..=2) {
+                    if let Some(point) = self.board.offset((x, y), d) {
+                        self.board[point] = Cell::Discovered(None);
+                    }
+                }
+            } else {
+                self.board[(x, y)] = Cell::Concrete(0);
+            }
+        }
+        if !self.bastard {
+            // Honest numbers are known up front, so zeros can open everything at once
+            if self.ruleset.cascades() {
+                self.board.reveal_cascade(x, y);
+            }
+        } else if self.kaboom {
+            self.judge((x, y));
+        } else if self.config.lazy {
+            self.decide((x, y));
+        }
+        if !self.board.clear_cell(x, y) {
+            self.lose = Some((x, y));
+            return None;
+        }
+        self.apply_rules(|rule, game| rule.on_reveal(game, (x, y)));
+        if self.lose.is_some() {
+            return None;
+        }
+        let bombs = match self.ruleset.first_click_bombs() {
+            // Go easy on the first click
+            Some(bombs) if self.first_click => bombs,
+            _ => self.max_bombs,
+        };
+        let reveal = self.job(
+            bombs,
+            (x.saturating_sub(5), y.saturating_sub(5))..(x + 5, y + 5),
+        );
+        self.first_click = false;
+        Some(reveal)
+    }
+
+    /// Settle whether `cell` is a bomb by the kaboom rules, before it's opened
+    fn judge(&mut self, cell: (usize, usize)) {
+        if !matches!(self.board[cell], Cell::Quantum(_)) {
+            return;
+        }
+        let solver = solver::Solver::new(&self.board, self.max_bombs);
+        let probability = solver.probability(cell.0, cell.1);
+        let forced = solver.safe_cells().is_empty();
+        let bomb = if forced {
+            probability >= 1.
+        } else {
+            probability > 0.
+        };
+        self.pin(cell, bomb);
+    }
+
+    /// Settle an undecided cell a lazy collapse left next to the numbers, before it's opened,
+    /// making it a bomb as often as the layouts the numbers allow do
+    fn decide(&mut self, cell: (usize, usize)) {
+        let seen = self
+            .board
+            .neighbors(cell.0, cell.1)
+            .any(|(_, _, c)| matches!(c, Cell::Discovered(_)));
+        if !matches!(self.board[cell], Cell::Quantum(None)) || !seen {
+            return;
+        }
+        let probability = solver::Solver::new(&self.board, self.max_bombs)
+            .probability(cell.0, cell.1)
+            .clamp(0., 1.);
+        let bomb = match &mut self.rng {
+            Some(rng) => rng.random_bool(probability),
+            None => rng().random_bool(probability),
+        };
+        self.pin(cell, bomb);
+    }
+
+    /// [`Board::pin`] with this game's limits and randomness
+    fn pin(&mut self, cell: (usize, usize), bomb: bool) {
+        let config = if self.rng.is_some() {
+            self.config.reproducible()
+        } else {
+            self.config
+        };
+        let mut rng = self.rng.as_mut().map_or_else(
+            || StdRng::from_rng(&mut rng()),
+            |rng| StdRng::seed_from_u64(rng.random()),
+        );
+        self.board
+            .pin(cell, bomb, &config, self.max_bombs, &mut rng);
+    }
+
+    /// A safe cell to reveal, or failing that a bomb to flag, as the solver proves from the
+    /// revealed numbers. `None` if only guesses are left.
+    #[must_use]
+    pub fn hint(&self) -> Option<Hint> {
+        if self.state() != GameState::Playing {
+            return None;
+        }
+        let solver = self.solver()?;
+        let unflagged = |p: &(usize, usize)| !self.flags.contains(p);
+        solver
+            .safe_cells()
+            .into_iter()
+            .find(unflagged)
+            .map(Hint::Reveal)
+            .or_else(|| solver.mines().into_iter().find(unflagged).map(Hint::Flag))
+    }
+
+    /// Like [`Game::hint`], but once only guesses are left, the unflagged cell `policy`
+    /// ranks best
+    ///
+    /// # Errors
+    /// If `policy` couldn't rank the cells
+    pub fn hint_or_guess(&self, policy: &dyn guess::GuessPolicy) -> Result<Option<Hint>, String> {
+        if let Some(hint) = self.hint() {
+            return Ok(Some(hint));
+        }
+        if self.state() != GameState::Playing {
+            return Ok(None);
+        }
+        Ok(policy
+            .rank(self)?
+            .into_iter()
+            .find(|p| !self.flags.contains(p))
+            .map(Hint::Guess))
+    }
+
+    /// The cells a chord on `(x, y)` would open: the unflagged hidden neighbors of a number
+    /// with exactly that many flags around it. Empty if it isn't such a number.
+    #[must_use]
+    pub fn chord_cells(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let Some(n) = self.shown((x, y)) else {
+            return Vec::new();
+        };
+        let (flagged, hidden): (Vec<_>, Vec<_>) = self
+            .board
+            .neighbors(x, y)
+            .filter(|(_, _, c)| matches!(c, Cell::Quantum(_) | Cell::Concrete(_)))
+            .map(|(x, y, _)| (x, y))
+            .partition(|p| self.flags.contains(p));
+        if usize::try_from(n).is_ok_and(|n| flagged.len() == n) {
+            hidden
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Like [`Game::start_reveal`] on every cell [`Game::chord_cells`] gives, as one move.
+    /// Stops at the first bomb, losing the game.
+    pub fn start_chord(&mut self, x: usize, y: usize) -> Option<Reveal> {
+        let cells = self.chord_cells(x, y);
+        if self.state() != GameState::Playing || cells.is_empty() {
+            return None;
+        }
+        self.last_move = Some(Instant::now());
+        if self.undo_enabled {
+            self.undo.push(self.position());
+            self.redo.clear();
+        }
+        for (x, y) in cells {
+            if !self.bastard {
+                if self.ruleset.cascades() {
+                    self.board.reveal_cascade(x, y);
+                }
+            } else if self.config.lazy {
+                self.decide((x, y));
+            }
+            if !self.board.clear_cell(x, y) {
+                self.lose = Some((x, y));
+                return None;
+            }
+            self.apply_rules(|rule, game| rule.on_reveal(game, (x, y)));
+            if self.lose.is_some() {
+                return None;
+            }
+        }
+        Some(self.job(
+            self.max_bombs,
+            (x.saturating_sub(6), y.saturating_sub(6))..(x + 7, y + 7),
+        ))
+    }
+
+    /// Open every hidden cell next to a zero, returning the numbers still to work out.
+    /// Nothing opens while the ruleset lies, and only zeros that are shown as they are open
+    /// anything, in case lies were told before.
+    pub fn cascade(&mut self) -> Option<Reveal> {
+        if !self.ruleset.cascades() {
+            return None;
+        }
+        let clearable = self
+            .board
+            .points()
+            .filter(|p| {
+                matches!(self.board[*p], Cell::Discovered(Some(0))) && self.shown(*p) == Some(0)
+            })
+            .flat_map(|(x, y)| {
+                self.board
+                    .neighbors(x, y)
+                    .map(|(x, y, _)| (x, y))
+                    .filter(|p| matches!(self.board[*p], Cell::Quantum(_) | Cell::Concrete(_)))
+                    .collect_vec()
+            })
+            .collect::<BTreeSet<_>>();
+        if clearable.is_empty() {
+            return None;
+        }
+        let range = clearable
+            .iter()
+            .fold((usize::MAX, usize::MAX)..(0, 0), |acc, el| {
+                (
+                    acc.start.0.min(el.0.saturating_sub(2)),
+                    acc.start.1.min(el.1.saturating_sub(2)),
+                )..(acc.end.0.max(el.0 + 3), acc.end.1.max(el.1 + 3))
+            });
+        for (x, y) in clearable {
+            self.board.clear_cell(x, y);
+        }
+        Some(self.job(self.max_bombs, range))
+    }
+
+    /// Take the board a [`Reveal`] worked out, winning if every safe cell is open
+    pub fn finish(&mut self, board: Board) {
+        self.board = board;
+        self.tell_lies();
+        self.apply_rules(|rule, game| rule.on_collapse(game));
+        self.win |= self.ruleset.won(&self.board);
+    }
+
+    /// Decide how far off the ruleset shows every newly counted number
+    fn tell_lies(&mut self) {
+        let counted = self
+            .board
+            .indexed_iter()
+            .filter_map(|(p, c)| match c {
+                Cell::Discovered(Some(n)) if !self.lies.contains_key(&p) => Some((p, *n)),
+                _ => None,
+            })
+            .collect_vec();
+        if counted.is_empty() {
+            return;
+        }
+        let mut unseeded = None;
+        let rng = match &mut self.rng {
+            Some(rng) => rng,
+            None => unseeded.insert(StdRng::from_rng(&mut rng())),
+        };
+        for (p, n) in counted {
+            let shown = self.ruleset.shown_number(n, rng);
+            self.lies.insert(p, shown - n);
+        }
+    }
+
+    /// The number the player sees at `cell`, once it's counted: the real one, unless liar
+    /// mode has it off by one
+    #[must_use]
+    pub fn shown(&self, cell: (usize, usize)) -> Option<i16> {
+        let Cell::Discovered(Some(n)) = self.board[cell] else {
+            return None;
+        };
+        Some(n + self.lies.get(&cell).copied().unwrap_or(0))
+    }
+
+    /// The board as the player sees it, with every number as [`Game::shown`] has it
+    #[must_use]
+    pub fn seen(&self) -> Cow<'_, Board> {
+        if self.lies.values().all(|lie| *lie == 0) {
+            return Cow::Borrowed(&self.board);
+        }
+        let mut board = self.board.clone();
+        for (cell, lie) in &self.lies {
+            if let Cell::Discovered(Some(n)) = &mut board[*cell] {
+                *n += lie;
+            }
+        }
+        Cow::Owned(board)
+    }
+
+    /// What the player can work out from the numbers they see, as the ruleset reads them, or
+    /// `None` if the solver can't read this variant's numbers
+    #[must_use]
+    pub fn solver(&self) -> Option<solver::Solver> {
+        self.ruleset.solver(&self.seen(), self.max_bombs)
+    }
+
+    /// Flag or unflag a hidden cell. Returns whether it's flagged once the rules have had
+    /// their say, or `None` if it can't be flagged.
+    pub fn toggle_flag(&mut self, x: usize, y: usize) -> Option<bool> {
+        if !matches!(self.board[(x, y)], Cell::Quantum(_) | Cell::Concrete(_)) {
+            return None;
+        }
+        let flagged = !self.flags.remove(&(x, y));
+        if flagged {
+            self.flags.insert((x, y));
+        }
+        self.apply_rules(|rule, game| rule.on_flag(game, (x, y), flagged));
+        Some(self.flags.contains(&(x, y)))
+    }
+}
+
+/// Numbers left to work out after a reveal, which can take a while in bastard mode
+#[derive(Clone, Debug)]
+pub struct Reveal {
+    board: Board,
+    bastard: bool,
+    max_bombs: usize,
+    /// Where collapses may reassign cells
+    range: Option<Range<(usize, usize)>>,
+    /// Collapse once even with no numbers to work out, for [`Game::idle_collapse`]
+    reshuffle: bool,
+    /// Set in seeded games
+    rng: Option<StdRng>,
+}
+
+impl Reveal {
+    /// Fill in the numbers, collapsing as often as it takes in bastard mode.
+    /// `observer` hears about every collapse, and `None` comes back if it cancels one.
+    /// In a seeded game `config` is made [`CollapseConfig::reproducible`].
+    pub fn run(
+        mut self,
+        config: &CollapseConfig,
+        observer: &mut impl CollapseObserver,
+    ) -> Option<(Board, Vec<CollapseTrace>)> {
+        let (config, mut rng) = match self.rng.take() {
+            Some(rng) => (config.reproducible(), rng),
+            None => (*config, StdRng::from_rng(&mut rng())),
+        };
+        let mut traces = Vec::new();
+        if self.bastard {
+            while std::mem::take(&mut self.reshuffle)
+                || self
+                    .board
+                    .iter()
+                    .any(|c| matches!(c, Cell::Discovered(None)))
+            {
+                let trace = self.board.collapse_observed(
+                    &config,
+                    self.max_bombs,
+                    self.range.clone(),
+                    &mut rng,
+                    observer,
+                );
+                if trace.outcome == CollapseOutcome::Cancelled {
+                    return None;
+                }
+                traces.push(trace);
+                self.board.fill_discovered();
+            }
+        } else {
+            self.board.fill_discovered();
+        }
+        Some((self.board, traces))
+    }
+}
+
+/// Spreadsheet-style letters for a column: A to Z, then AA, AB and so on
+#[must_use]
+pub fn column_name(x: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = x + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(char::from(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"[n % 26]));
+        n /= 26;
+    }
+    letters.into_iter().rev().collect()
+}
+
+/// How players read out a cell: column letters then a row number from 1, like `C7`
+#[must_use]
+pub fn format_coord(x: usize, y: usize) -> String {
+    format!("{}{}", column_name(x), y + 1)
+}
+
+/// Read a cell written like [`format_coord`] does, ignoring case and surrounding space
+#[must_use]
+pub fn parse_coord(s: &str) -> Option<(usize, usize)> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (letters, digits) = s.split_at(split);
+    let x = letters.bytes().try_fold(0usize, |acc, b| {
+        acc.checked_mul(26)?
+            .checked_add(usize::from(b.to_ascii_uppercase() - b'A') + 1)
+    })?;
+    let y = digits.parse::<usize>().ok()?;
+    Some((x.checked_sub(1)?, y.checked_sub(1)?))
+}
+
+/// Ask the scheduler to favor other work over the calling thread.
+/// Per thread on Linux, but the whole process on other unixes.
+pub fn lower_priority() {
+    #[cfg(unix)]
+    // SAFETY: nice only changes the scheduling priority, and can't fail in a way that matters here
+    unsafe {
+        libc::nice(10);
+    }
+}
+
+/// How [`Board::to_text`] writes the number `n`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn digit(n: i16) -> char {
+    char::from(
+        (if n < 0 {
+            i16::from(b'z') + 1
+        } else {
+            i16::from(b'0')
+        } + n) as u8,
+    )
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn ln_choose(n: usize, k: usize) -> f64 {
+    (0..k.min(n))
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lazy_chords_decide_undecided_cells() {
+        // The bomb in the corner is flagged, and the zeros make both cells the chord opens safe
+        let board = Board::from_text("Q1?0\n11?0\n").unwrap();
+        let mut game = Game::new(board, 1, true).with_seed(0);
+        game.config.lazy = true;
+        game.first_click = false;
+        game.toggle_flag(0, 0);
+        game.chord(1, 0);
+        assert_eq!(game.state(), GameState::Won);
+        assert!(matches!(game.board[(2, 0)], Cell::Discovered(_)));
+        assert!(matches!(game.board[(2, 1)], Cell::Discovered(_)));
+    }
+
+    #[test]
+    fn lying_zeros_open_nothing() {
+        let board = Board::from_text("*...\n....\n").unwrap();
+        let mut game = Game::new(board, 1, false).with_seed(0);
+        game.first_click = false;
+        game.ruleset = Arc::new(rules::Liar {
+            neighborhood: Neighborhood::default(),
+            chance: 1.,
+        });
+        game.reveal(3, 1);
+        assert_eq!(game.shown((3, 1)), Some(1));
+        let open = game
+            .board
+            .iter()
+            .filter(|c| matches!(c, Cell::Discovered(_)))
+            .count();
+        assert_eq!(open, 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saves_keep_the_ruleset() {
+        let board = Board::from_text("**..\n....\n").unwrap();
+        let game = Game::new(board, 2, false).with_ruleset(Arc::new(rules::Multimines {
+            neighborhood: Neighborhood::default(),
+            capacity: 3,
+        }));
+        let json = save::SavedGame::from(&game).to_json();
+        let loaded = Game::from(save::SavedGame::from_json(&json).unwrap());
+        assert_eq!(loaded.ruleset.name(), "Multimines");
+        assert_eq!(loaded.ruleset.parameters(), vec![("capacity", 3.)]);
+        let unknown = json.replace("\"Multimines\"", "\"Tetris\"");
+        assert!(save::SavedGame::from_json(&unknown).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn traces_keep_times_in_seconds() {
+        let trace = CollapseTrace {
+            policy: Some(Policy::Random),
+            enumerate_time: Duration::from_millis(1500),
+            ..CollapseTrace::default()
+        };
+        let json = trace.to_json();
+        assert!(json.contains("\"enumerate_secs\":1.5"), "{json}");
+        assert!(json.contains("\"sample_secs\":0.0"), "{json}");
+        let read: CollapseTrace = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.enumerate_time, trace.enumerate_time);
+        assert_eq!(read.policy, trace.policy);
+    }
+}
+
+// pub enum Board {
+//     Quad([[Arc<Board>; 2]; 2]),
+//     Concrete(Array2<Cell>),
+// }
+
+// impl Board {
+//     pub fn size(&self) -> (usize, usize) {
+//         match self {
+//             Board::Quad([[tl, tr], [bl, br]]) => {
+//                 let tl = tl.size();
+//                 let tr = tr.size();
+//                 let bl = bl.size();
+//                 let br = br.size();
+//                 let top_width = tl.0 + tr.0;
+//                 let bottom_width = bl.0 + br.0;
+//                 debug_assert_eq!(top_width, bottom_width);
+//                 let left_height = tl.1 + bl.1;
+//                 let right_height = tr.1 + br.1;
+//                 debug_assert_eq!(left_height, right_height);
+//                 (top_width, left_height)
+//             }
+//             Board::Concrete(array) => array.dim(),
+//         }
+//     }
+
+//     pub fn call_descend(&self, )
+
+//     pub fn assignment_is_legal(&self, x: usize, y: usize, value: bool) -> bool {
+//         todo!()
+//     }
+
+//     pub fn collapse(self: Arc<Self>, maximize: impl Fn(&Self) -> f64) -> Arc<Self> {
+//         match *self {
+//             Board::Quad(_) => todo!(),
+//             Board::Concrete(contents) => {}
+//         }
+//     }
+// }
+
+// impl Index<(usize, usize)> for Board {
+//     type Output = Cell;
+
+//     fn index(&self, index: (usize, usize)) -> &Self::Output {
+//         match self {
+//             Board::Quad(_) => todo!(),
+//             Board::Concrete(array) => &array[index],
+//         }
+//     }
+// }
+
+// impl IndexMut<(usize, usize)> for Board {
+//     type Output = Cell;
+
+//     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+//         todo!()
+//     }
+// }
@@ -0,0 +1,136 @@
+//! Where to make the first click on an honest board, worked out from its shape and bomb
+//! count alone
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use ndarray::Array2;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use rayon::prelude::*;
+
+use crate::Board;
+
+/// How a first click on one cell tends to go
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FirstClick {
+    pub cell: (usize, usize),
+    /// Cells around it, which all have to be safe for it to open an area
+    pub neighbors: usize,
+    /// Chance it opens an area
+    pub opens: f64,
+    /// Cells it reveals on average, itself included
+    pub revealed: f64,
+}
+
+/// First clicks on cells with the same number of neighbors, averaged. On a plain board
+/// that's the corners, the edges and the middle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Spot {
+    pub neighbors: usize,
+    /// How many cells have that many neighbors
+    pub cells: usize,
+    pub opens: f64,
+    pub revealed: f64,
+}
+
+/// How a first click goes on every cell of a board
+#[derive(Clone, Debug, Default)]
+pub struct Advice {
+    pub clicks: Vec<FirstClick>,
+}
+
+impl Advice {
+    /// Work out first clicks on `shape` with `bombs` bombs dealt uniformly at random, from
+    /// `samples` deals. A bomb under the first click is taken away, as honest games do.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(shape: &Board, bombs: usize, samples: usize, rng: &mut impl Rng) -> Self {
+        let points = shape.points().collect_vec();
+        let seeds = (0..samples).map(|_| rng.random::<u64>()).collect_vec();
+        let totals = seeds
+            .into_par_iter()
+            .map(|seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut dealt = Array2::from_elem(shape.dim(), false);
+                for p in points.choose_multiple(&mut rng, bombs) {
+                    dealt[*p] = true;
+                }
+                points
+                    .iter()
+                    .map(|p| revealed(shape, &mut dealt, *p))
+                    .collect_vec()
+            })
+            .reduce(
+                || vec![0; points.len()],
+                |a, b| a.into_iter().zip(b).map(|(a, b)| a + b).collect(),
+            );
+        let cells = points.len();
+        let clicks = points
+            .into_iter()
+            .zip(totals)
+            .map(|(cell, total)| {
+                let neighbors = shape.neighbors(cell.0, cell.1).count();
+                // The neighbors all have to miss the bombs, which are dealt over every cell
+                let opens = (0..neighbors)
+                    .map(|i| cells.saturating_sub(bombs + i) as f64 / (cells - i) as f64)
+                    .product();
+                FirstClick {
+                    cell,
+                    neighbors,
+                    opens,
+                    revealed: total as f64 / samples.max(1) as f64,
+                }
+            })
+            .collect();
+        Self { clicks }
+    }
+
+    /// The first click most likely to open an area, and of those the one revealing the most
+    #[must_use]
+    pub fn best(&self) -> Option<&FirstClick> {
+        self.clicks.iter().max_by(|a, b| {
+            a.opens
+                .total_cmp(&b.opens)
+                .then(a.revealed.total_cmp(&b.revealed))
+        })
+    }
+
+    /// First clicks averaged over cells with as many neighbors, fewest first
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn spots(&self) -> Vec<Spot> {
+        self.clicks
+            .iter()
+            .into_group_map_by(|c| c.neighbors)
+            .into_iter()
+            .map(|(neighbors, clicks)| Spot {
+                neighbors,
+                cells: clicks.len(),
+                opens: clicks.iter().map(|c| c.opens).sum::<f64>() / clicks.len() as f64,
+                revealed: clicks.iter().map(|c| c.revealed).sum::<f64>() / clicks.len() as f64,
+            })
+            .sorted_by_key(|s| s.neighbors)
+            .collect()
+    }
+}
+
+/// How many cells a first click on `cell` reveals with bombs where `dealt` says
+fn revealed(shape: &Board, dealt: &mut Array2<bool>, cell: (usize, usize)) -> usize {
+    let under = std::mem::replace(&mut dealt[cell], false);
+    let mut seen = HashSet::from([cell]);
+    let mut queue = vec![cell];
+    while let Some((x, y)) = queue.pop() {
+        if shape.neighbors(x, y).any(|(x, y, _)| dealt[(x, y)]) {
+            continue;
+        }
+        for (x, y, _) in shape.neighbors(x, y) {
+            if seen.insert((x, y)) {
+                queue.push((x, y));
+            }
+        }
+    }
+    dealt[cell] = under;
+    seen.len()
+}
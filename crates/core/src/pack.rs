@@ -0,0 +1,127 @@
+//! Board packs: `.bmpack` files bundling boards under a title, author and difficulty.
+//!
+//! A pack is plain text. `key = value` lines for the pack come first, then each board starts
+//! with a `[name]` line, followed by its own `key = value` lines and its rows as
+//! [`Board::to_text`] writes them:
+//!
+//! ```text
+//! title = Warmups
+//! author = Someone
+//! difficulty = Beginner
+//!
+//! [First]
+//! note = Start in the corner
+//! *..
+//! ...
+//! ```
+
+use std::fmt::Write;
+
+use crate::{Board, Cell, Game};
+
+/// Boards bundled together for sharing
+#[derive(Clone, Debug, Default)]
+pub struct Pack {
+    pub title: String,
+    pub author: String,
+    /// However the author describes it
+    pub difficulty: String,
+    pub boards: Vec<PackBoard>,
+}
+
+/// One board in a [`Pack`]
+#[derive(Clone, Debug)]
+pub struct PackBoard {
+    pub name: String,
+    /// Anything the author wants to say about it
+    pub note: String,
+    pub board: Board,
+}
+
+impl PackBoard {
+    /// How many bombs the board has
+    #[must_use]
+    pub fn bombs(&self) -> usize {
+        self.board
+            .iter()
+            .map(|c| usize::from(c.bomb_count().start().unsigned_abs()))
+            .sum()
+    }
+
+    /// An honest game on the board, starting with a first click unless some of it is
+    /// already revealed
+    #[must_use]
+    pub fn game(&self) -> Game {
+        let mut game = Game::new(self.board.clone(), self.bombs(), false);
+        game.first_click = !self.board.iter().any(|c| matches!(c, Cell::Discovered(_)));
+        game
+    }
+}
+
+impl Pack {
+    /// Read a pack, saying which line is wrong if it isn't one
+    ///
+    /// # Errors
+    /// If a line isn't a key, a board name or a board row, a board's rows don't read back
+    /// as a board, or there are no boards
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut pack = Pack::default();
+        // The board being read: its name, note and rows so far
+        let mut current: Option<(String, String, String)> = None;
+        for (number, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(board) = current.take() {
+                    pack.boards.push(finish(board)?);
+                }
+                current = Some((name.trim().to_string(), String::new(), String::new()));
+            } else if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim().to_string();
+                match (key.trim(), &mut current) {
+                    ("title", None) => pack.title = value,
+                    ("author", None) => pack.author = value,
+                    ("difficulty", None) => pack.difficulty = value,
+                    ("note", Some((_, note, _))) => *note = value,
+                    _ => return Err(format!("line {number}: unknown key {line:?}")),
+                }
+            } else if let Some((_, _, rows)) = &mut current {
+                rows.push_str(line);
+                rows.push('\n');
+            } else {
+                return Err(format!("line {number}: board row before any [name]"));
+            }
+        }
+        if let Some(board) = current {
+            pack.boards.push(finish(board)?);
+        }
+        if pack.boards.is_empty() {
+            return Err("no boards".to_string());
+        }
+        Ok(pack)
+    }
+
+    /// Format as text that [`Pack::parse`] reads back
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "title = {}\nauthor = {}\ndifficulty = {}\n",
+            self.title, self.author, self.difficulty
+        );
+        for board in &self.boards {
+            let _ = write!(text, "\n[{}]\n", board.name);
+            if !board.note.is_empty() {
+                let _ = writeln!(text, "note = {}", board.note);
+            }
+            text += &board.board.to_text();
+        }
+        text
+    }
+}
+
+/// Turn the rows read for a board into one
+fn finish((name, note, rows): (String, String, String)) -> Result<PackBoard, String> {
+    let board = Board::from_text(&rows).ok_or_else(|| format!("board {name:?} doesn't read"))?;
+    Ok(PackBoard { name, note, board })
+}
@@ -0,0 +1,287 @@
+//! How a collapse picks one of the bomb placements that fit the numbers
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use rand::{Rng, RngCore};
+use rayon::prelude::*;
+use web_time::Instant;
+
+use crate::{Board, Cell, CollapseConfig, CollapseObserver, CollapseTrace};
+
+/// How many states to score between checks on the signature time limit
+const SIGNATURE_CHUNK: usize = 1024;
+
+/// Sets of numbers, with what the states giving each have in common
+type Tally = HashMap<Vec<((usize, usize), i16)>, Seen>;
+
+/// The states that give one set of numbers
+#[derive(Clone, Debug, Default)]
+struct Seen {
+    count: usize,
+    /// The index of the first of them
+    first: usize,
+    /// How many of them put a bomb on each frontier cell, if that was asked for
+    bombs: Vec<usize>,
+}
+
+impl Seen {
+    /// Add `other`'s states to these
+    fn merge(&mut self, other: Seen) {
+        if self.count == 0 {
+            self.first = other.first;
+        }
+        self.count += other.count;
+        self.first = self.first.min(other.first);
+        if self.bombs.is_empty() {
+            self.bombs = other.bombs;
+        } else {
+            for (a, b) in self.bombs.iter_mut().zip(other.bombs) {
+                *a += b;
+            }
+        }
+    }
+
+    /// How unsure the player stays about the frontier if these numbers are kept, in bits:
+    /// the entropy of each cell holding a bomb, added up. Cells the numbers settle count nothing.
+    #[allow(clippy::cast_precision_loss)]
+    fn uncertainty(&self) -> f64 {
+        self.bombs
+            .iter()
+            .map(|&bombs| {
+                let p = bombs as f64 / self.count as f64;
+                [p, 1. - p]
+                    .into_iter()
+                    .filter(|p| *p > 0.)
+                    .map(|p| -p * p.log2())
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+}
+
+/// One way of placing bombs on a collapse's frontier: whether each cell gets one, in order
+pub type State = Vec<bool>;
+
+/// What else a [`CollapsePolicy`] has to go on
+pub struct Choosing<'a> {
+    /// The cells every state assigns, in order
+    pub frontier: &'a [(usize, usize)],
+    pub config: &'a CollapseConfig,
+    pub rng: &'a mut dyn RngCore,
+    /// Hears how scoring is going, and may ask to give up
+    pub observer: &'a mut dyn CollapseObserver,
+    /// Where to note how the choice was made
+    pub trace: &'a mut CollapseTrace,
+}
+
+/// Picks which of the states that fit the numbers a collapse keeps
+pub trait CollapsePolicy {
+    /// The index of the state to keep, out of `states`, which isn't empty. `board` has the
+    /// frontier unassigned. `None` gives up, as when the observer cancels.
+    fn choose(&mut self, states: &[State], board: &Board, choosing: &mut Choosing)
+    -> Option<usize>;
+}
+
+/// The built-in policies, by name
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Policy {
+    /// [`MostAmbiguous`]
+    #[default]
+    Bastard,
+    /// [`Uniform`]
+    Random,
+    /// [`Friendly`]
+    Angel,
+    /// [`LeastInformative`]
+    Entropy,
+}
+
+impl CollapsePolicy for Policy {
+    fn choose(
+        &mut self,
+        states: &[State],
+        board: &Board,
+        choosing: &mut Choosing,
+    ) -> Option<usize> {
+        match self {
+            Policy::Bastard => MostAmbiguous.choose(states, board, choosing),
+            Policy::Random => Uniform.choose(states, board, choosing),
+            Policy::Angel => Friendly.choose(states, board, choosing),
+            Policy::Entropy => LeastInformative.choose(states, board, choosing),
+        }
+    }
+}
+
+/// Keep the most common set of numbers, so the player's next numbers tell them as little
+/// as possible
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MostAmbiguous;
+
+impl CollapsePolicy for MostAmbiguous {
+    fn choose(
+        &mut self,
+        states: &[State],
+        board: &Board,
+        choosing: &mut Choosing,
+    ) -> Option<usize> {
+        choosing.trace.policy = Some(Policy::Bastard);
+        let counts = tally(states, board, choosing, false)?;
+        // Ties go to the smallest set of numbers, so the choice doesn't depend on map order
+        let (_, kept) = counts
+            .into_iter()
+            .max_by(|(a_numbers, a), (b_numbers, b)| {
+                a.count.cmp(&b.count).then_with(|| b_numbers.cmp(a_numbers))
+            })?;
+        choosing.trace.chosen_states = kept.count;
+        Some(kept.first)
+    }
+}
+
+/// Keep any state, each as likely as the others, like an honest board would have
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Uniform;
+
+impl CollapsePolicy for Uniform {
+    fn choose(
+        &mut self,
+        states: &[State],
+        _board: &Board,
+        choosing: &mut Choosing,
+    ) -> Option<usize> {
+        choosing.trace.policy = Some(Policy::Random);
+        (!choosing.observer.cancelled()).then(|| choosing.rng.random_range(0..states.len()))
+    }
+}
+
+/// Keep the rarest set of numbers, which rules out the most and leaves the player the most
+/// to deduce
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Friendly;
+
+impl CollapsePolicy for Friendly {
+    fn choose(
+        &mut self,
+        states: &[State],
+        board: &Board,
+        choosing: &mut Choosing,
+    ) -> Option<usize> {
+        choosing.trace.policy = Some(Policy::Angel);
+        let counts = tally(states, board, choosing, false)?;
+        // Ties go to the smallest set of numbers, so the choice doesn't depend on map order
+        let (_, kept) = counts
+            .into_iter()
+            .min_by(|(a_numbers, a), (b_numbers, b)| {
+                a.count.cmp(&b.count).then_with(|| a_numbers.cmp(b_numbers))
+            })?;
+        choosing.trace.chosen_states = kept.count;
+        Some(kept.first)
+    }
+}
+
+/// Keep the set of numbers that leaves the player least sure of where the frontier's bombs
+/// are, so they learn as little as possible from it. Unlike [`MostAmbiguous`], a rarer set
+/// wins if the common one would settle some cells for certain.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LeastInformative;
+
+impl CollapsePolicy for LeastInformative {
+    fn choose(
+        &mut self,
+        states: &[State],
+        board: &Board,
+        choosing: &mut Choosing,
+    ) -> Option<usize> {
+        choosing.trace.policy = Some(Policy::Entropy);
+        let counts = tally(states, board, choosing, true)?;
+        // Ties go to the more common set, then the smallest, so the choice doesn't depend on
+        // map order
+        let (_, kept) = counts
+            .into_iter()
+            .max_by(|(a_numbers, a), (b_numbers, b)| {
+                a.uncertainty()
+                    .total_cmp(&b.uncertainty())
+                    .then_with(|| a.count.cmp(&b.count))
+                    .then_with(|| b_numbers.cmp(a_numbers))
+            })?;
+        choosing.trace.chosen_states = kept.count;
+        Some(kept.first)
+    }
+}
+
+/// For each set of numbers the states give, how many states give it and the first of them,
+/// along with where they put bombs if `bombs` is set. Scores as many states as
+/// `config.signature_time` allows, recording the counts in the trace. `None` if the observer
+/// cancels.
+fn tally(states: &[State], board: &Board, choosing: &mut Choosing, bombs: bool) -> Option<Tally> {
+    let Choosing {
+        frontier,
+        config,
+        observer,
+        trace,
+        ..
+    } = choosing;
+    let began = Instant::now();
+    let pool = config.pool();
+    let mut state_counts = HashMap::new();
+    for (chunk, quantas) in states.chunks(SIGNATURE_CHUNK).enumerate() {
+        // Always score a chunk, so there's something to choose from
+        if observer.cancelled() || (chunk > 0 && began.elapsed() >= config.signature_time) {
+            break;
+        }
+        let offset = chunk * SIGNATURE_CHUNK;
+        observer.on_progress(offset, states.len());
+        let counted = pool.install(|| {
+            quantas
+                .par_iter()
+                .enumerate()
+                .map_init(
+                    || board.clone(),
+                    |board, (i, quanta)| {
+                        for (c, b) in frontier.iter().zip(quanta) {
+                            board[*c] = Cell::Quantum(Some(*b));
+                        }
+                        let seen = Seen {
+                            count: 1,
+                            first: offset + i,
+                            bombs: if bombs {
+                                quanta.iter().map(|b| usize::from(*b)).collect()
+                            } else {
+                                Vec::new()
+                            },
+                        };
+                        (board.find_discovered_counts(), seen)
+                    },
+                )
+                .fold(HashMap::new, |mut counts, (numbers, seen)| {
+                    count_signature(&mut counts, numbers, seen);
+                    counts
+                })
+                .reduce(HashMap::new, |mut a, b| {
+                    for (numbers, seen) in b {
+                        count_signature(&mut a, numbers, seen);
+                    }
+                    a
+                })
+        });
+        for (numbers, seen) in counted {
+            count_signature(&mut state_counts, numbers, seen);
+        }
+    }
+    if observer.cancelled() {
+        return None;
+    }
+    trace.sample_time = began.elapsed();
+    trace.signature_counts = state_counts
+        .values()
+        .map(|seen| seen.count)
+        .sorted_unstable_by(|a, b| b.cmp(a))
+        .collect();
+    Some(state_counts)
+}
+
+/// Add `seen` states to the tally for `numbers`
+fn count_signature<K: Eq + std::hash::Hash>(counts: &mut HashMap<K, Seen>, numbers: K, seen: Seen) {
+    counts.entry(numbers).or_default().merge(seen);
+}
@@ -0,0 +1,141 @@
+//! Boards drawn as text for a terminal, optionally in color
+
+use std::collections::HashSet;
+
+use crate::{Board, Cell};
+
+/// Which characters a board is drawn with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Glyphs {
+    /// Digits and punctuation, cells separated by spaces
+    #[default]
+    Plain,
+    /// The same characters inside a box-drawing grid
+    Grid,
+    /// One emoji per cell, for pasting where colors don't survive
+    Emoji,
+}
+
+/// How to draw a board as text
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextStyle {
+    pub glyphs: Glyphs,
+    /// Color cells with ANSI escapes: numbers in the classic colors, bombs red, flags yellow.
+    /// Emoji bring their own colors.
+    pub color: bool,
+    /// Show where the bombs are, as far as the board knows
+    pub bombs: bool,
+}
+
+/// What a cell looks like to the player
+#[derive(Clone, Copy)]
+enum Look {
+    Zero,
+    Number(i16),
+    /// Revealed, but its number isn't worked out yet
+    Counting,
+    Flag,
+    Bomb,
+    Hidden,
+    /// A hole in a shaped board
+    Void,
+}
+
+impl TextStyle {
+    /// One line per row. Plain and grid glyphs are digits for numbers, `.` for zeros, `-` for
+    /// cells still being counted, `F` for flags, `*` for bombs, `#` for anything else hidden and
+    /// a space for holes.
+    #[must_use]
+    pub fn render(self, board: &Board, flags: &HashSet<(usize, usize)>) -> String {
+        let (width, height) = board.dim();
+        let rows = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| self.cell(self.look(board[(x, y)], flags.contains(&(x, y)))))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        match self.glyphs {
+            Glyphs::Plain => rows.into_iter().map(|row| row.join(" ") + "\n").collect(),
+            Glyphs::Emoji => rows.into_iter().map(|row| row.concat() + "\n").collect(),
+            Glyphs::Grid => {
+                let line = |left: &str, middle: &str, right: &str| {
+                    format!("{left}{}{right}\n", vec!["───"; width].join(middle))
+                };
+                let mut text = line("┌", "┬", "┐");
+                for (y, row) in rows.into_iter().enumerate() {
+                    if y > 0 {
+                        text += &line("├", "┼", "┤");
+                    }
+                    text += "│ ";
+                    text += &row.join(" │ ");
+                    text += " │\n";
+                }
+                text + &line("└", "┴", "┘")
+            }
+        }
+    }
+
+    /// One cell, as [`TextStyle::render`] draws it
+    #[must_use]
+    pub fn glyph(self, cell: Cell, flagged: bool) -> String {
+        self.cell(self.look(cell, flagged))
+    }
+
+    fn look(self, cell: Cell, flagged: bool) -> Look {
+        match cell {
+            Cell::Discovered(Some(0)) => Look::Zero,
+            Cell::Discovered(Some(n)) => Look::Number(n),
+            Cell::Discovered(None) => Look::Counting,
+            Cell::Void => Look::Void,
+            _ if flagged => Look::Flag,
+            Cell::Quantum(Some(true)) | Cell::Concrete(..=-1 | 1..) if self.bombs => Look::Bomb,
+            _ => Look::Hidden,
+        }
+    }
+
+    fn cell(self, look: Look) -> String {
+        if self.glyphs == Glyphs::Emoji {
+            return match look {
+                Look::Zero => "⬜".to_string(),
+                Look::Number(n @ 0..10) => format!("{n}\u{fe0f}\u{20e3}"),
+                // Bigger neighborhoods and anti-mines count past the keycaps
+                Look::Number(n) => n.to_string(),
+                Look::Counting => "➖".to_string(),
+                Look::Flag => "🚩".to_string(),
+                Look::Bomb => "💣".to_string(),
+                Look::Hidden => "🟦".to_string(),
+                // Emoji are two columns wide
+                Look::Void => "  ".to_string(),
+            };
+        }
+        let (glyph, color) = match look {
+            Look::Zero => (".".to_string(), None),
+            Look::Number(n) => (n.to_string(), Some(number_color(n))),
+            Look::Counting => ("-".to_string(), None),
+            Look::Flag => ("F".to_string(), Some("33")),
+            Look::Bomb => ("*".to_string(), Some("31;1")),
+            Look::Hidden => ("#".to_string(), Some("90")),
+            Look::Void => (" ".to_string(), None),
+        };
+        match color {
+            Some(code) if self.color => format!("\x1b[{code}m{glyph}\x1b[0m"),
+            _ => glyph,
+        }
+    }
+}
+
+/// ANSI color of a number, after the usual minesweeper colors
+fn number_color(n: i16) -> &'static str {
+    match n {
+        ..=-1 => "95",
+        1 => "94",
+        2 => "32",
+        3 => "91",
+        4 => "34",
+        5 => "31",
+        6 => "36",
+        7 => "35",
+        _ => "37",
+    }
+}
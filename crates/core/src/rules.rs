@@ -0,0 +1,359 @@
+//! Hooks for game variants, so they can change what happens without touching [`Game`]
+
+use std::{fmt::Debug, sync::Arc};
+
+use itertools::Itertools;
+use rand::{Rng, RngCore, seq::IndexedRandom};
+
+use crate::{Board, Cell, Game, Neighborhood, solver::Solver};
+
+/// Something a variant adds to the rules, told about every move and free to change the game
+/// in response. Rules keep their state in the game, so undo takes it back with everything else.
+pub trait Rule: Debug + Send + Sync {
+    /// The player opened `cell`, by clicking it or chording next to it, and it wasn't a bomb.
+    /// Losing the game here stops the move.
+    fn on_reveal(&self, _game: &mut Game, _cell: (usize, usize)) {}
+    /// A collapse filled in the numbers left by a reveal, before the game checks for a win
+    fn on_collapse(&self, _game: &mut Game) {}
+    /// The player flagged `cell`, or unflagged it if `flagged` is false
+    fn on_flag(&self, _game: &mut Game, _cell: (usize, usize), _flagged: bool) {}
+}
+
+/// No more flags than there are bombs: a flag past that many is taken straight back off
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlagLimit;
+
+impl Rule for FlagLimit {
+    fn on_flag(&self, game: &mut Game, cell: (usize, usize), flagged: bool) {
+        if flagged && game.flags.len() > game.max_bombs {
+            game.flags.remove(&cell);
+        }
+    }
+}
+
+/// What a variant's numbers and goal are, settled before the board is dealt: which cells a
+/// number counts, how bombs are dealt, what numbers show, how the game is won and how gentle
+/// the first click is. Where a [`Rule`] reacts to moves, a ruleset decides what they mean.
+pub trait Ruleset: Debug + Send + Sync {
+    /// The variant's name, for players
+    fn name(&self) -> &'static str;
+    /// Which cells a number counts
+    fn neighborhood(&self) -> Neighborhood;
+    /// What the ruleset was made with besides its neighborhood, by name, so saves can make
+    /// it again with [`from_parameters`]
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+    /// What players need to keep in mind about these rules, if anything
+    fn describe(&self) -> Option<String> {
+        None
+    }
+    /// Why `bombs` bombs can't be dealt into `cells` cells, if they can't
+    ///
+    /// # Errors
+    /// If the bombs don't fit, or the ruleset was made with settings that make no sense
+    fn check(&self, cells: usize, bombs: usize) -> Result<(), String> {
+        fits(cells, bombs, 1)
+    }
+    /// `cell` with one more bomb dealt into it, or `None` if it can't take another
+    fn place(&self, cell: Cell) -> Option<Cell> {
+        matches!(cell, Cell::Concrete(0)).then_some(Cell::Concrete(1))
+    }
+    /// `shape` with `bombs` bombs dealt into it up front at random, leaving its holes be.
+    /// The bombs have to pass [`Ruleset::check`].
+    fn deal(&self, shape: Board, bombs: usize, rng: &mut dyn RngCore) -> Board {
+        scatter(shape, bombs, rng, |cell| self.place(cell))
+    }
+    /// Whether zeros open the cells around them by themselves, which only makes sense while
+    /// they're true
+    fn cascades(&self) -> bool {
+        true
+    }
+    /// The number shown on a cell with `number` bombs around it, decided once when it's
+    /// counted
+    fn shown_number(&self, number: i16, _rng: &mut dyn RngCore) -> i16 {
+        number
+    }
+    /// What a player can work out from the numbers on `board`, with `bombs` bombs in all, or
+    /// `None` if the solver can't read these numbers. Hints, statistics, puzzles and quantum
+    /// cells all need it to.
+    fn solver(&self, board: &Board, bombs: usize) -> Option<Solver> {
+        Some(Solver::new(board, bombs))
+    }
+    /// The same rules with every number shown one off with `chance`, or `None` if the solver
+    /// couldn't allow for lies in them
+    fn lying(&self, _chance: f64) -> Option<Arc<dyn Ruleset>> {
+        None
+    }
+    /// Whether `board` is won: every safe cell is open
+    fn won(&self, board: &Board) -> bool {
+        board.iter().all(|c| {
+            matches!(
+                c,
+                Cell::Quantum(Some(true))
+                    | Cell::Discovered(_)
+                    | Cell::Concrete(..=-1 | 1..)
+                    | Cell::Void
+            )
+        })
+    }
+    /// Most bombs the first reveal of a bastard game may leave near the cell it opens, so it
+    /// doesn't start on a guess, or `None` to play it like any other reveal
+    fn first_click_bombs(&self) -> Option<usize> {
+        Some(8)
+    }
+}
+
+/// The ruleset [`Ruleset::name`] calls `name`, made with `parameters` as
+/// [`Ruleset::parameters`] gave them. `None` for a name or parameters it doesn't know.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn from_parameters(
+    name: &str,
+    neighborhood: Neighborhood,
+    parameters: &[(String, f64)],
+) -> Option<Arc<dyn Ruleset>> {
+    let parameter = |wanted: &str| {
+        parameters
+            .iter()
+            .find(|(name, _)| name == wanted)
+            .map(|(_, value)| *value)
+    };
+    Some(match name {
+        "Classic" => Arc::new(Classic { neighborhood }),
+        "Liar" => Arc::new(Liar {
+            neighborhood,
+            chance: parameter("chance")?,
+        }),
+        "Multimines" => Arc::new(Multimines {
+            neighborhood,
+            capacity: parameter("capacity")? as u8,
+        }),
+        "Anti-mines" => Arc::new(AntiMines {
+            neighborhood,
+            count: parameter("count")? as usize,
+        }),
+        _ => return None,
+    })
+}
+
+/// Whether `bombs` bombs fit in `cells` cells of `capacity` bombs each, leaving room to move
+fn fits(cells: usize, bombs: usize, capacity: u8) -> Result<(), String> {
+    if bombs < cells * usize::from(capacity) {
+        Ok(())
+    } else {
+        Err(format!("{bombs} bombs don't fit in {cells} cells"))
+    }
+}
+
+/// `board` with every cell emptied, then `bombs` bombs dropped on random cells that `place`
+/// takes them in
+fn scatter(
+    mut board: Board,
+    bombs: usize,
+    rng: &mut dyn RngCore,
+    place: impl Fn(Cell) -> Option<Cell>,
+) -> Board {
+    let (width, height) = board.dim();
+    for p in board.points().collect_vec() {
+        board[p] = Cell::Concrete(0);
+    }
+    let mut left = bombs;
+    while left > 0 {
+        let p = (rng.random_range(0..width), rng.random_range(0..height));
+        if let Some(cell) = place(board[p]) {
+            board[p] = cell;
+            left -= 1;
+        }
+    }
+    board
+}
+
+/// Classic or liar rules counting `neighborhood`, lying with `chance`
+fn lying(neighborhood: Neighborhood, chance: f64) -> Arc<dyn Ruleset> {
+    if chance > 0. {
+        Arc::new(Liar {
+            neighborhood,
+            chance,
+        })
+    } else {
+        Arc::new(Classic { neighborhood })
+    }
+}
+
+/// Plain minesweeper
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Classic {
+    pub neighborhood: Neighborhood,
+}
+
+impl Ruleset for Classic {
+    fn name(&self) -> &'static str {
+        "Classic"
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    fn lying(&self, chance: f64) -> Option<Arc<dyn Ruleset>> {
+        Some(lying(self.neighborhood, chance))
+    }
+}
+
+/// Liar mode: each number has `chance` of being shown one off from the truth, up or down
+/// alike, and always up from a zero
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Liar {
+    pub neighborhood: Neighborhood,
+    pub chance: f64,
+}
+
+impl Ruleset for Liar {
+    fn name(&self) -> &'static str {
+        "Liar"
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("chance", self.chance)]
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(format!(
+            "numbers lie {:.0}% of the time",
+            self.chance * 100.
+        ))
+    }
+
+    fn check(&self, cells: usize, bombs: usize) -> Result<(), String> {
+        if !(0. ..=1.).contains(&self.chance) {
+            return Err("The chance of a lie has to be from 0 to 1".to_string());
+        }
+        fits(cells, bombs, 1)
+    }
+
+    /// A zero may be a lie, and a real one may not look like one
+    fn cascades(&self) -> bool {
+        self.chance <= 0.
+    }
+
+    fn shown_number(&self, number: i16, rng: &mut dyn RngCore) -> i16 {
+        if !rng.random_bool(self.chance.clamp(0., 1.)) {
+            number
+        } else if number <= 0 || rng.random() {
+            number + 1
+        } else {
+            number - 1
+        }
+    }
+
+    fn solver(&self, board: &Board, bombs: usize) -> Option<Solver> {
+        Some(Solver::with_lies(board, bombs, self.chance))
+    }
+
+    fn lying(&self, chance: f64) -> Option<Arc<dyn Ruleset>> {
+        Some(lying(self.neighborhood, chance))
+    }
+}
+
+/// Multimines: cells hold up to `capacity` bombs, and numbers count every one. Only honest
+/// games deal them, since quantum cells, collapses and the solver still hold one bomb a cell.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Multimines {
+    pub neighborhood: Neighborhood,
+    pub capacity: u8,
+}
+
+impl Ruleset for Multimines {
+    fn name(&self) -> &'static str {
+        "Multimines"
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("capacity", f64::from(self.capacity))]
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(format!("cells hold up to {} bombs", self.capacity))
+    }
+
+    fn check(&self, cells: usize, bombs: usize) -> Result<(), String> {
+        fits(cells, bombs, self.capacity)
+    }
+
+    fn place(&self, cell: Cell) -> Option<Cell> {
+        match cell {
+            Cell::Concrete(n) if n.unsigned_abs() < self.capacity => Some(Cell::Concrete(n + 1)),
+            _ => None,
+        }
+    }
+
+    /// The solver takes every bomb as one bomb
+    fn solver(&self, _board: &Board, _bombs: usize) -> Option<Solver> {
+        None
+    }
+}
+
+/// Anti-mines: `count` of the bombs take one off the numbers around them instead of adding
+/// one, so numbers can go below zero. Only honest games deal them, since quantum cells,
+/// collapses and the solver can't count a bomb as minus one.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AntiMines {
+    pub neighborhood: Neighborhood,
+    pub count: usize,
+}
+
+impl Ruleset for AntiMines {
+    fn name(&self) -> &'static str {
+        "Anti-mines"
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![("count", self.count as f64)]
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(format!("{} of the bombs are anti-mines", self.count))
+    }
+
+    fn check(&self, cells: usize, bombs: usize) -> Result<(), String> {
+        if self.count > bombs {
+            return Err(format!("Only {bombs} bombs can be anti-mines"));
+        }
+        fits(cells, bombs, 1)
+    }
+
+    fn deal(&self, shape: Board, bombs: usize, rng: &mut dyn RngCore) -> Board {
+        let mut board = scatter(shape, bombs, rng, |cell| self.place(cell));
+        let bombs = board
+            .points()
+            .filter(|p| matches!(board[*p], Cell::Concrete(1)))
+            .collect_vec();
+        for p in bombs
+            .choose_multiple(rng, self.count)
+            .copied()
+            .collect_vec()
+        {
+            board[p] = Cell::Concrete(-1);
+        }
+        board
+    }
+
+    /// The solver can't count a bomb as minus one
+    fn solver(&self, _board: &Board, _bombs: usize) -> Option<Solver> {
+        None
+    }
+}
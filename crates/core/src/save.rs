@@ -0,0 +1,245 @@
+//! Games on disk, as JSON with a schema version so old saves can still be read
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    Board, Cell, Game, GameState, Neighborhood,
+    rules::{self, Liar, Ruleset},
+};
+
+/// Bumped whenever a saved game's layout changes
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// A board as it's stored: its size, then its cells a row at a time
+#[derive(Serialize, Deserialize)]
+pub struct BoardData {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Cell>,
+    #[serde(default)]
+    pub wrap: bool,
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    /// Layers the rows are split into, one for a flat board
+    #[serde(default = "one_layer")]
+    pub layers: usize,
+}
+
+fn one_layer() -> usize {
+    1
+}
+
+impl From<Board> for BoardData {
+    fn from(board: Board) -> Self {
+        let (width, height) = board.dim();
+        Self {
+            width,
+            height,
+            cells: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|p| board[p])
+                .collect(),
+            wrap: board.wraps(),
+            neighborhood: board.neighborhood(),
+            layers: board.layers(),
+        }
+    }
+}
+
+impl TryFrom<BoardData> for Board {
+    type Error = String;
+
+    fn try_from(data: BoardData) -> Result<Self, Self::Error> {
+        if data.cells.len() != data.width * data.height {
+            return Err(format!(
+                "{} cells for a {}x{} board",
+                data.cells.len(),
+                data.width,
+                data.height
+            ));
+        }
+        if data.layers == 0 || !data.height.is_multiple_of(data.layers) {
+            return Err(format!(
+                "{} rows don't split into {} layers",
+                data.height, data.layers
+            ));
+        }
+        let mut board = Board::new(data.width, data.height)
+            .with_wrap(data.wrap)
+            .with_neighborhood(data.neighborhood)
+            .with_layers(data.layers);
+        for (i, cell) in data.cells.into_iter().enumerate() {
+            board[(i % data.width, i / data.width)] = cell;
+        }
+        Ok(board)
+    }
+}
+
+/// Everything needed to pick a game back up. Collapse limits belong to the machine, so
+/// they aren't saved, and a seeded game's collapses start over from the seed.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    pub board: Board,
+    pub flags: BTreeSet<(usize, usize)>,
+    pub max_bombs: usize,
+    pub bastard: bool,
+    pub first_click: bool,
+    pub state: GameState,
+    pub seed: Option<u64>,
+    /// The ruleset, missing from saves before schema 4
+    #[serde(default)]
+    pub ruleset: Option<SavedRuleset>,
+    /// Liar mode's chance of a lie, which saves before schema 4 kept instead of the ruleset
+    #[serde(default, skip_serializing)]
+    pub liar: Option<f64>,
+    /// How far off each number is shown
+    #[serde(default)]
+    pub lies: Vec<((usize, usize), i16)>,
+}
+
+/// A ruleset as it's stored: its name and parameters, for [`rules::from_parameters`]. The
+/// neighborhood is the board's.
+#[derive(Serialize, Deserialize)]
+pub struct SavedRuleset {
+    pub name: String,
+    #[serde(default)]
+    pub parameters: BTreeMap<String, f64>,
+}
+
+impl From<&dyn Ruleset> for SavedRuleset {
+    fn from(ruleset: &dyn Ruleset) -> Self {
+        Self {
+            name: ruleset.name().to_string(),
+            parameters: ruleset
+                .parameters()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+}
+
+impl SavedRuleset {
+    /// The ruleset again, counting `neighborhood`, or `None` if this version doesn't know it
+    #[must_use]
+    pub fn ruleset(&self, neighborhood: Neighborhood) -> Option<Arc<dyn Ruleset>> {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|(name, value)| (name.clone(), *value))
+            .collect_vec();
+        rules::from_parameters(&self.name, neighborhood, &parameters)
+    }
+}
+
+impl From<&Game> for SavedGame {
+    fn from(game: &Game) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            board: game.board.clone(),
+            flags: game.flags.iter().copied().collect(),
+            max_bombs: game.max_bombs,
+            bastard: game.bastard,
+            first_click: game.first_click,
+            state: game.state(),
+            seed: game.seed,
+            ruleset: Some(SavedRuleset::from(&*game.ruleset)),
+            liar: None,
+            lies: game
+                .lies
+                .iter()
+                .map(|(cell, lie)| (*cell, *lie))
+                .sorted()
+                .collect(),
+        }
+    }
+}
+
+impl From<SavedGame> for Game {
+    fn from(saved: SavedGame) -> Self {
+        let mut game = Game::new(saved.board, saved.max_bombs, saved.bastard);
+        if let Some(seed) = saved.seed {
+            game = game.with_seed(seed);
+        }
+        game.flags = saved.flags.into_iter().collect();
+        game.first_click = saved.first_click;
+        let neighborhood = game.board.neighborhood();
+        // Saves from_json let through only name rulesets this version knows
+        if let Some(ruleset) = saved
+            .ruleset
+            .and_then(|ruleset| ruleset.ruleset(neighborhood))
+        {
+            game = game.with_ruleset(ruleset);
+        } else if let Some(chance) = saved.liar {
+            game = game.with_ruleset(Arc::new(Liar {
+                neighborhood,
+                chance,
+            }));
+        }
+        game.lies = saved.lies.into_iter().collect();
+        game.win = saved.state == GameState::Won;
+        if let GameState::Lost(cell) = saved.state {
+            game.lose = Some(cell);
+        }
+        game
+    }
+}
+
+impl SavedGame {
+    /// Format as JSON
+    ///
+    /// # Panics
+    /// Never, since every field has a JSON form
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Read a saved game, refusing ones written by a newer version
+    ///
+    /// # Errors
+    /// If `text` isn't a saved game this version understands
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if version > SCHEMA_VERSION {
+            return Err(format!(
+                "saved by a newer version (schema {version}, this reads up to {SCHEMA_VERSION})"
+            ));
+        }
+        let saved: Self = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if let Some(ruleset) = &saved.ruleset
+            && ruleset.ruleset(saved.board.neighborhood()).is_none()
+        {
+            return Err(format!(
+                "played by rules this version doesn't know ({})",
+                ruleset.name
+            ));
+        }
+        Ok(saved)
+    }
+}
+
+/// The bombs in a concrete cell, which schema 1 saved as whether it had one
+pub(crate) fn bombs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i8, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Bombs {
+        Any(bool),
+        Count(i8),
+    }
+    Ok(match Bombs::deserialize(deserializer)? {
+        Bombs::Any(bomb) => i8::from(bomb),
+        Bombs::Count(n) => n,
+    })
+}
@@ -0,0 +1,254 @@
+//! Internal consistency checks that can run on a player's machine
+
+use itertools::Itertools;
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{
+    Board, Cell, CollapseConfig, Game, format_coord,
+    solver::{self, Solver},
+    stats,
+};
+
+/// How one kind of check went
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Check {
+    pub name: &'static str,
+    /// How many times it was checked
+    pub runs: usize,
+    /// What went wrong, one line each
+    pub failures: Vec<String>,
+}
+
+impl Check {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            runs: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Count a run, recording `failure` if there was one
+    fn record(&mut self, failure: Option<String>) {
+        self.runs += 1;
+        self.failures.extend(failure);
+    }
+
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Play `rounds` small bastard games, round-trip `rounds` boards, brute force `rounds`
+/// positions and rate `rounds` games, checking everything along the way
+#[must_use]
+pub fn run(rounds: usize, rng: &mut impl Rng) -> Vec<Check> {
+    let mut checks = [
+        Check::new("numbers are settled after every reveal"),
+        Check::new("numbers never change"),
+        Check::new("collapses stay within the bomb count"),
+        Check::new("solver deductions match the collapsed board"),
+        Check::new("solver agrees with brute force"),
+        Check::new("board text round-trips"),
+        Check::new("canonical form ignores rotation and reflection"),
+        Check::new("collapse config round-trips"),
+        Check::new("ratings move the right way"),
+    ];
+    #[cfg(feature = "serde")]
+    let mut saved = Check::new("saved games round-trip");
+    let config = CollapseConfig::default();
+    for _ in 0..rounds {
+        let [
+            settled,
+            unchanged,
+            within,
+            deductions,
+            brute,
+            text,
+            symmetric,
+            _,
+            rating,
+        ] = &mut checks;
+        let board = play(&config, rng, |before, after, bombs| {
+            settled.record(unsettled(after));
+            unchanged.record(changed_number(before, after));
+            within.record(too_many_bombs(after, bombs));
+            deductions.record(wrong_deduction(after, bombs));
+        });
+        text.record(round_trip(&board));
+        rating.record(wrong_rating(
+            rng.random_range(0. ..3000.),
+            rng.random_range(0. ..3000.),
+        ));
+        #[cfg(feature = "serde")]
+        saved.record(save_round_trip(&board));
+        symmetric.record(asymmetric_canonical_form(&board));
+        // Small enough that every cell but the first click can be brute forced
+        let position = solver::midgame(5, 4, 4, rng).board;
+        let problems = solver::cross_check(&position, 4);
+        brute.record((!problems.is_empty()).then(|| {
+            format!(
+                "{}: {}",
+                position.to_text().trim_end().replace('\n', "/"),
+                problems.join("; ")
+            )
+        }));
+    }
+    let config_text = config.to_config_string();
+    checks[7].record(
+        (CollapseConfig::parse(&config_text) != (config, Vec::new()))
+            .then(|| format!("{config_text:?} parsed differently")),
+    );
+    #[cfg_attr(not(feature = "serde"), allow(unused_mut))]
+    let mut checks = Vec::from(checks);
+    #[cfg(feature = "serde")]
+    checks.push(saved);
+    checks
+}
+
+/// Play an 8x8 bastard game with 10 bombs, revealing a safe cell at random each turn.
+/// `check` sees the board before and after every reveal, and the bomb count.
+/// Returns the last board.
+fn play(
+    config: &CollapseConfig,
+    rng: &mut impl Rng,
+    mut check: impl FnMut(&Board, &Board, usize),
+) -> Board {
+    const BOMBS: usize = 10;
+    let mut game = Game::new(Board::new(8, 8), BOMBS, true);
+    game.config = *config;
+    let mut cell = (rng.random_range(0..8), rng.random_range(0..8));
+    loop {
+        let before = game.board.clone();
+        game.reveal(cell.0, cell.1);
+        check(&before, &game.board, BOMBS);
+        let safe = game
+            .board
+            .points()
+            .filter(|p| matches!(game.board[*p], Cell::Quantum(Some(false))))
+            .collect_vec();
+        let Some(p) = safe.choose(rng) else {
+            return game.board;
+        };
+        cell = *p;
+    }
+}
+
+/// A revealed cell without a number, or whose number could still change
+fn unsettled(board: &Board) -> Option<String> {
+    board.points().find_map(|(x, y)| {
+        let Cell::Discovered(n) = board[(x, y)] else {
+            return None;
+        };
+        let range = board.count_neighboring_bombs(x, y);
+        (n.is_none() || range.start() != range.end()).then(|| {
+            format!(
+                "{} is {n:?} with {range:?} bombs around it",
+                format_coord(x, y)
+            )
+        })
+    })
+}
+
+/// A number that was on `before` and is different on `after`
+fn changed_number(before: &Board, after: &Board) -> Option<String> {
+    before.points().find_map(|p| match (before[p], after[p]) {
+        (Cell::Discovered(Some(a)), Cell::Discovered(b)) if b != Some(a) => Some(format!(
+            "{} changed from {a} to {b:?}",
+            format_coord(p.0, p.1)
+        )),
+        _ => None,
+    })
+}
+
+fn too_many_bombs(board: &Board, bombs: usize) -> Option<String> {
+    let placed = board
+        .iter()
+        .map(|c| usize::from(c.bomb_count().start().unsigned_abs()))
+        .sum::<usize>();
+    (placed > bombs).then(|| format!("{placed} bombs placed out of {bombs}"))
+}
+
+/// A cell the solver is sure about that the board disagrees with.
+/// The board's assignment is one the player can't rule out, so certainties have to hold in it.
+fn wrong_deduction(board: &Board, bombs: usize) -> Option<String> {
+    let placed = board.iter().filter(|c| c.bomb_count() == (1..=1)).count();
+    let open = board
+        .iter()
+        .filter(|c| matches!(c, Cell::Quantum(None)))
+        .count();
+    if placed > bombs || placed + open < bombs {
+        // The total can't be honored, so the solver's answers don't apply
+        return None;
+    }
+    let solver = Solver::new(board, bombs);
+    let decided = |p: &(usize, usize)| match board[*p] {
+        Cell::Quantum(Some(b)) => Some(b),
+        Cell::Concrete(n) => Some(n > 0),
+        _ => None,
+    };
+    let safe = solver.safe_cells().into_iter().map(|p| (p, false));
+    let mines = solver.mines().into_iter().map(|p| (p, true));
+    safe.chain(mines).find_map(|(p, bomb)| {
+        (decided(&p) == Some(!bomb)).then(|| {
+            format!(
+                "solver says {} is {} but the board disagrees",
+                format_coord(p.0, p.1),
+                if bomb { "a bomb" } else { "safe" }
+            )
+        })
+    })
+}
+
+fn round_trip(board: &Board) -> Option<String> {
+    let text = board.to_text();
+    match Board::from_text(&text) {
+        Some(read) if read.to_text() == text && read.dim() == board.dim() => None,
+        Some(read) => Some(format!("{text:?} came back as {:?}", read.to_text())),
+        None => Some(format!("{text:?} couldn't be read")),
+    }
+}
+
+/// A game on `board` that comes back different after saving and loading
+#[cfg(feature = "serde")]
+fn save_round_trip(board: &Board) -> Option<String> {
+    use crate::save::SavedGame;
+
+    let mut game = Game::new(board.clone(), 10, true).with_seed(7);
+    game.flags.insert((0, 0));
+    game.first_click = false;
+    let json = SavedGame::from(&game).to_json();
+    let loaded = match SavedGame::from_json(&json) {
+        Ok(saved) => Game::from(saved),
+        Err(e) => return Some(format!("{json:?} couldn't be read: {e}")),
+    };
+    let same = format!("{:?}", loaded.board) == format!("{:?}", game.board)
+        && loaded.flags == game.flags
+        && loaded.first_click == game.first_click
+        && loaded.state() == game.state()
+        && loaded.seed == game.seed;
+    (!same).then(|| format!("{json:?} came back different"))
+}
+
+/// Anything off about a game between players rated `a` and `b`: chances that don't add up
+/// to one, or a win that costs rating or a loss that gains it
+fn wrong_rating(a: f64, b: f64) -> Option<String> {
+    let chances = stats::expected_score(a, b) + stats::expected_score(b, a);
+    if (chances - 1.).abs() > 1e-9 {
+        return Some(format!("{a} and {b} have chances adding up to {chances}"));
+    }
+    let (won, lost) = (stats::rate(a, b, true), stats::rate(a, b, false));
+    (won < a || lost > a).then(|| format!("{a} against {b} goes to {won} or {lost}"))
+}
+
+/// A rotation or reflection of `board` with a different canonical form
+fn asymmetric_canonical_form(board: &Board) -> Option<String> {
+    let canonical = board.canonical_form().to_text();
+    board
+        .symmetries()
+        .into_iter()
+        .find(|b| b.canonical_form().to_text() != canonical)
+        .map(|b| format!("{:?} and {:?} differ", board.to_text(), b.to_text()))
+}
@@ -0,0 +1,167 @@
+//! Synthetic players, for seeing how bastard mode holds up against different kinds of opponent
+
+use std::time::Duration;
+
+use itertools::Itertools;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+
+use crate::{
+    Board, Cell, CollapseConfig, CollapseTrace, Game, GameState,
+    solver::{Grade, Solver},
+};
+
+/// How a synthetic player picks their next cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Player {
+    /// Reveals a provably safe cell whenever there is one, otherwise the least likely bomb
+    Logician,
+    /// Never proves anything, just reveals the cell its neighboring numbers make look safest
+    Greedy,
+    /// Only spots a number whose bombs are all accounted for, otherwise clicks at random
+    Beginner,
+}
+
+impl Player {
+    pub const ALL: [Self; 3] = [Self::Logician, Self::Greedy, Self::Beginner];
+
+    /// The cell to reveal next on `board`, out of `bombs` in total
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn choose(self, board: &Board, bombs: usize, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        let hidden = hidden(board);
+        match self {
+            Player::Logician => {
+                let solver = Solver::new(board, bombs);
+                solver.safe_cells().first().copied().or_else(|| {
+                    hidden.into_iter().min_by(|a, b| {
+                        solver
+                            .probability(a.0, a.1)
+                            .total_cmp(&solver.probability(b.0, b.1))
+                    })
+                })
+            }
+            Player::Greedy => {
+                let density = bombs as f64 / hidden.len().max(1) as f64;
+                hidden.into_iter().min_by(|a, b| {
+                    local_risk(board, *a, density).total_cmp(&local_risk(board, *b, density))
+                })
+            }
+            Player::Beginner => {
+                let solver = Solver::new(board, bombs);
+                let mines = solver
+                    .mines()
+                    .into_iter()
+                    .filter(|(x, y)| solver.grade(*x, *y) == Some(Grade::Trivial))
+                    .collect_vec();
+                solver
+                    .safe_cells()
+                    .into_iter()
+                    .find(|(x, y)| solver.grade(*x, *y) == Some(Grade::Trivial))
+                    .or_else(|| {
+                        hidden
+                            .into_iter()
+                            .filter(|p| !mines.contains(p))
+                            .collect_vec()
+                            .choose(rng)
+                            .copied()
+                    })
+            }
+        }
+    }
+}
+
+/// How one simulated game went
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Outcome {
+    pub won: bool,
+    /// Cells the player revealed, including the first click
+    pub moves: usize,
+    /// Moves after the first that weren't provably safe
+    pub guesses: usize,
+    /// Collapses the moves took
+    pub collapses: usize,
+    /// How long the collapses took, all told
+    pub collapse_time: Duration,
+}
+
+/// Play one bastard game as `player`
+pub fn play(
+    player: Player,
+    (width, height): (usize, usize),
+    bombs: usize,
+    rng: &mut impl Rng,
+) -> Outcome {
+    run(
+        Game::new(Board::new(width, height), bombs, true),
+        player,
+        rng,
+    )
+}
+
+/// Play one bastard game as `player`, collapsing by `config`. The game and the player's
+/// choices are seeded by `seed`, so each config faces the same games until it collapses
+/// differently.
+#[must_use]
+pub fn play_seeded(
+    player: Player,
+    (width, height): (usize, usize),
+    bombs: usize,
+    config: CollapseConfig,
+    seed: u64,
+) -> Outcome {
+    let mut game = Game::new(Board::new(width, height), bombs, true).with_seed(seed);
+    game.config = config;
+    run(game, player, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Play `game` out as `player`, starting in the middle
+fn run(mut game: Game, player: Player, rng: &mut impl Rng) -> Outcome {
+    let bombs = game.max_bombs;
+    let (width, height) = game.board.dim();
+    let mut outcome = Outcome::default();
+    let mut cell = (width / 2, height / 2);
+    while game.state() == GameState::Playing {
+        if outcome.moves > 0 && !Solver::new(&game.board, bombs).safe_cells().contains(&cell) {
+            outcome.guesses += 1;
+        }
+        let traces = game.reveal(cell.0, cell.1);
+        outcome.collapses += traces.len();
+        outcome.collapse_time += traces.iter().map(CollapseTrace::duration).sum::<Duration>();
+        outcome.moves += 1;
+        let Some(next) = player.choose(&game.board, bombs, rng) else {
+            break;
+        };
+        cell = next;
+    }
+    outcome.won = game.state() == GameState::Won;
+    outcome
+}
+
+/// Cells the player can't see into
+fn hidden(board: &Board) -> Vec<(usize, usize)> {
+    board
+        .points()
+        .filter(|p| !matches!(board[*p], Cell::Discovered(_)))
+        .collect()
+}
+
+/// The worst bomb share among the numbers around `cell`, taking each number on its own.
+/// `density` for a cell next to no numbers.
+#[allow(clippy::cast_precision_loss)]
+fn local_risk(board: &Board, (x, y): (usize, usize), density: f64) -> f64 {
+    board
+        .neighbors(x, y)
+        .filter_map(|(nx, ny, c)| {
+            let Cell::Discovered(Some(n)) = c else {
+                return None;
+            };
+            let hidden = board
+                .neighbors(nx, ny)
+                .filter(|(_, _, c)| !matches!(c, Cell::Discovered(_)))
+                .count();
+            Some(f64::from(*n) / hidden as f64)
+        })
+        .reduce(f64::max)
+        .unwrap_or(density)
+}
@@ -0,0 +1,1331 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
+
+use itertools::Itertools;
+use ndarray::Array2;
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{Board, Cell, format_coord, ln_choose};
+
+/// A revealed number: exactly `bombs` of `cells` are bombs
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Constraint {
+    pub cells: Vec<(usize, usize)>,
+    pub bombs: usize,
+}
+
+/// A cell the player could click, with how good an idea that is
+#[derive(Clone, Copy, Debug)]
+pub struct Guess {
+    pub cell: (usize, usize),
+    /// Chance of not hitting a bomb
+    pub survival: f64,
+    /// Expected bits of information revealed by the cell's number, if it survives
+    pub information: f64,
+}
+
+/// The hardest kind of reasoning needed to decide a cell, easiest first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, clap::ValueEnum)]
+pub enum Grade {
+    /// A single number is already satisfied, or needs all of its unrevealed neighbors
+    Trivial,
+    /// Two overlapping numbers settle it, as in 1-2-1
+    Subset,
+    /// Only considering whole groups of numbers together settles it
+    MultiConstraint,
+}
+
+/// How hard a board is to clear by deduction alone
+#[derive(Clone, Debug, Default)]
+pub struct DifficultyReport {
+    /// Every safe cell revealed, in order, with how it was found
+    pub moves: Vec<((usize, usize), Grade)>,
+    /// How many times the solver had to guess to make progress
+    pub guesses: usize,
+}
+
+impl DifficultyReport {
+    /// How many moves needed each grade
+    #[must_use]
+    pub fn count(&self, grade: Grade) -> usize {
+        self.moves.iter().filter(|(_, g)| *g == grade).count()
+    }
+
+    /// The hardest grade needed anywhere on the board
+    #[must_use]
+    pub fn hardest(&self) -> Option<Grade> {
+        self.moves.iter().map(|(_, g)| *g).max()
+    }
+}
+
+/// How a move looks in hindsight
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    /// The cell could be proven safe
+    Forced,
+    /// Nothing could be proven, and no cell was safer
+    OptimalGuess,
+    /// Nothing could be proven, but a safer cell existed
+    SuboptimalGuess,
+    /// Guessed even though a cell could be proven safe
+    Blunder,
+}
+
+/// A position to practice on, with the deductions it allows
+#[derive(Clone, Debug)]
+pub struct Drill {
+    /// A concrete board with some cells already revealed
+    pub board: Board,
+    pub bombs: usize,
+    /// Every cell that can be proven safe
+    pub answer: Vec<(usize, usize)>,
+    /// Every cell that can be proven to be a bomb
+    pub mines: Vec<(usize, usize)>,
+}
+
+impl Drill {
+    fn new(board: Board, bombs: usize, solver: &Solver) -> Self {
+        Self {
+            board,
+            bombs,
+            answer: solver.safe_cells(),
+            mines: solver.mines(),
+        }
+    }
+}
+
+/// Frontier cells that share constraints, and every way to fill them in
+struct Component {
+    cells: Vec<(usize, usize)>,
+    /// Indexed by bomb count: how many configurations have that many bombs,
+    /// and in how many of those each cell is a bomb
+    by_bombs: Vec<(f64, Vec<f64>)>,
+}
+
+/// Works out what the player can know from the numbers they've uncovered
+pub struct Solver {
+    constraints: Vec<Constraint>,
+    /// Constraints left after removing cells decided by pattern rules
+    reduced: Vec<Constraint>,
+    components: Vec<Component>,
+    /// Unrevealed cells not bordering any number
+    interior: Vec<(usize, usize)>,
+    /// Total bomb count the probabilities were computed with, if it could be honored
+    bombs: Option<usize>,
+    /// Bombs among the cells pattern rules decided
+    known_mines: usize,
+    /// Bomb probability of every cell, 0 for revealed ones
+    probabilities: Array2<f64>,
+    /// Cells that are a bomb in every or no consistent configuration
+    certain: HashMap<(usize, usize), bool>,
+    /// Cells decided by pattern rules, and which rule did it
+    grades: HashMap<(usize, usize), Grade>,
+    /// Chance each number is off by one, in liar mode
+    lies: f64,
+}
+
+impl Solver {
+    /// Analyze `board` from the player's point of view, assuming `bombs` bombs in total.
+    /// Only revealed numbers are used; flags and hidden state are ignored.
+    #[must_use]
+    pub fn new(board: &Board, bombs: usize) -> Self {
+        Self::with_lies(board, bombs, 0.)
+    }
+
+    /// Like [`Solver::new`], for liar mode: each number is off by one with chance `lies`,
+    /// up or down alike, and always up from a zero. Layouts are weighted by how likely
+    /// they make the numbers shown.
+    #[must_use]
+    pub fn with_lies(board: &Board, bombs: usize, lies: f64) -> Self {
+        let constraints = constraints(board);
+        // Cheap local patterns settle most cells, leaving much less to enumerate, but they
+        // take the numbers at their word
+        let (grades, known, reduced) = if lies > 0. {
+            (HashMap::new(), HashMap::new(), constraints.clone())
+        } else {
+            propagate(constraints.clone())
+        };
+        let frontier = reduced
+            .iter()
+            .flat_map(|c| &c.cells)
+            .copied()
+            .collect::<HashSet<_>>();
+        let interior = board
+            .points()
+            .filter(|p| {
+                !matches!(board[*p], Cell::Discovered(_))
+                    && !frontier.contains(p)
+                    && !known.contains_key(p)
+            })
+            .collect_vec();
+        let components = components(&reduced)
+            .into_iter()
+            .map(|cells| {
+                let by_bombs = enumerate(&cells, &reduced, &HashSet::new(), None, lies)
+                    .into_iter()
+                    .map(|(n, bombs, _)| (n, bombs))
+                    .collect();
+                Component { cells, by_bombs }
+            })
+            .collect_vec();
+
+        let mut probabilities = Array2::zeros(board.dim());
+        for (p, bomb) in &known {
+            probabilities[*p] = f64::from(u8::from(*bomb));
+        }
+        let known_mines = known.values().filter(|b| **b).count();
+        let mut solver = Self {
+            constraints,
+            reduced,
+            components,
+            interior,
+            bombs: bombs.checked_sub(known_mines),
+            known_mines,
+            probabilities,
+            certain: known,
+            grades,
+            lies,
+        };
+        // With fewer bombs than the numbers allow (or too many to fit), drop the global count
+        if !solver.weigh() {
+            solver.bombs = None;
+            solver.weigh();
+        }
+        solver
+    }
+
+    /// Fill in probabilities, returning false if no configuration has exactly `bombs` bombs.
+    /// Configuration counts are whole numbers, or in liar mode the same weights summed in the
+    /// same order, so they're compared exactly.
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    fn weigh(&mut self) -> bool {
+        let bombs = self.bombs;
+        let free = self.interior.len();
+        let interior_weight =
+            |frontier_bombs: usize| interior_weight(bombs, frontier_bombs, free, (0, 0));
+        let interior_bombs = |frontier_bombs: usize| -> f64 {
+            bombs.map_or(free as f64 / 2., |b| {
+                b.saturating_sub(frontier_bombs) as f64
+            })
+        };
+        let components = &self.components;
+        let distributions = components
+            .iter()
+            .map(|c| c.by_bombs.iter().map(|(n, _)| *n).collect_vec())
+            .collect_vec();
+        let all = convolve_all(distributions.iter());
+        let total = all
+            .iter()
+            .enumerate()
+            .map(|(k, n)| n * interior_weight(k))
+            .sum::<f64>();
+        if total <= 0. {
+            return false;
+        }
+
+        for (i, component) in components.iter().enumerate() {
+            let others = convolve_all(
+                distributions
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, d)| d),
+            );
+            let weights = (0..component.by_bombs.len())
+                .map(|k| {
+                    others
+                        .iter()
+                        .enumerate()
+                        .map(|(rest, n)| n * interior_weight(k + rest))
+                        .sum::<f64>()
+                })
+                .collect_vec();
+            for (index, cell) in component.cells.iter().enumerate() {
+                let relevant = component
+                    .by_bombs
+                    .iter()
+                    .zip(&weights)
+                    .filter(|((n, _), w)| *n > 0. && **w > 0.);
+                let (mut always, mut never, mut weighted) = (true, true, 0.);
+                for ((n, bombs), w) in relevant {
+                    always &= bombs[index] == *n;
+                    never &= bombs[index] == 0.;
+                    weighted += bombs[index] * w;
+                }
+                self.probabilities[*cell] = weighted / total;
+                if always || never {
+                    self.certain.insert(*cell, always);
+                    self.probabilities[*cell] = if always { 1. } else { 0. };
+                }
+            }
+        }
+
+        if !self.interior.is_empty() {
+            let relevant = all
+                .iter()
+                .enumerate()
+                .filter(|(k, n)| **n > 0. && interior_weight(*k) > 0.)
+                .map(|(k, n)| (interior_bombs(k), n * interior_weight(k)))
+                .collect_vec();
+            let expected = relevant.iter().map(|(b, w)| b * w).sum::<f64>() / total;
+            let mut probability = expected / free as f64;
+            let certainty = if relevant.iter().all(|(b, _)| *b == 0.) {
+                Some(false)
+            } else if relevant.iter().all(|(b, _)| *b == free as f64) {
+                Some(true)
+            } else {
+                None
+            };
+            if let Some(bomb) = certainty {
+                probability = if bomb { 1. } else { 0. };
+            }
+            for cell in &self.interior {
+                self.probabilities[*cell] = probability;
+                if let Some(bomb) = certainty {
+                    self.certain.insert(*cell, bomb);
+                }
+            }
+        }
+        true
+    }
+
+    /// The constraints imposed by revealed numbers
+    #[must_use]
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Each group of undecided frontier cells that share constraints,
+    /// with how many ways there are to fill it in with each number of bombs
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    pub fn component_counts(&self) -> Vec<(&[(usize, usize)], Vec<f64>)> {
+        self.components
+            .iter()
+            .map(|c| {
+                (
+                    c.cells.as_slice(),
+                    c.by_bombs.iter().map(|(n, _)| *n).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// The undecided cells next to numbers, along with those numbers: what's left to think
+    /// about, leaving out cells that are decided or that no number says anything about
+    #[must_use]
+    pub fn active_frontier(&self, board: &Board) -> HashSet<(usize, usize)> {
+        self.components
+            .iter()
+            .flat_map(|c| &c.cells)
+            .flat_map(|&(x, y)| {
+                board
+                    .neighbors(x, y)
+                    .filter(|(_, _, c)| matches!(c, Cell::Discovered(Some(_))))
+                    .map(|(x, y, _)| (x, y))
+                    .chain([(x, y)])
+            })
+            .collect()
+    }
+
+    /// How many undecided cells aren't next to any number
+    #[must_use]
+    pub fn interior_len(&self) -> usize {
+        self.interior.len()
+    }
+
+    /// The natural log of how many ways of placing the bombs fit every revealed number, since
+    /// the count itself gets astronomical. Without a usable bomb total, the cells away from
+    /// the numbers can hold any number of bombs. Negative infinity if nothing fits.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ln_configurations(&self) -> f64 {
+        let distributions = self
+            .components
+            .iter()
+            .map(|c| c.by_bombs.iter().map(|(n, _)| *n).collect_vec())
+            .collect_vec();
+        let free = self.interior.len();
+        let terms = convolve_all(distributions.iter())
+            .into_iter()
+            .enumerate()
+            .filter(|(_, n)| *n > 0.)
+            .filter_map(|(k, n)| {
+                let interior = match self.bombs {
+                    Some(bombs) => {
+                        let rest = bombs.checked_sub(k).filter(|rest| *rest <= free)?;
+                        ln_choose(free, rest)
+                    }
+                    None => free as f64 * std::f64::consts::LN_2,
+                };
+                Some(n.ln() + interior)
+            })
+            .collect_vec();
+        let Some(largest) = terms.iter().copied().reduce(f64::max) else {
+            return f64::NEG_INFINITY;
+        };
+        largest + terms.iter().map(|t| (t - largest).exp()).sum::<f64>().ln()
+    }
+
+    /// Fewest and most bombs the hidden cells can hold in all, by the numbers. The total is
+    /// only taken as a limit, like in bastard mode. `None` if nothing fits.
+    #[must_use]
+    pub fn bomb_range(&self) -> Option<RangeInclusive<usize>> {
+        let distributions = self
+            .components
+            .iter()
+            .map(|c| c.by_bombs.iter().map(|(n, _)| *n).collect_vec())
+            .collect_vec();
+        let (fewest, most) = convolve_all(distributions.iter())
+            .into_iter()
+            .enumerate()
+            .filter(|(_, n)| *n > 0.)
+            .map(|(k, _)| k)
+            .minmax()
+            .into_option()?;
+        let most = self.bombs.map_or(most + self.interior.len(), |bombs| {
+            bombs.min(most + self.interior.len())
+        });
+        Some(self.known_mines + fewest..=self.known_mines + most)
+    }
+
+    /// Bombs not yet accounted for by decided cells, if the total is known
+    #[must_use]
+    pub fn bombs_left(&self) -> Option<usize> {
+        self.bombs
+    }
+
+    /// Chance that a cell is a bomb, 0 for revealed cells
+    #[must_use]
+    pub fn probability(&self, x: usize, y: usize) -> f64 {
+        self.probabilities[(x, y)]
+    }
+
+    /// Bomb probability of every cell, 0 for revealed cells
+    #[must_use]
+    pub fn probabilities(&self) -> &Array2<f64> {
+        &self.probabilities
+    }
+
+    /// Unrevealed cells that can't be bombs
+    #[must_use]
+    pub fn safe_cells(&self) -> Vec<(usize, usize)> {
+        self.certain_cells(false)
+    }
+
+    /// Unrevealed cells that must be bombs
+    #[must_use]
+    pub fn mines(&self) -> Vec<(usize, usize)> {
+        self.certain_cells(true)
+    }
+
+    /// Unrevealed cells that can't be bombs, the ones whose number would tell the player the most first
+    #[must_use]
+    pub fn safe_cells_by_information(&self, board: &Board) -> Vec<(usize, usize)> {
+        self.safe_cells()
+            .into_iter()
+            .map(|(x, y)| ((x, y), self.information(board, x, y)))
+            .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    /// How hard it is to prove whether a cell is a bomb, or `None` if it can't be proven
+    #[must_use]
+    pub fn grade(&self, x: usize, y: usize) -> Option<Grade> {
+        if !self.certain.contains_key(&(x, y)) {
+            return None;
+        }
+        Some(
+            self.grades
+                .get(&(x, y))
+                .copied()
+                .unwrap_or(Grade::MultiConstraint),
+        )
+    }
+
+    fn certain_cells(&self, bomb: bool) -> Vec<(usize, usize)> {
+        self.certain
+            .iter()
+            .filter(|(_, b)| **b == bomb)
+            .map(|(p, _)| *p)
+            .sorted()
+            .collect()
+    }
+
+    /// Every unrevealed cell that might be safe, most likely to survive first,
+    /// ties broken by how much the revealed number would tell the player.
+    #[must_use]
+    pub fn best_guesses(&self, board: &Board) -> Vec<Guess> {
+        board
+            .points()
+            .filter(|p| {
+                !matches!(board[*p], Cell::Discovered(_)) && self.certain.get(p) != Some(&true)
+            })
+            .map(|(x, y)| Guess {
+                cell: (x, y),
+                survival: 1. - self.probabilities[(x, y)],
+                information: self.information(board, x, y),
+            })
+            .sorted_by(|a, b| {
+                b.survival
+                    .total_cmp(&a.survival)
+                    .then(b.information.total_cmp(&a.information))
+            })
+            .collect()
+    }
+
+    /// Entropy in bits of the number that revealing a cell would show,
+    /// over the configurations in which it's safe
+    #[must_use]
+    pub fn information(&self, board: &Board, x: usize, y: usize) -> f64 {
+        -self
+            .outcomes(board, x, y)
+            .iter()
+            .filter(|p| **p > 0.)
+            .map(|p| p * p.log2())
+            .sum::<f64>()
+    }
+
+    /// Chance of each number a cell would show if revealed, given that it's safe.
+    /// Empty if the cell can't be safe.
+    #[must_use]
+    pub fn outcomes(&self, board: &Board, x: usize, y: usize) -> Vec<f64> {
+        // Decided neighbors only shift the number, so they're left out of the enumeration
+        let near = board
+            .neighbors(x, y)
+            .filter(|(x, y, c)| {
+                !matches!(c, Cell::Discovered(_)) && !self.certain.contains_key(&(*x, *y))
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect::<HashSet<_>>();
+        // Joint distribution of bombs in the frontier and bombs next to the cell
+        let joint = self.components.iter().fold(vec![vec![1.]], |acc, c| {
+            let local = if c.cells.iter().any(|p| near.contains(p) || *p == (x, y)) {
+                enumerate(&c.cells, &self.reduced, &near, Some((x, y)), self.lies)
+                    .into_iter()
+                    .map(|(_, _, by_near)| by_near)
+                    .collect()
+            } else {
+                c.by_bombs.iter().map(|(n, _)| vec![*n]).collect_vec()
+            };
+            convolve_joint(&acc, &local)
+        });
+        let interior_near = self.interior.iter().filter(|p| near.contains(p)).count();
+        let free = self.interior.len() - usize::from(self.interior.contains(&(x, y)));
+
+        let width = joint.iter().map(Vec::len).max().unwrap_or(1);
+        let mut numbers = vec![0.; width + interior_near];
+        for (frontier_bombs, row) in joint.iter().enumerate() {
+            for (frontier_near, n) in row.iter().enumerate() {
+                for split in 0..=interior_near {
+                    numbers[frontier_near + split] += n * interior_weight(
+                        self.bombs,
+                        frontier_bombs,
+                        free,
+                        (interior_near, split),
+                    );
+                }
+            }
+        }
+        let total = numbers.iter().sum::<f64>();
+        if total == 0. {
+            return Vec::new();
+        }
+        let mines = board
+            .neighbors(x, y)
+            .filter(|(x, y, _)| self.certain.get(&(*x, *y)) == Some(&true))
+            .count();
+        std::iter::repeat_n(0., mines)
+            .chain(numbers.iter().map(|n| n / total))
+            .collect()
+    }
+}
+
+/// Relative number of ways to place the remaining bombs in the interior,
+/// with `split` bombs among `near` particular interior cells and the rest elsewhere.
+/// `free` is the number of interior cells available.
+#[allow(clippy::cast_precision_loss)]
+fn interior_weight(
+    bombs: Option<usize>,
+    frontier_bombs: usize,
+    free: usize,
+    (near, split): (usize, usize),
+) -> f64 {
+    let Some(bombs) = bombs else {
+        return (ln_choose(near, split) - near as f64 * std::f64::consts::LN_2).exp();
+    };
+    match bombs.checked_sub(frontier_bombs + split) {
+        Some(rest) if split <= near && rest <= free - near => {
+            (ln_choose(near, split) + ln_choose(free - near, rest) - ln_choose(free, free / 2))
+                .exp()
+        }
+        _ => 0.,
+    }
+}
+
+/// Most unrevealed cells [`BruteForce`] will try every assignment of
+pub const BRUTE_FORCE_LIMIT: usize = 20;
+
+/// Bomb probabilities found by trying every way of placing the bombs in every unrevealed cell.
+/// Slow, but simple enough to trust when checking [`Solver`] on small boards.
+pub struct BruteForce {
+    /// Unrevealed cells, in the order their bits appear in an assignment
+    cells: Vec<(usize, usize)>,
+    /// How many consistent assignments there are
+    worlds: usize,
+    /// In how many of them each cell is a bomb
+    bombs: Vec<usize>,
+}
+
+impl BruteForce {
+    /// `None` if there are more than [`BRUTE_FORCE_LIMIT`] unrevealed cells
+    #[must_use]
+    pub fn new(board: &Board, bombs: usize) -> Option<Self> {
+        let cells = board
+            .points()
+            .filter(|p| !matches!(board[*p], Cell::Discovered(_)))
+            .collect_vec();
+        if cells.len() > BRUTE_FORCE_LIMIT {
+            return None;
+        }
+        let index = cells
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (*p, i))
+            .collect::<HashMap<_, _>>();
+        let masks = constraints(board)
+            .into_iter()
+            .map(|c| {
+                (
+                    c.cells.iter().map(|p| 1u32 << index[p]).sum::<u32>(),
+                    c.bombs,
+                )
+            })
+            .collect_vec();
+        let mut counts = vec![0; cells.len()];
+        let mut worlds = 0;
+        for world in 0..1u32 << cells.len() {
+            if world.count_ones() as usize != bombs
+                || masks
+                    .iter()
+                    .any(|(mask, n)| (world & mask).count_ones() as usize != *n)
+            {
+                continue;
+            }
+            worlds += 1;
+            for (i, count) in counts.iter_mut().enumerate() {
+                *count += (world >> i & 1) as usize;
+            }
+        }
+        Some(Self {
+            cells,
+            worlds,
+            bombs: counts,
+        })
+    }
+
+    /// How many ways there are to place the bombs that fit every number
+    #[must_use]
+    pub fn worlds(&self) -> usize {
+        self.worlds
+    }
+
+    /// Every unrevealed cell with its bomb probability, or nothing if no placement fits
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn probabilities(&self) -> Vec<((usize, usize), f64)> {
+        if self.worlds == 0 {
+            return Vec::new();
+        }
+        self.cells
+            .iter()
+            .zip(&self.bombs)
+            .map(|(p, n)| (*p, *n as f64 / self.worlds as f64))
+            .collect()
+    }
+}
+
+/// Everything [`Solver`] and [`Board::assignment_is_legal`] get wrong about `board`, according
+/// to [`BruteForce`]. Empty if they agree, or if the board is too big to brute force.
+#[must_use]
+pub fn cross_check(board: &Board, bombs: usize) -> Vec<String> {
+    let Some(brute) = BruteForce::new(board, bombs) else {
+        return Vec::new();
+    };
+    let mut problems = Vec::new();
+    if brute.worlds() == 0 {
+        return problems;
+    }
+    let solver = Solver::new(board, bombs);
+    let safe = solver.safe_cells();
+    let mines = solver.mines();
+    // What the player sees: numbers, and nothing decided about the rest
+    let mut seen = board.clone();
+    for p in &brute.cells {
+        seen[*p] = Cell::Quantum(None);
+    }
+    #[allow(clippy::cast_precision_loss)]
+    for (&(x, y), &n) in brute.cells.iter().zip(&brute.bombs) {
+        let coord = format_coord(x, y);
+        let expected = n as f64 / brute.worlds as f64;
+        let got = solver.probability(x, y);
+        if (got - expected).abs() > 1e-9 {
+            problems.push(format!(
+                "{coord} has bomb chance {got}, should be {expected}"
+            ));
+        }
+        if safe.contains(&(x, y)) != (n == 0) {
+            problems.push(format!("{coord} safe should be {}", n == 0));
+        }
+        if mines.contains(&(x, y)) != (n == brute.worlds) {
+            problems.push(format!("{coord} mine should be {}", n == brute.worlds));
+        }
+        for (value, possible) in [(false, n < brute.worlds), (true, n > 0)] {
+            if possible && !seen.assignment_is_legal(x, y, value) {
+                problems.push(format!(
+                    "{coord} can be {value} but the assignment is called illegal"
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Clear a board with hidden bombs by deduction, starting by revealing `start`.
+/// When stuck, the safest cell that really is safe gets revealed and counted as a guess.
+#[must_use]
+pub fn grade_board(board: &Board, bombs: usize, start: (usize, usize)) -> DifficultyReport {
+    let mut board = board.clone();
+    let mut report = DifficultyReport::default();
+    if board[start].is_bomb() {
+        return report;
+    }
+    reveal(&mut board, start);
+    loop {
+        let solver = Solver::new(&board, bombs);
+        let safe = solver.safe_cells();
+        if !safe.is_empty() {
+            for (x, y) in safe {
+                let grade = solver.grade(x, y).unwrap_or(Grade::MultiConstraint);
+                report.moves.push(((x, y), grade));
+                reveal(&mut board, (x, y));
+            }
+            continue;
+        }
+        let Some(guess) = solver
+            .best_guesses(&board)
+            .into_iter()
+            .find(|g| !board[g.cell].is_bomb())
+        else {
+            return report;
+        };
+        report.guesses += 1;
+        reveal(&mut board, guess.cell);
+    }
+}
+
+/// Clear a board with hidden bombs by deduction like [`grade_board`], but only count the times
+/// the best odds on offer are no better than a coin flip. Guesses go to the least likely bomb
+/// that really is safe, without ranking them by information, which keeps big boards quick.
+#[must_use]
+pub fn fifty_fifties(board: &Board, bombs: usize, start: (usize, usize)) -> usize {
+    let mut board = board.clone();
+    let mut count = 0;
+    if board[start].is_bomb() {
+        return count;
+    }
+    reveal(&mut board, start);
+    loop {
+        let solver = Solver::new(&board, bombs);
+        let safe = solver.safe_cells();
+        if !safe.is_empty() {
+            for p in safe {
+                reveal(&mut board, p);
+            }
+            continue;
+        }
+        let risk = |(x, y): &(usize, usize)| solver.probability(*x, *y);
+        let hidden = board
+            .points()
+            .filter(|p| !matches!(board[*p], Cell::Discovered(_)))
+            .collect_vec();
+        let Some(guess) = hidden
+            .iter()
+            .filter(|p| !board[**p].is_bomb())
+            .min_by(|a, b| risk(a).total_cmp(&risk(b)))
+        else {
+            return count;
+        };
+        if hidden.iter().map(risk).all(|p| p >= 0.5 - 1e-9) {
+            count += 1;
+        }
+        reveal(&mut board, *guess);
+    }
+}
+
+/// How hard the unrevealed cells next to numbers are to decide, from 0 when every one follows
+/// from a single number to 1 when none can be proven either way
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn opening_difficulty(board: &Board, bombs: usize) -> f64 {
+    let solver = Solver::new(board, bombs);
+    let border = board
+        .points()
+        .filter(|(x, y)| {
+            !matches!(board[(*x, *y)], Cell::Discovered(_))
+                && board
+                    .neighbors(*x, *y)
+                    .any(|(_, _, c)| matches!(c, Cell::Discovered(Some(_))))
+        })
+        .collect_vec();
+    if border.is_empty() {
+        return 0.;
+    }
+    let hardness = border
+        .iter()
+        .map(|(x, y)| match solver.grade(*x, *y) {
+            Some(Grade::Trivial) => 0.,
+            Some(Grade::Subset) => 1. / 3.,
+            Some(Grade::MultiConstraint) => 2. / 3.,
+            None => 1.,
+        })
+        .sum::<f64>();
+    hardness / border.len() as f64
+}
+
+/// Judge clicking `cell` on `board`, as it was before the click, by what `solver` made of it
+#[must_use]
+pub fn review(solver: &Solver, board: &Board, cell: (usize, usize)) -> Verdict {
+    let safe = solver.safe_cells();
+    if safe.contains(&cell) {
+        return Verdict::Forced;
+    }
+    if !safe.is_empty() {
+        return Verdict::Blunder;
+    }
+    let safest = board
+        .points()
+        .filter(|p| !matches!(board[*p], Cell::Discovered(_)))
+        .map(|(x, y)| solver.probability(x, y))
+        .fold(1., f64::min);
+    // Leave room for rounding, since probabilities of equally good cells are computed separately
+    if solver.probability(cell.0, cell.1) <= safest + 1e-9 {
+        Verdict::OptimalGuess
+    } else {
+        Verdict::SuboptimalGuess
+    }
+}
+
+/// Play random `width` by `height` boards out from the middle until reaching a position
+/// where the easiest forced move is exactly `grade`
+#[must_use]
+pub fn drill(grade: Grade, width: usize, height: usize, bombs: usize, rng: &mut impl Rng) -> Drill {
+    let start = (width / 2, height / 2);
+    loop {
+        let mut board = random_board(width, height, bombs, start, rng);
+        reveal(&mut board, start);
+        loop {
+            let solver = Solver::new(&board, bombs);
+            let safe = solver.safe_cells();
+            let Some(easiest) = safe.iter().filter_map(|&(x, y)| solver.grade(x, y)).min() else {
+                break;
+            };
+            if easiest == grade {
+                return Drill::new(board, bombs, &solver);
+            }
+            if easiest > grade {
+                break;
+            }
+            for p in safe {
+                if solver.grade(p.0, p.1) == Some(easiest) {
+                    reveal(&mut board, p);
+                }
+            }
+        }
+    }
+}
+
+/// Play a random board out from the middle, stopping at a random point where
+/// at least one move can be proven safe
+#[must_use]
+pub fn midgame(width: usize, height: usize, bombs: usize, rng: &mut impl Rng) -> Drill {
+    let start = (width / 2, height / 2);
+    loop {
+        let mut board = random_board(width, height, bombs, start, rng);
+        reveal(&mut board, start);
+        loop {
+            let solver = Solver::new(&board, bombs);
+            let safe = solver.safe_cells();
+            if safe.is_empty() {
+                break;
+            }
+            if rng.random_bool(0.25) {
+                return Drill::new(board, bombs, &solver);
+            }
+            for p in safe {
+                reveal(&mut board, p);
+            }
+        }
+    }
+}
+
+/// How a generated bomb layout mirrors itself
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Symmetry {
+    /// The same after a half turn
+    Half,
+    /// The same after a quarter turn, only on square boards
+    Quarter,
+}
+
+impl Symmetry {
+    /// Every cell that has to match `(x, y)`, including itself
+    fn orbit(self, (x, y): (usize, usize), (width, height): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut cells = vec![(x, y), (width - 1 - x, height - 1 - y)];
+        if self == Symmetry::Quarter {
+            cells.extend([(height - 1 - y, x), (y, width - 1 - x)]);
+        }
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+}
+
+/// A concrete board whose bombs are laid out with `symmetry`, that can be cleared from the
+/// middle without guessing, with the middle already revealed. `bombs` gets rounded down to
+/// what fits the symmetry; the board is returned with how many it really has.
+/// `None` for a quarter turn on a board that isn't square.
+#[must_use]
+pub fn symmetric_puzzle(
+    symmetry: Symmetry,
+    width: usize,
+    height: usize,
+    bombs: usize,
+    rng: &mut impl Rng,
+) -> Option<(Board, usize)> {
+    if symmetry == Symmetry::Quarter && width != height {
+        return None;
+    }
+    let start = (width / 2, height / 2);
+    let orbits = Board::new(width, height)
+        .points()
+        .map(|p| symmetry.orbit(p, (width, height)))
+        .filter(|orbit| {
+            orbit
+                .iter()
+                .all(|p| p.0.abs_diff(start.0) > 1 || p.1.abs_diff(start.1) > 1)
+        })
+        .unique()
+        .collect_vec();
+    loop {
+        let mut board = Board::new(width, height);
+        for p in board.points().collect_vec() {
+            board[p] = Cell::Concrete(0);
+        }
+        let mut placed = 0;
+        for orbit in orbits.choose_multiple(rng, orbits.len()) {
+            if placed + orbit.len() <= bombs {
+                for p in orbit {
+                    board[*p] = Cell::Concrete(1);
+                }
+                placed += orbit.len();
+            }
+        }
+        if solvable(&board, placed, start) {
+            reveal(&mut board, start);
+            return Some((board, placed));
+        }
+    }
+}
+
+/// A concrete board with `bombs` bombs that can be cleared from the middle without guessing,
+/// with the middle already revealed
+#[must_use]
+pub fn no_guess_board(width: usize, height: usize, bombs: usize, rng: &mut impl Rng) -> Board {
+    let start = (width / 2, height / 2);
+    loop {
+        let mut board = random_board(width, height, bombs, start, rng);
+        if solvable(&board, bombs, start) {
+            reveal(&mut board, start);
+            return board;
+        }
+    }
+}
+
+/// Whether a board with hidden bombs can be cleared by deduction alone, starting by revealing
+/// `start`. Quicker than asking [`grade_board`] for no guesses, since it stops when stuck.
+#[must_use]
+pub fn solvable(board: &Board, bombs: usize, start: (usize, usize)) -> bool {
+    let mut board = board.clone();
+    if board[start].is_bomb() {
+        return false;
+    }
+    reveal(&mut board, start);
+    loop {
+        let safe = Solver::new(&board, bombs).safe_cells();
+        if safe.is_empty() {
+            return board
+                .iter()
+                .all(|c| c.is_bomb() || matches!(c, Cell::Discovered(_)));
+        }
+        for p in safe {
+            reveal(&mut board, p);
+        }
+    }
+}
+
+/// A concrete board with `bombs` bombs, none of them next to `start`
+fn random_board(
+    width: usize,
+    height: usize,
+    bombs: usize,
+    start: (usize, usize),
+    rng: &mut impl Rng,
+) -> Board {
+    let mut board = Board::new(width, height);
+    for p in board.points().collect_vec() {
+        board[p] = Cell::Concrete(0);
+    }
+    let mut to_place = bombs;
+    while to_place > 0 {
+        let p = (rng.random_range(0..width), rng.random_range(0..height));
+        let near_start = p.0.abs_diff(start.0) <= 1 && p.1.abs_diff(start.1) <= 1;
+        if !near_start && !board[p].is_bomb() {
+            board[p] = Cell::Concrete(1);
+            to_place -= 1;
+        }
+    }
+    board
+}
+
+/// Reveal a safe cell on a concrete board
+fn reveal(board: &mut Board, (x, y): (usize, usize)) {
+    let n = *board.count_neighboring_bombs(x, y).start();
+    board[(x, y)] = Cell::Discovered(Some(n));
+}
+
+/// Every revealed number that still has unrevealed neighbors
+#[must_use]
+pub fn constraints(board: &Board) -> Vec<Constraint> {
+    board
+        .points()
+        .filter_map(|(x, y)| {
+            let Cell::Discovered(Some(n)) = board[(x, y)] else {
+                return None;
+            };
+            let cells = board
+                .neighbors(x, y)
+                .filter(|(_, _, c)| !matches!(c, Cell::Discovered(_)))
+                .map(|(x, y, _)| (x, y))
+                .collect_vec();
+            // Anti-mines aren't modelled, so their negative numbers say nothing here
+            let bombs = usize::try_from(n).ok()?;
+            (!cells.is_empty()).then_some(Constraint { cells, bombs })
+        })
+        .collect()
+}
+
+/// Apply cheap local rules until nothing changes, returning the cells they decide
+/// (and which rule decided them) and the constraints left over once those cells are removed
+#[allow(clippy::type_complexity)]
+fn propagate(
+    mut constraints: Vec<Constraint>,
+) -> (
+    HashMap<(usize, usize), Grade>,
+    HashMap<(usize, usize), bool>,
+    Vec<Constraint>,
+) {
+    let mut grades = HashMap::new();
+    let mut known = HashMap::new();
+    loop {
+        let mut found = trivial(&constraints);
+        let mut grade = Grade::Trivial;
+        if found.is_empty() {
+            found = pairs(&constraints);
+            grade = Grade::Subset;
+        }
+        if found.is_empty() {
+            return (grades, known, constraints);
+        }
+        for (p, bomb) in found {
+            known.insert(p, bomb);
+            grades.entry(p).or_insert(grade);
+        }
+        constraints = reduce(constraints, &known);
+    }
+}
+
+/// A number with no bombs left around it, or as many as it has unrevealed neighbors
+fn trivial(constraints: &[Constraint]) -> Vec<((usize, usize), bool)> {
+    constraints
+        .iter()
+        .filter_map(|c| {
+            if c.bombs == 0 {
+                Some((c, false))
+            } else if c.bombs == c.cells.len() {
+                Some((c, true))
+            } else {
+                None
+            }
+        })
+        .flat_map(|(c, bomb)| c.cells.iter().map(move |p| (*p, bomb)))
+        .collect()
+}
+
+/// Two overlapping numbers whose difference settles the cells only one of them touches.
+/// This covers subsets, 1-2-1, 1-2-2-1 and the usual edge reductions.
+fn pairs(constraints: &[Constraint]) -> Vec<((usize, usize), bool)> {
+    let mut by_cell = HashMap::<(usize, usize), Vec<usize>>::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for p in &c.cells {
+            by_cell.entry(*p).or_default().push(i);
+        }
+    }
+    let overlapping = by_cell
+        .values()
+        .flat_map(|ids| ids.iter().copied().tuple_combinations())
+        .collect::<HashSet<(usize, usize)>>();
+    let mut found = vec![];
+    for (i, j) in overlapping.into_iter().sorted() {
+        let (a, b) = (&constraints[i], &constraints[j]);
+        let shared = a.cells.iter().filter(|p| b.cells.contains(p)).count();
+        let only_a = a.cells.len() - shared;
+        let only_b = b.cells.len() - shared;
+        // Range of bombs the shared cells can hold
+        let most = shared.min(a.bombs).min(b.bombs);
+        let least = a
+            .bombs
+            .saturating_sub(only_a)
+            .max(b.bombs.saturating_sub(only_b));
+        if least > most {
+            continue;
+        }
+        for (c, other, only) in [(a, b, only_a), (b, a, only_b)] {
+            let bomb = if c.bombs <= least {
+                false
+            } else if c.bombs - most == only {
+                true
+            } else {
+                continue;
+            };
+            if only > 0 {
+                found.extend(
+                    c.cells
+                        .iter()
+                        .filter(|p| !other.cells.contains(p))
+                        .map(|p| (*p, bomb)),
+                );
+            }
+        }
+    }
+    found
+}
+
+/// Remove decided cells from constraints, dropping the ones with nothing left to decide
+fn reduce(constraints: Vec<Constraint>, known: &HashMap<(usize, usize), bool>) -> Vec<Constraint> {
+    constraints
+        .into_iter()
+        .filter_map(|c| {
+            let mines = c
+                .cells
+                .iter()
+                .filter(|p| known.get(p) == Some(&true))
+                .count();
+            let cells = c
+                .cells
+                .into_iter()
+                .filter(|p| !known.contains_key(p))
+                .collect_vec();
+            (!cells.is_empty()).then(|| Constraint {
+                cells,
+                bombs: c.bombs.saturating_sub(mines),
+            })
+        })
+        .unique()
+        .collect()
+}
+
+/// Group constrained cells into sets that don't share any constraint
+fn components(constraints: &[Constraint]) -> Vec<Vec<(usize, usize)>> {
+    let mut by_cell = HashMap::<(usize, usize), Vec<usize>>::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for cell in &c.cells {
+            by_cell.entry(*cell).or_default().push(i);
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut components = vec![];
+    for start in by_cell.keys().sorted() {
+        if !seen.insert(*start) {
+            continue;
+        }
+        let mut component = vec![];
+        let mut queue = vec![*start];
+        while let Some(cell) = queue.pop() {
+            component.push(cell);
+            for c in &by_cell[&cell] {
+                for next in &constraints[*c].cells {
+                    if seen.insert(*next) {
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Count the configurations of a component by bomb count, along with how often each cell
+/// is a bomb and how many bombs are in `near`. Configurations where `safe` is a bomb are skipped.
+fn enumerate(
+    cells: &[(usize, usize)],
+    constraints: &[Constraint],
+    near: &HashSet<(usize, usize)>,
+    safe: Option<(usize, usize)>,
+    lies: f64,
+) -> Vec<(f64, Vec<f64>, Vec<f64>)> {
+    let index = cells
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (*c, i))
+        .collect::<HashMap<_, _>>();
+    let local = constraints
+        .iter()
+        .filter(|c| index.contains_key(&c.cells[0]))
+        .map(|c| (c.cells.iter().map(|p| index[p]).collect_vec(), c.bombs))
+        .collect_vec();
+    let mut touching = vec![vec![]; cells.len()];
+    for (i, (members, _)) in local.iter().enumerate() {
+        for m in members {
+            touching[*m].push(i);
+        }
+    }
+    let near = cells.iter().map(|p| near.contains(p)).collect_vec();
+    let mut search = Search {
+        constraints: &local,
+        touching: &touching,
+        near: &near,
+        safe: safe.and_then(|p| index.get(&p).copied()),
+        lies,
+        placed: vec![0; local.len()],
+        unassigned: local.iter().map(|(m, _)| m.len()).collect(),
+        assignment: Vec::with_capacity(cells.len()),
+        by_bombs: vec![(0., vec![0.; cells.len()], vec![0.; cells.len() + 1]); cells.len() + 1],
+    };
+    search.run();
+    search.by_bombs
+}
+
+struct Search<'a> {
+    constraints: &'a [(Vec<usize>, usize)],
+    touching: &'a [Vec<usize>],
+    near: &'a [bool],
+    safe: Option<usize>,
+    /// Chance each number is off by one, letting the bombs around it miss it by one too
+    lies: f64,
+    placed: Vec<usize>,
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    by_bombs: Vec<(f64, Vec<f64>, Vec<f64>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self) {
+        let i = self.assignment.len();
+        if i == self.touching.len() {
+            let bombs = self.assignment.iter().filter(|b| **b).count();
+            let near = self
+                .assignment
+                .iter()
+                .zip(self.near)
+                .filter(|(b, n)| **b && **n)
+                .count();
+            let weight = if self.lies > 0. {
+                self.constraints
+                    .iter()
+                    .zip(&self.placed)
+                    .map(|((_, shown), bombs)| likelihood(*shown, *bombs, self.lies))
+                    .product()
+            } else {
+                1.
+            };
+            let (count, cells, by_near) = &mut self.by_bombs[bombs];
+            *count += weight;
+            by_near[near] += weight;
+            for (cell, bomb) in cells.iter_mut().zip(&self.assignment) {
+                if *bomb {
+                    *cell += weight;
+                }
+            }
+            return;
+        }
+        for value in [false, true] {
+            if value && self.safe == Some(i) {
+                continue;
+            }
+            for c in &self.touching[i] {
+                self.unassigned[*c] -= 1;
+                self.placed[*c] += usize::from(value);
+            }
+            let slack = usize::from(self.lies > 0.);
+            let feasible = self.touching[i].iter().all(|c| {
+                let wanted = self.constraints[*c].1;
+                self.placed[*c] <= wanted + slack
+                    && self.placed[*c] + self.unassigned[*c] + slack >= wanted
+            });
+            if feasible {
+                self.assignment.push(value);
+                self.run();
+                self.assignment.pop();
+            }
+            for c in &self.touching[i] {
+                self.unassigned[*c] += 1;
+                self.placed[*c] -= usize::from(value);
+            }
+        }
+    }
+}
+
+/// Chance a number with `bombs` around it shows `shown` in liar mode, where it's off by one
+/// with chance `lies`
+fn likelihood(shown: usize, bombs: usize, lies: f64) -> f64 {
+    match shown.abs_diff(bombs) {
+        0 => 1. - lies,
+        1 if bombs == 0 => lies,
+        1 => lies / 2.,
+        _ => 0.,
+    }
+}
+
+/// Distribution of total bomb count over independent components
+pub(crate) fn convolve_all<'a>(distributions: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    distributions.fold(vec![1.], |acc, d| {
+        let mut out = vec![0.; acc.len() + d.len() - 1];
+        for (i, a) in acc.iter().enumerate() {
+            for (j, b) in d.iter().enumerate() {
+                out[i + j] += a * b;
+            }
+        }
+        out
+    })
+}
+
+/// Like `convolve_all`, but carrying along a second count
+fn convolve_joint(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let width = a.iter().chain(b).map(Vec::len).max().unwrap_or(1);
+    let mut out = vec![vec![0.; width * 2]; a.len() + b.len() - 1];
+    for (i, row_a) in a.iter().enumerate() {
+        for (j, row_b) in b.iter().enumerate() {
+            for (k, x) in row_a.iter().enumerate() {
+                for (l, y) in row_b.iter().enumerate() {
+                    out[i + j][k + l] += x * y;
+                }
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,104 @@
+//! How bombs are spread over the board: scattered at random, spread out evenly or in clumps
+
+use itertools::Itertools;
+use rand::{Rng, distr::weighted::WeightedIndex, prelude::Distribution};
+
+use crate::{Board, Cell, policy::State};
+
+/// How much likelier each bomb around a cell makes a bomb there, or unlikelier when spreading
+/// evenly
+const CLUSTERING: f64 = 2.;
+
+/// How bombs sit relative to each other
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Spread {
+    /// Each cell as likely as any other, whatever is around it
+    #[default]
+    Random,
+    /// Bombs keep away from each other, like blue noise
+    Even,
+    /// Bombs gather in clumps
+    Clumped,
+}
+
+impl Spread {
+    /// How much more likely a bomb is for each bomb around it, in log terms
+    fn pull(self) -> f64 {
+        match self {
+            Spread::Random => 0.,
+            Spread::Even => -CLUSTERING.ln(),
+            Spread::Clumped => CLUSTERING.ln(),
+        }
+    }
+
+    /// `board` with `max_bombs` placed one after another on its `Concrete(0)` cells, each
+    /// weighted by the bombs already around it
+    #[allow(clippy::cast_precision_loss)]
+    pub fn place(self, mut board: Board, max_bombs: usize, rng: &mut impl Rng) -> Board {
+        let mut free = board
+            .points()
+            .filter(|p| matches!(board[*p], Cell::Concrete(0)))
+            .collect_vec();
+        for _ in 0..max_bombs.min(free.len()) {
+            let weights = free
+                .iter()
+                .map(|&(x, y)| (self.pull() * bombs_around(&board, x, y) as f64).exp())
+                .collect_vec();
+            let Ok(index) = WeightedIndex::new(weights) else {
+                break;
+            };
+            let p = free.swap_remove(index.sample(rng));
+            board[p] = Cell::Concrete(1);
+        }
+        board
+    }
+
+    /// Draw as many of `states` again as there are, each as likely as it is under this spread,
+    /// so a collapse picking among them follows it. `board` has `frontier` unassigned.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn resample(
+        self,
+        board: &Board,
+        frontier: &[(usize, usize)],
+        states: Vec<State>,
+        rng: &mut impl Rng,
+    ) -> Vec<State> {
+        if self == Spread::Random || states.len() < 2 {
+            return states;
+        }
+        let mut board = board.clone();
+        let scores = states
+            .iter()
+            .map(|state| {
+                for (c, b) in frontier.iter().zip(state) {
+                    board[*c] = Cell::Quantum(Some(*b));
+                }
+                // Pairs of bombs on the frontier count twice, which only makes it stronger
+                let touching: usize = frontier
+                    .iter()
+                    .zip(state)
+                    .filter(|(_, b)| **b)
+                    .map(|(&(x, y), _)| bombs_around(&board, x, y))
+                    .sum();
+                self.pull() * touching as f64
+            })
+            .collect_vec();
+        let best = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let Ok(index) = WeightedIndex::new(scores.iter().map(|s| (s - best).exp())) else {
+            return states;
+        };
+        (0..states.len())
+            .map(|_| states[index.sample(rng)].clone())
+            .collect()
+    }
+}
+
+/// Decided bombs around `(x, y)`
+fn bombs_around(board: &Board, x: usize, y: usize) -> usize {
+    board
+        .neighbors(x, y)
+        .filter(|(_, _, c)| matches!(c, Cell::Concrete(1..) | Cell::Quantum(Some(true))))
+        .count()
+}
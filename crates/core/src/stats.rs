@@ -0,0 +1,118 @@
+//! Targets and rewards for a game, worked out the same way by every frontend
+
+use std::time::Duration;
+
+use crate::BoardStatistics;
+
+/// 3BV a second for par times, about a steady beginner's pace
+pub const DEFAULT_SKILL: f64 = 1.;
+
+/// How long clearing a board of `three_bv` takes at `skill` 3BV a second
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn par_time(three_bv: usize, skill: f64) -> Duration {
+    // Slower than this isn't a target
+    Duration::from_secs_f64(three_bv as f64 / skill.max(0.01))
+}
+
+/// What a win is worth against par
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Medal {
+    Gold,
+    Silver,
+    Bronze,
+}
+
+impl Medal {
+    /// Gold within par, silver within half as long again, bronze within twice par
+    #[must_use]
+    pub fn award(time: Duration, par: Duration) -> Option<Self> {
+        [(Medal::Gold, 1.), (Medal::Silver, 1.5), (Medal::Bronze, 2.)]
+            .into_iter()
+            .find(|(_, multiple)| time <= par.mul_f64(*multiple))
+            .map(|(medal, _)| medal)
+    }
+}
+
+/// Where a new player's rating starts
+pub const INITIAL_RATING: f64 = 1200.;
+/// Most a rating moves after one game
+const K_FACTOR: f64 = 32.;
+
+/// A board's strength as an opponent: more clicks and more coin flips make it stronger
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn board_rating(statistics: &BoardStatistics) -> f64 {
+    1000. + 4. * statistics.three_bv as f64 + 200. * statistics.fifty_fifties as f64
+}
+
+/// Chance a player rated `rating` beats an opponent rated `opponent`
+#[must_use]
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1. / (1. + 10f64.powf((opponent - rating) / 400.))
+}
+
+/// A player's rating after a game against `opponent`
+#[must_use]
+pub fn rate(rating: f64, opponent: f64, won: bool) -> f64 {
+    let score = if won { 1. } else { 0. };
+    rating + K_FACTOR * (score - expected_score(rating, opponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(three_bv: usize, fifty_fifties: usize) -> BoardStatistics {
+        BoardStatistics {
+            three_bv,
+            fifty_fifties,
+            ..BoardStatistics::default()
+        }
+    }
+
+    #[test]
+    fn wins_raise_and_losses_lower() {
+        assert!(rate(INITIAL_RATING, 1300., true) > INITIAL_RATING);
+        assert!(rate(INITIAL_RATING, 1300., false) < INITIAL_RATING);
+    }
+
+    #[test]
+    fn equals_are_even() {
+        assert!((expected_score(1500., 1500.) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn expectations_sum_to_one() {
+        for (a, b) in [(1200., 1400.), (800., 2000.), (1500., 1499.)] {
+            assert!((expected_score(a, b) + expected_score(b, a) - 1.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn one_game_moves_at_most_k() {
+        for opponent in [0., 1200., 5000.] {
+            for won in [false, true] {
+                assert!((rate(INITIAL_RATING, opponent, won) - INITIAL_RATING).abs() <= K_FACTOR);
+            }
+        }
+    }
+
+    #[test]
+    fn harder_boards_rate_higher() {
+        assert!(board_rating(&statistics(20, 0)) > board_rating(&statistics(10, 0)));
+        assert!(board_rating(&statistics(10, 1)) > board_rating(&statistics(10, 0)));
+    }
+
+    #[test]
+    fn medals_at_par_multiples() {
+        let par = Duration::from_secs(100);
+        let award = |secs| Medal::award(Duration::from_secs(secs), par);
+        assert_eq!(award(100), Some(Medal::Gold));
+        assert_eq!(award(101), Some(Medal::Silver));
+        assert_eq!(award(150), Some(Medal::Silver));
+        assert_eq!(award(151), Some(Medal::Bronze));
+        assert_eq!(award(200), Some(Medal::Bronze));
+        assert_eq!(award(201), None);
+    }
+}
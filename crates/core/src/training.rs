@@ -0,0 +1,309 @@
+//! Fixed-shape snapshots of what a player can see, and rewards for their moves, for training
+//! agents against the engine
+
+#[cfg(feature = "npy")]
+use std::{fmt::Write, io, path::Path};
+
+use ndarray::Array3;
+
+use crate::{Board, Cell, Game, GameState};
+
+/// Channels in an observation, one-hot per cell: hidden, flagged, revealed with 0 to 8,
+/// revealed with any other number, and holes
+pub const CHANNELS: usize = 13;
+/// The channel for a revealed 0, with 1 to 8 after it
+const NUMBERS: usize = 2;
+
+/// What the player did
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Reveal(usize, usize),
+    Flag(usize, usize),
+}
+
+/// What each kind of move is worth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rewards {
+    /// For opening every safe cell on the board, shared out over the cells a move opens
+    pub progress: f32,
+    pub win: f32,
+    pub loss: f32,
+    /// For a move that changes nothing, like revealing an open cell
+    pub wasted: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Self {
+            progress: 1.0,
+            win: 1.0,
+            loss: -1.0,
+            wasted: -0.1,
+        }
+    }
+}
+
+/// One move in an [`Episode`]
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// The board before the move, as [`observe`] sees it
+    pub observation: Array3<f32>,
+    pub action: Action,
+    pub reward: f32,
+    /// Whether the move ended the game
+    pub done: bool,
+}
+
+/// The moves of a game, to train on
+#[derive(Clone, Debug, Default)]
+pub struct Episode {
+    /// Add the solver's bomb chances to every observation, as one more channel
+    pub probabilities: bool,
+    pub rewards: Rewards,
+    pub steps: Vec<Step>,
+}
+
+/// What the player can see of `game`, shaped (channel, row, column), with the solver's bomb
+/// chances as a last channel if `probabilities` is set. Numbers are as shown, lies and all. A cell still being worked out
+/// counts as hidden.
+#[must_use]
+pub fn observe(game: &Game, probabilities: bool) -> Array3<f32> {
+    let board = game.seen();
+    let (width, height) = board.dim();
+    let channels = CHANNELS + usize::from(probabilities);
+    let mut observation = Array3::zeros((channels, height, width));
+    for (x, y) in board.points() {
+        let channel = match board[(x, y)] {
+            Cell::Discovered(Some(n @ 0..=8)) => NUMBERS + usize::try_from(n).unwrap_or(0),
+            Cell::Discovered(Some(_)) => NUMBERS + 9,
+            Cell::Void => NUMBERS + 10,
+            _ if game.flags.contains(&(x, y)) => 1,
+            _ => 0,
+        };
+        observation[(channel, y, x)] = 1.0;
+    }
+    if probabilities && let Some(solver) = game.solver() {
+        #[allow(clippy::cast_possible_truncation)]
+        for ((x, y), p) in solver.probabilities().indexed_iter() {
+            if !matches!(board[(x, y)], Cell::Discovered(_) | Cell::Void) {
+                observation[(CHANNELS, y, x)] = *p as f32;
+            }
+        }
+    }
+    observation
+}
+
+/// Play `action` on `game`, running any collapse it takes, and say what it was worth and
+/// whether the game is over
+#[allow(clippy::cast_precision_loss)]
+pub fn step(game: &mut Game, action: Action, rewards: &Rewards) -> (f32, bool) {
+    let opened = |game: &Game| {
+        game.board
+            .iter()
+            .filter(|c| matches!(c, Cell::Discovered(_)))
+            .count()
+    };
+    let before = opened(game);
+    let changed = match action {
+        Action::Reveal(x, y) => {
+            game.reveal(x, y);
+            game.state() != GameState::Playing || opened(game) > before
+        }
+        Action::Flag(x, y) => game.toggle_flag(x, y).is_some(),
+    };
+    let safe = game
+        .board
+        .iter()
+        .filter(|c| !matches!(c, Cell::Void))
+        .count()
+        .saturating_sub(game.max_bombs)
+        .max(1);
+    let mut reward = rewards.progress * opened(game).saturating_sub(before) as f32 / safe as f32;
+    if !changed {
+        reward += rewards.wasted;
+    }
+    match game.state() {
+        GameState::Playing => (reward, false),
+        GameState::Won => (reward + rewards.win, true),
+        GameState::Lost(_) => (reward + rewards.loss, true),
+    }
+}
+
+/// A game to train an agent in, reset and stepped through like a Gym environment
+#[derive(Clone, Debug)]
+pub struct Env {
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    pub bastard: bool,
+    /// Add the solver's bomb chances to every observation, as one more channel
+    pub probabilities: bool,
+    pub rewards: Rewards,
+    game: Game,
+}
+
+impl Env {
+    /// An environment for `width` by `height` games with `bombs` bombs, reset to a game
+    /// with no seed
+    #[must_use]
+    pub fn new(width: usize, height: usize, bombs: usize, bastard: bool) -> Self {
+        Self {
+            width,
+            height,
+            bombs,
+            bastard,
+            probabilities: false,
+            rewards: Rewards::default(),
+            game: Game::new(Board::new(width, height), bombs, bastard),
+        }
+    }
+
+    #[must_use]
+    pub fn with_probabilities(self, probabilities: bool) -> Self {
+        Self {
+            probabilities,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_rewards(self, rewards: Rewards) -> Self {
+        Self { rewards, ..self }
+    }
+
+    /// The shape of every observation: (channel, row, column)
+    #[must_use]
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        (
+            CHANNELS + usize::from(self.probabilities),
+            self.height,
+            self.width,
+        )
+    }
+
+    /// The game being played
+    #[must_use]
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Start a new game, the same every time for the same `seed`, and observe it
+    pub fn reset(&mut self, seed: Option<u64>) -> Array3<f32> {
+        let game = Game::new(
+            Board::new(self.width, self.height),
+            self.bombs,
+            self.bastard,
+        );
+        self.game = match seed {
+            Some(seed) => game.with_seed(seed),
+            None => game,
+        };
+        observe(&self.game, self.probabilities)
+    }
+
+    /// Play `action`, returning what the board looks like after it, what it was worth and
+    /// whether the game is over. Once it is, every action is wasted until a reset.
+    pub fn step(&mut self, action: Action) -> (Array3<f32>, f32, bool) {
+        let (reward, done) = step(&mut self.game, action, &self.rewards);
+        (observe(&self.game, self.probabilities), reward, done)
+    }
+}
+
+impl Episode {
+    #[must_use]
+    pub fn new(probabilities: bool, rewards: Rewards) -> Self {
+        Self {
+            probabilities,
+            rewards,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Observe `game`, play `action` on it and remember how it went. Returns the reward and
+    /// whether the game is over, like [`step`].
+    pub fn play(&mut self, game: &mut Game, action: Action) -> (f32, bool) {
+        let observation = observe(game, self.probabilities);
+        let (reward, done) = step(game, action, &self.rewards);
+        self.steps.push(Step {
+            observation,
+            action,
+            reward,
+            done,
+        });
+        (reward, done)
+    }
+
+    /// Write the episode into `dir` as `NumPy` arrays: `observations.npy` (step, channel, row,
+    /// column) in float32, `actions.npy` (step, [kind, x, y]) in int64 with kind 0 for a
+    /// reveal and 1 for a flag, `rewards.npy` in float32 and `dones.npy` in uint8.
+    ///
+    /// # Errors
+    /// If the files can't be written, or the board changed size partway.
+    #[cfg(feature = "npy")]
+    pub fn write_npy(&self, dir: &Path) -> io::Result<()> {
+        let shape = self.steps.first().map_or([0; 3], |s| {
+            <[usize; 3]>::try_from(s.observation.shape()).unwrap_or([0; 3])
+        });
+        if self.steps.iter().any(|s| s.observation.shape() != shape) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "observations aren't all the same shape",
+            ));
+        }
+        std::fs::create_dir_all(dir)?;
+        let len = self.steps.len();
+        let observations = self
+            .steps
+            .iter()
+            .flat_map(|s| s.observation.iter().flat_map(|v| v.to_le_bytes()))
+            .collect::<Vec<_>>();
+        write_npy(
+            &dir.join("observations.npy"),
+            "<f4",
+            &[len, shape[0], shape[1], shape[2]],
+            &observations,
+        )?;
+        let actions = self
+            .steps
+            .iter()
+            .flat_map(|s| match s.action {
+                Action::Reveal(x, y) => [0, x, y],
+                Action::Flag(x, y) => [1, x, y],
+            })
+            .flat_map(|v| i64::try_from(v).unwrap_or(i64::MAX).to_le_bytes())
+            .collect::<Vec<_>>();
+        write_npy(&dir.join("actions.npy"), "<i8", &[len, 3], &actions)?;
+        let rewards = self
+            .steps
+            .iter()
+            .flat_map(|s| s.reward.to_le_bytes())
+            .collect::<Vec<_>>();
+        write_npy(&dir.join("rewards.npy"), "<f4", &[len], &rewards)?;
+        let dones = self
+            .steps
+            .iter()
+            .map(|s| u8::from(s.done))
+            .collect::<Vec<_>>();
+        write_npy(&dir.join("dones.npy"), "|u1", &[len], &dones)
+    }
+}
+
+/// Write `data`, already in the byte order `descr` says, as a version 1.0 `.npy` file
+#[cfg(feature = "npy")]
+fn write_npy(path: &Path, descr: &str, shape: &[usize], data: &[u8]) -> io::Result<()> {
+    let dims = shape.iter().fold(String::new(), |mut dims, d| {
+        let _ = write!(dims, "{d},");
+        dims
+    });
+    let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': ({dims}), }}");
+    // Magic, version and length take 10 bytes, and the data starts on a multiple of 64
+    header.push_str(&" ".repeat(63 - (10 + header.len()) % 64));
+    header.push('\n');
+    let len = u16::try_from(header.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "shape too long for .npy"))?;
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend(len.to_le_bytes());
+    bytes.extend(header.bytes());
+    bytes.extend(data);
+    std::fs::write(path, bytes)
+}
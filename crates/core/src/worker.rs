@@ -0,0 +1,253 @@
+//! Working out a reveal on its own thread, so a frontend can keep drawing while bastard mode
+//! collapses. Browsers can't start threads, so on the web that's a web worker instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread::JoinHandle;
+#[cfg(target_arch = "wasm32")]
+use std::{cell::RefCell, ops::Range, rc::Rc};
+
+#[cfg(target_arch = "wasm32")]
+use rand::{Rng, SeedableRng, rngs::StdRng};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+#[cfg(target_arch = "wasm32")]
+use web_sys::{DedicatedWorkerGlobalScope, ErrorEvent, MessageEvent};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::lower_priority;
+use crate::{Board, CancellationToken, CollapseConfig, CollapseObserver, CollapseTrace, Reveal};
+
+#[cfg(all(target_arch = "wasm32", not(feature = "serde")))]
+compile_error!(
+    "the web build sends reveals to its web worker as JSON, so it needs the serde feature"
+);
+
+/// What a [`Reveal`] worked out: the board with its numbers and every collapse it took, or
+/// `None` if it was cancelled
+pub type Outcome = Option<(Board, Vec<CollapseTrace>)>;
+
+/// Where the web build serves the script that runs [`serve`], next to the page
+#[cfg(target_arch = "wasm32")]
+pub const SCRIPT: &str = "./collapse-worker_loader.js";
+
+/// A [`Reveal`] running on another thread
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct Worker {
+    handle: JoinHandle<Outcome>,
+    cancel: CancellationToken,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Worker {
+    /// Start working out `job`, at low priority if `config` asks for it. `observer` is made
+    /// on the new thread from the worker's cancellation token, and should give up once it's
+    /// cancelled.
+    pub fn spawn<O: CollapseObserver>(
+        job: Reveal,
+        config: CollapseConfig,
+        observer: impl FnOnce(CancellationToken) -> O + Send + 'static,
+    ) -> Self {
+        let cancel = CancellationToken::default();
+        let token = cancel.clone();
+        let handle = std::thread::spawn(move || {
+            if config.low_priority {
+                lower_priority();
+            }
+            job.run(&config, &mut observer(token))
+        });
+        Self { handle, cancel }
+    }
+
+    /// Ask the collapse to give up, so the worker finishes with `None`
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the outcome
+    ///
+    /// # Errors
+    /// If a collapse panicked, with what it panicked with
+    pub fn join(self) -> std::thread::Result<Outcome> {
+        self.handle.join()
+    }
+}
+
+/// A [`Reveal`] running in a web worker started from [`SCRIPT`], which the page hands the
+/// reveal to once it's loaded
+#[cfg(target_arch = "wasm32")]
+pub struct Worker {
+    worker: web_sys::Worker,
+    /// Filled in when the web worker answers, fails or is cancelled
+    outcome: Rc<RefCell<Option<std::thread::Result<Outcome>>>>,
+    /// Hears about the collapses once they're back, as they can't be watched from here
+    observer: Box<dyn CollapseObserver>,
+    // Kept alive for as long as the web worker can call them
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(ErrorEvent)>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Worker {
+    /// Start working out `job` in a new web worker. `observer` is made here instead, and is
+    /// told about each collapse when the outcome is joined.
+    ///
+    /// # Panics
+    /// If the page can't start the web worker
+    pub fn spawn<O: CollapseObserver + 'static>(
+        job: Reveal,
+        config: CollapseConfig,
+        observer: impl FnOnce(CancellationToken) -> O + Send + 'static,
+    ) -> Self {
+        let worker = web_sys::Worker::new(SCRIPT).expect("the page should serve the worker");
+        let outcome = Rc::new(RefCell::new(None));
+        let message = serde_json::to_string(&Job::new(job, config)).unwrap();
+        let on_message = {
+            let (worker, outcome) = (worker.clone(), outcome.clone());
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                // Anything but text is the web worker saying it's ready for the reveal
+                let Some(text) = event.data().as_string() else {
+                    let _ = worker.post_message(&JsValue::from_str(&message));
+                    return;
+                };
+                let result = serde_json::from_str::<Outcome>(&text)
+                    .map_err(|e| Box::new(e.to_string()) as Box<dyn std::any::Any + Send>);
+                outcome.borrow_mut().get_or_insert(result);
+                worker.terminate();
+            })
+        };
+        let on_error = {
+            let (worker, outcome) = (worker.clone(), outcome.clone());
+            // Panics abort a web worker, which only leaves a message behind
+            Closure::<dyn FnMut(ErrorEvent)>::new(move |event: ErrorEvent| {
+                let error = Box::new(event.message()) as Box<dyn std::any::Any + Send>;
+                outcome.borrow_mut().get_or_insert(Err(error));
+                worker.terminate();
+            })
+        };
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        Self {
+            worker,
+            outcome,
+            observer: Box::new(observer(CancellationToken::default())),
+            _on_message: on_message,
+            _on_error: on_error,
+        }
+    }
+
+    /// Stop the web worker, so the worker finishes with `None`
+    pub fn cancel(&self) {
+        self.worker.terminate();
+        self.outcome.borrow_mut().get_or_insert(Ok(None));
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.outcome.borrow().is_some()
+    }
+
+    /// Take the outcome, once [`Worker::is_finished`]
+    ///
+    /// # Errors
+    /// If the web worker failed, with its error message
+    ///
+    /// # Panics
+    /// If the worker isn't finished, as the page can't wait for it
+    pub fn join(mut self) -> std::thread::Result<Outcome> {
+        let outcome = self
+            .outcome
+            .borrow_mut()
+            .take()
+            .expect("the web worker should be finished");
+        if let Ok(Some((_, traces))) = &outcome {
+            for trace in traces {
+                self.observer.on_done(trace);
+            }
+        }
+        outcome
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// A reveal as the page sends it to the web worker. Random number generators can't be sent,
+/// so a seeded game sends a seed drawn from its own.
+#[cfg(target_arch = "wasm32")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Job {
+    board: Board,
+    bastard: bool,
+    max_bombs: usize,
+    range: Option<Range<(usize, usize)>>,
+    reshuffle: bool,
+    seed: Option<u64>,
+    config: CollapseConfig,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Job {
+    fn new(reveal: Reveal, config: CollapseConfig) -> Self {
+        Self {
+            board: reveal.board,
+            bastard: reveal.bastard,
+            max_bombs: reveal.max_bombs,
+            range: reveal.range,
+            reshuffle: reveal.reshuffle,
+            seed: reveal.rng.map(|mut rng| rng.random()),
+            config,
+        }
+    }
+
+    fn run(self) -> Outcome {
+        let reveal = Reveal {
+            board: self.board,
+            bastard: self.bastard,
+            max_bombs: self.max_bombs,
+            range: self.range,
+            reshuffle: self.reshuffle,
+            rng: self.seed.map(StdRng::seed_from_u64),
+        };
+        reveal.run(&self.config, &mut ())
+    }
+}
+
+/// Work out the reveals [`Worker::spawn`] sends, from inside the web worker it starts
+///
+/// # Panics
+/// If this isn't running in a web worker
+#[cfg(target_arch = "wasm32")]
+pub fn serve() {
+    let scope = js_sys::global()
+        .dyn_into::<DedicatedWorkerGlobalScope>()
+        .expect("the collapse worker should run in a web worker");
+    let on_message = {
+        let scope = scope.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let Some(job) = event
+                .data()
+                .as_string()
+                .and_then(|text| serde_json::from_str::<Job>(&text).ok())
+            else {
+                return;
+            };
+            let outcome = serde_json::to_string(&job.run()).unwrap();
+            let _ = scope.post_message(&JsValue::from_str(&outcome));
+        })
+    };
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // Needed for as long as the web worker runs
+    on_message.forget();
+    // Messages sent before now were dropped, so the page waits for this before sending one
+    let _ = scope.post_message(&JsValue::NULL);
+}
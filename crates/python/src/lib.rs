@@ -0,0 +1,91 @@
+//! Python bindings for the training environment, built with `maturin develop` in this
+//! directory and imported as `bastard_minesweeper`
+#![warn(clippy::pedantic)]
+
+use bastard_minesweeper_core::training::{self, Action, Rewards};
+use numpy::{IntoPyArray, PyArray3};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// A game to train an agent in. Actions are numbers: below `width * height` reveals the cell
+/// at `action % width, action // width`, and the same again above that flags it.
+#[pyclass]
+struct Env(training::Env);
+
+#[pymethods]
+impl Env {
+    #[new]
+    #[pyo3(signature = (width=9, height=9, bombs=10, bastard=true, probabilities=false))]
+    fn new(
+        width: usize,
+        height: usize,
+        bombs: usize,
+        bastard: bool,
+        probabilities: bool,
+    ) -> PyResult<Self> {
+        if width == 0 || height == 0 || bombs >= width * height {
+            return Err(PyValueError::new_err(format!(
+                "Can't fit {bombs} bombs and a safe cell on {width}x{height}"
+            )));
+        }
+        Ok(Self(
+            training::Env::new(width, height, bombs, bastard).with_probabilities(probabilities),
+        ))
+    }
+
+    /// Change what moves are worth
+    #[pyo3(signature = (progress=1.0, win=1.0, loss=-1.0, wasted=-0.1))]
+    fn set_rewards(&mut self, progress: f32, win: f32, loss: f32, wasted: f32) {
+        self.0.rewards = Rewards {
+            progress,
+            win,
+            loss,
+            wasted,
+        };
+    }
+
+    /// (channels, height, width) of every observation
+    #[getter]
+    fn observation_shape(&self) -> (usize, usize, usize) {
+        self.0.observation_shape()
+    }
+
+    /// How many actions there are
+    #[getter]
+    fn action_count(&self) -> usize {
+        2 * self.0.width * self.0.height
+    }
+
+    /// Start a new game, the same every time for the same seed, and observe it
+    #[pyo3(signature = (seed=None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> Bound<'py, PyArray3<f32>> {
+        self.0.reset(seed).into_pyarray(py)
+    }
+
+    /// Play an action, returning the observation after it, the reward and whether the game
+    /// is over
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: usize,
+    ) -> PyResult<(Bound<'py, PyArray3<f32>>, f32, bool)> {
+        let (width, cells) = (self.0.width, self.0.width * self.0.height);
+        let (x, y) = (action % cells % width, action % cells / width);
+        let action = match action / cells {
+            0 => Action::Reveal(x, y),
+            1 => Action::Flag(x, y),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Action {action} isn't below {}",
+                    2 * cells
+                )));
+            }
+        };
+        let (observation, reward, done) = self.0.step(action);
+        Ok((observation.into_pyarray(py), reward, done))
+    }
+}
+
+#[pymodule]
+fn bastard_minesweeper(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Env>()
+}
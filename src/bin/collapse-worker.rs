@@ -0,0 +1,12 @@
+//! The web build's collapse worker. The page starts it in a web worker for every reveal, so
+//! bastard mode can think without freezing the page.
+
+fn main() {
+    #[cfg(target_arch = "wasm32")]
+    bastard_minesweeper::worker::serve();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        eprintln!("Only the web build runs collapses in a web worker, run bastard-minesweeper");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,3 @@
+// Without serde the reports are built but never read
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub mod output;
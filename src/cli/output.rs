@@ -0,0 +1,175 @@
+//! What the command line's subcommands found, in the shapes `--output json` prints. Fields
+//! only ever get added, so scripts reading them keep working.
+
+use std::path::PathBuf;
+
+use bastard_minesweeper::{
+    Tuning,
+    opening::{FirstClick, Spot},
+    policy::Policy,
+    selfcheck::Check,
+    simulate::Player,
+};
+
+/// How subcommands print what they found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Output {
+    /// Lines for people to read
+    #[default]
+    Text,
+    /// One JSON document for scripts, shaped like the reports here
+    Json,
+}
+
+/// A subcommand's result, which can be printed as JSON
+#[cfg(feature = "serde")]
+pub trait Report: serde::Serialize {
+    /// Format as indented JSON
+    ///
+    /// # Panics
+    /// Never, since every report has a JSON form
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// A subcommand's result, which can be printed as JSON with the serde feature
+#[cfg(not(feature = "serde"))]
+pub trait Report {}
+
+impl Report for Tuning {}
+
+/// Print a report for scripts to read
+#[cfg(feature = "serde")]
+pub fn print_json(report: &impl Report) {
+    println!("{}", report.to_json());
+}
+
+/// Without serde there's no JSON to print, so say so and stop
+#[cfg(not(feature = "serde"))]
+pub fn print_json(_report: &impl Report) {
+    eprintln!("JSON output needs the serde feature");
+    std::process::exit(1);
+}
+
+/// `selfcheck`: how each kind of check went
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SelfcheckReport {
+    pub passed: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Report for SelfcheckReport {}
+
+/// `simulate`: how each kind of player fared
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SimulateReport {
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    /// Games each player played
+    pub games: usize,
+    pub players: Vec<PlayerResult>,
+}
+
+impl Report for SimulateReport {}
+
+/// How one kind of player fared in `simulate`
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PlayerResult {
+    pub player: Player,
+    pub won: usize,
+    pub moves_per_game: f64,
+    pub guesses_per_game: f64,
+}
+
+/// `bench-strategies`: how one player fared against each policy over the same seeded games
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BenchReport {
+    pub player: Player,
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    /// Games played under each policy
+    pub games: usize,
+    /// Seed of the first game, the rest counting up from it
+    pub seed: u64,
+    pub policies: Vec<PolicyResult>,
+}
+
+impl Report for BenchReport {}
+
+/// How the player fared against one policy in `bench-strategies`
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PolicyResult {
+    pub policy: Policy,
+    pub won: usize,
+    /// Moves that weren't provably safe
+    pub guesses_per_game: f64,
+    pub ms_per_collapse: f64,
+}
+
+/// `analyze`: how first clicks go on an honest board of one size
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AnalyzeReport {
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    /// Deals each cell's first click was averaged over
+    pub samples: usize,
+    /// Fewest neighbors first
+    pub spots: Vec<Spot>,
+    pub best: Option<FirstClick>,
+}
+
+impl Report for AnalyzeReport {}
+
+/// `find-seed`: a seed whose opening is as hard as asked
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SeedReport {
+    pub seed: u64,
+    pub difficulty: f64,
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    pub bastard: bool,
+    /// The cell the opening was made on
+    pub opening: (usize, usize),
+    /// The board after the opening, in [`bastard_minesweeper::Board::to_text`]'s format
+    pub board: String,
+}
+
+impl Report for SeedReport {}
+
+/// `generate`: the pack of boards written, as its manifest lists them
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackReport {
+    pub out: PathBuf,
+    pub boards: Vec<PackBoard>,
+}
+
+impl Report for PackReport {}
+
+/// One board in a pack
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackBoard {
+    /// The file name, in the pack's directory
+    pub file: String,
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    /// Makes the same board again
+    pub seed: u64,
+    pub three_bv: usize,
+    /// The cell that comes already opened, on boards that promise no guessing
+    pub opened: Option<(usize, usize)>,
+}
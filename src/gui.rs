@@ -0,0 +1,27 @@
+pub mod analysis;
+pub mod branch;
+pub mod clock;
+pub mod crash;
+pub mod deal;
+pub mod effects;
+pub mod frontier;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod heat;
+pub mod input;
+pub mod locale;
+pub mod log;
+#[cfg(unix)]
+pub mod observer;
+pub mod overview;
+pub mod packs;
+#[cfg(feature = "power-probe")]
+pub mod power;
+pub mod preview;
+pub mod progress;
+pub mod quiz;
+pub mod remaining;
+pub mod review;
+pub mod script;
+pub mod settings;
+pub mod statistics;
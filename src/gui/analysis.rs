@@ -0,0 +1,72 @@
+use bastard_minesweeper::{Game, format_coord};
+use eframe::egui::{Context, ScrollArea, Window};
+
+/// Exact configuration counts for every frontier component
+pub struct Analysis {
+    components: Vec<Component>,
+    interior: usize,
+    bombs_left: Option<usize>,
+}
+
+struct Component {
+    cells: String,
+    /// Configurations with each number of bombs
+    counts: Vec<f64>,
+}
+
+impl Analysis {
+    /// `None` if the solver can't read the game's numbers
+    pub fn new(game: &Game) -> Option<Self> {
+        let solver = game.solver()?;
+        Some(Self {
+            components: solver
+                .component_counts()
+                .into_iter()
+                .map(|(cells, counts)| Component {
+                    cells: cells
+                        .iter()
+                        .map(|(x, y)| format_coord(*x, *y))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    counts,
+                })
+                .collect(),
+            interior: solver.interior_len(),
+            bombs_left: solver.bombs_left(),
+        })
+    }
+
+    /// Returns false once the player closes the window
+    pub fn show(&self, ctx: &Context) -> bool {
+        let mut open = true;
+        Window::new("Components").open(&mut open).show(ctx, |ui| {
+            match self.bombs_left {
+                Some(bombs) => ui.label(format!(
+                    "{bombs} bombs left among the components and {} other cells",
+                    self.interior
+                )),
+                None => ui.label(format!(
+                    "Bomb count unknown, {} cells away from any number",
+                    self.interior
+                )),
+            };
+            ScrollArea::vertical().show(ui, |ui| {
+                for (i, component) in self.components.iter().enumerate() {
+                    ui.separator();
+                    ui.label(format!(
+                        "Component {}: {} configurations",
+                        i + 1,
+                        component.counts.iter().sum::<f64>()
+                    ));
+                    ui.small(&component.cells);
+                    for (bombs, count) in component.counts.iter().enumerate() {
+                        if *count > 0. {
+                            ui.label(format!("{bombs} bombs: {count}"));
+                        }
+                    }
+                }
+            });
+        });
+        open
+    }
+}
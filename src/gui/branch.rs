@@ -0,0 +1,25 @@
+use bastard_minesweeper::Game;
+use web_time::Instant;
+
+use super::clock::Waiting;
+
+/// Everything a move can change, so it can be put back
+#[derive(Clone)]
+pub struct Snapshot {
+    pub game: Game,
+    pub started: Option<Instant>,
+    pub ended: Option<Instant>,
+    pub waiting: Waiting,
+    /// Length of the move history, which only grows
+    pub history: usize,
+    /// Mark in the event log, which only grows
+    pub log: (usize, usize),
+}
+
+/// A sandbox forked from the real game
+pub struct Branch {
+    /// The real game, restored when the branch is discarded
+    pub real: Snapshot,
+    /// The state before each move made in the branch
+    pub undo: Vec<Snapshot>,
+}
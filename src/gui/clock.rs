@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use web_time::Instant;
+
+/// Time the player spends away from the game, which is taken off their time: while the window
+/// is in the background. Collapses are timed by the game itself.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Waiting {
+    /// Waits that are over
+    total: Duration,
+    /// When the current wait began
+    since: Option<Instant>,
+}
+
+impl Waiting {
+    /// Start or stop waiting at `now`
+    pub fn set(&mut self, waiting: bool, now: Instant) {
+        match (waiting, self.since) {
+            (true, None) => self.since = Some(now),
+            (false, Some(since)) => {
+                self.total += now.saturating_duration_since(since);
+                self.since = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Time spent waiting up to `now`
+    pub fn total(&self, now: Instant) -> Duration {
+        self.total
+            + self
+                .since
+                .map_or(Duration::ZERO, |since| now.saturating_duration_since(since))
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::VecDeque,
+    env, fs, panic,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
+
+use bastard_minesweeper::{Board, CollapseTrace};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// How many of the latest collapses go in a report
+const TRACE_TAIL: usize = 16;
+
+/// What a report would contain if something went wrong right now
+struct Recent {
+    board: String,
+    settings: String,
+    traces: VecDeque<String>,
+}
+
+static RECENT: Mutex<Recent> = Mutex::new(Recent {
+    board: String::new(),
+    settings: String::new(),
+    traces: VecDeque::new(),
+});
+
+/// Where the panic hook last wrote a report
+static WRITTEN: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Remember the board and settings a collapse is about to start from
+pub fn record(board: &Board, settings: String) {
+    let mut recent = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+    recent.board = board.to_text();
+    recent.settings = settings;
+}
+
+/// Remember a finished collapse, forgetting the oldest past [`TRACE_TAIL`]
+pub fn record_trace(trace: &CollapseTrace) {
+    let mut recent = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+    if recent.traces.len() == TRACE_TAIL {
+        recent.traces.pop_front();
+    }
+    recent.traces.push_back(trace.to_json());
+}
+
+/// Write everything recorded so far to a new file in the temp directory
+pub fn write(reason: &str) -> Option<PathBuf> {
+    // A panic while holding the lock would deadlock the hook, so don't wait for it
+    let recent = match RECENT.try_lock() {
+        Ok(recent) => recent,
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(std::sync::TryLockError::WouldBlock) => return None,
+    };
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = env::temp_dir().join(format!("bastard-minesweeper-crash-{stamp}.txt"));
+    let report = format!(
+        "reason: {reason}\nversion: {}\nargs: {}\n\n[settings]\n{}\n\n[board]\n{}\n[traces]\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        env::args().collect::<Vec<_>>().join(" "),
+        recent.settings,
+        recent.board,
+        recent.traces.iter().cloned().collect::<Vec<_>>().join("\n"),
+    );
+    match fs::write(&path, report) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            eprintln!("Failed to write crash report to {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Write a report on every panic, on top of the usual message
+pub fn install_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default(info);
+        if let Some(path) = write(&info.to_string()) {
+            eprintln!("Crash report written to {}", path.display());
+            *WRITTEN.lock().unwrap_or_else(PoisonError::into_inner) = Some(path);
+        }
+    }));
+}
+
+/// The report the panic hook wrote, if it wrote one since last asked
+pub fn take_written() -> Option<PathBuf> {
+    WRITTEN
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use bastard_minesweeper::{
+    Board,
+    rules::{Classic, Ruleset},
+    spread::Spread,
+    stats,
+};
+use rand::{Rng, rngs::StdRng};
+
+/// Boards dealt to find one near the target rating
+const ADAPTIVE_TRIES: usize = 20;
+/// Boards dealt looking for enough openings before settling for the one with the most
+const OPENING_TRIES: usize = 1000;
+
+/// Deals the boards of an honest game, dealing again when the player re-rolls
+pub struct Dealer {
+    /// The board to deal onto, for its size, shape, edges and neighborhood
+    shape: Board,
+    bombs: usize,
+    /// Keep dealing until a board has at most this many coin flips
+    max_fifty_fifties: Option<usize>,
+    rng: StdRng,
+    /// Re-rolls the player has left
+    pub rerolls: usize,
+    /// Deal boards rated about this strongly, to match the player
+    pub target: Option<f64>,
+    /// Keep dealing until a board has at least this many openings
+    pub min_openings: Option<usize>,
+    /// How the bombs sit relative to each other
+    pub spread: Spread,
+    /// How many bombs a cell can hold and how many are anti-mines
+    pub ruleset: Arc<dyn Ruleset>,
+}
+
+impl Dealer {
+    pub fn new(
+        shape: Board,
+        bombs: usize,
+        max_fifty_fifties: Option<usize>,
+        rerolls: usize,
+        rng: StdRng,
+    ) -> Self {
+        let neighborhood = shape.neighborhood();
+        Self {
+            shape,
+            bombs,
+            max_fifty_fifties,
+            rng,
+            rerolls,
+            target: None,
+            min_openings: None,
+            spread: Spread::Random,
+            ruleset: Arc::new(Classic { neighborhood }),
+        }
+    }
+
+    /// The next board, or with a `target` rating the closest to it of a few
+    pub fn deal(&mut self) -> Board {
+        let Some(target) = self.target else {
+            return self.deal_one();
+        };
+        // Boards of one size only vary so much, so there may be none close
+        (0..ADAPTIVE_TRIES)
+            .map(|_| {
+                let board = self.deal_one();
+                let distance = (stats::board_rating(&board.statistics()) - target).abs();
+                (board, distance)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(board, _)| board)
+            .unwrap_or_else(|| self.deal_one())
+    }
+
+    /// The next board within the coin flip limit with enough openings, or if the openings
+    /// don't turn up the one with the most of a lot of tries
+    fn deal_one(&mut self) -> Board {
+        let mut best: Option<(Board, usize)> = None;
+        let mut tries = 0;
+        loop {
+            let board = match self.spread {
+                Spread::Random => self
+                    .ruleset
+                    .deal(self.shape.clone(), self.bombs, &mut self.rng),
+                spread => {
+                    let board = self.ruleset.deal(self.shape.clone(), 0, &mut self.rng);
+                    spread.place(board, self.bombs, &mut self.rng)
+                }
+            };
+            if self.max_fifty_fifties.is_none() && self.min_openings.is_none() {
+                return board;
+            }
+            let statistics = board.statistics();
+            if self
+                .max_fifty_fifties
+                .is_some_and(|limit| statistics.fifty_fifties > limit)
+            {
+                continue;
+            }
+            if self
+                .min_openings
+                .is_none_or(|least| statistics.openings >= least)
+            {
+                return board;
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(_, most)| statistics.openings > *most)
+            {
+                best = Some((board, statistics.openings));
+            }
+            tries += 1;
+            if tries == OPENING_TRIES
+                && let Some((board, _)) = best.take()
+            {
+                return board;
+            }
+        }
+    }
+
+    /// The next board, if there are re-rolls left
+    pub fn reroll(&mut self) -> Option<Board> {
+        self.rerolls = self.rerolls.checked_sub(1)?;
+        Some(self.deal())
+    }
+}
+
+/// A board with its bombs placed up front
+pub fn honest_board(width: usize, height: usize, max_bombs: usize, rng: &mut impl Rng) -> Board {
+    Classic::default().deal(Board::new(width, height), max_bombs, rng)
+}
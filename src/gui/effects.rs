@@ -0,0 +1,123 @@
+use std::f32::consts::TAU;
+
+use eframe::{
+    egui::{Color32, Context, Id, LayerId, Order, Pos2, Rect, Vec2},
+    emath::TSTransform,
+};
+use rand::{Rng, rng};
+
+const GRAVITY: f32 = 600.;
+const SHAKE_DURATION: f64 = 0.5;
+const SHAKE_AMPLITUDE: f32 = 12.;
+
+struct Particle {
+    pos: Pos2,
+    vel: Vec2,
+    color: Color32,
+    radius: f32,
+    born: f64,
+    lifetime: f64,
+}
+
+/// Purely cosmetic particle system, drawn on top of everything else
+#[derive(Default)]
+pub struct Effects {
+    particles: Vec<Particle>,
+    /// Start time and intensity of the current screen shake
+    shake: Option<(f64, f32)>,
+    last_frame: Option<f64>,
+}
+
+impl Effects {
+    /// Burst of fire-colored particles around a detonated mine
+    pub fn explode(&mut self, at: Pos2, now: f64) {
+        let mut rng = rng();
+        for _ in 0..80 {
+            let angle = rng.random_range(0.0..TAU);
+            let speed = rng.random_range(50.0..400.0);
+            self.particles.push(Particle {
+                pos: at,
+                vel: Vec2::angled(angle) * speed,
+                color: [
+                    Color32::RED,
+                    Color32::ORANGE,
+                    Color32::YELLOW,
+                    Color32::DARK_GRAY,
+                ][rng.random_range(0..4)],
+                radius: rng.random_range(1.5..4.),
+                born: now,
+                lifetime: rng.random_range(0.4..1.2),
+            });
+        }
+    }
+
+    /// Confetti raining down from the top of `area`
+    pub fn confetti(&mut self, area: Rect, now: f64) {
+        let mut rng = rng();
+        for _ in 0..200 {
+            self.particles.push(Particle {
+                pos: Pos2::new(
+                    rng.random_range(area.left()..=area.right()),
+                    area.top() - rng.random_range(0.0..100.),
+                ),
+                vel: Vec2::new(rng.random_range(-80.0..80.), rng.random_range(0.0..150.)),
+                color: Color32::from_rgb(
+                    rng.random_range(64..=255),
+                    rng.random_range(64..=255),
+                    rng.random_range(64..=255),
+                ),
+                radius: rng.random_range(2.0..4.),
+                born: now,
+                lifetime: rng.random_range(1.5..3.),
+            });
+        }
+    }
+
+    /// Jolt the board around, `intensity` being between 0 and 1
+    pub fn shake(&mut self, intensity: f32, now: f64) {
+        if intensity > 0. {
+            self.shake = Some((now, intensity));
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.particles.is_empty() || self.shake.is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Advance the simulation to the current frame and paint it
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn show(&mut self, ctx: &Context) {
+        let now = ctx.input(|i| i.time);
+        let dt = (now - self.last_frame.unwrap_or(now)) as f32;
+        self.last_frame = Some(now);
+        if !self.is_active() {
+            return;
+        }
+
+        if let Some((began, intensity)) = self.shake {
+            let elapsed = now - began;
+            let offset = if elapsed < SHAKE_DURATION {
+                let decay = 1. - (elapsed / SHAKE_DURATION) as f32;
+                Vec2::angled(rng().random_range(0.0..TAU)) * SHAKE_AMPLITUDE * intensity * decay
+            } else {
+                self.shake = None;
+                Vec2::ZERO
+            };
+            ctx.set_transform_layer(LayerId::background(), TSTransform::from_translation(offset));
+        }
+
+        self.particles.retain(|p| now - p.born < p.lifetime);
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("effects")));
+        for p in &mut self.particles {
+            p.vel.y += GRAVITY * dt;
+            p.pos += p.vel * dt;
+            let fade = 1. - ((now - p.born) / p.lifetime) as f32;
+            painter.circle_filled(p.pos, p.radius, p.color.gamma_multiply(fade));
+        }
+        ctx.request_repaint();
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::HashSet;
+
+use bastard_minesweeper::Game;
+use eframe::egui::Color32;
+
+use super::heat::numbers_key;
+
+/// Dim everything but the cells still in question and the numbers bordering them,
+/// to help focus on a messy endgame
+#[derive(Default)]
+pub struct FrontierView {
+    pub enabled: bool,
+    /// The active frontier, and a hash of the revealed numbers it was found from
+    cache: Option<(u64, HashSet<(usize, usize)>)>,
+}
+
+impl FrontierView {
+    /// Find the frontier again if the revealed numbers changed
+    pub fn update(&mut self, game: &Game) {
+        if !self.enabled {
+            return;
+        }
+        let key = numbers_key(game);
+        if self.cache.as_ref().is_some_and(|(k, _)| *k == key) {
+            return;
+        }
+        self.cache = game
+            .solver()
+            .map(|solver| (key, solver.active_frontier(&game.seen())));
+    }
+
+    pub fn tint(&self, cell: (usize, usize)) -> Option<Color32> {
+        let (_, frontier) = self.cache.as_ref().filter(|_| self.enabled)?;
+        (!frontier.contains(&cell)).then_some(Color32::from_black_alpha(160))
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use gilrs::{
+    Axis, Button, EventType, Gilrs,
+    ff::{BaseEffect, BaseEffectType, EffectBuilder, Envelope, Replay, Ticks},
+};
+
+use super::input::Action;
+
+/// How often to poll for controller events, since they don't wake egui up
+pub const POLL_INTERVAL: Duration = Duration::from_millis(16);
+const STICK_THRESHOLD: f32 = 0.5;
+const ZOOM_STEP: f32 = 1.1;
+
+/// D-pad/left stick move, A reveals, X flags, triggers zoom
+pub struct Gamepad {
+    gilrs: Gilrs,
+    /// Which direction the left stick is currently tilted, so holding it only moves once
+    stick: (isize, isize),
+}
+
+impl Gamepad {
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Self {
+                gilrs,
+                stick: (0, 0),
+            }),
+            Err(e) => {
+                eprintln!("Gamepad support unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn actions(&mut self) -> Vec<Action> {
+        let mut actions = vec![];
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => actions.extend(match button {
+                    Button::DPadLeft => Some(Action::Move(-1, 0)),
+                    Button::DPadRight => Some(Action::Move(1, 0)),
+                    Button::DPadUp => Some(Action::Move(0, -1)),
+                    Button::DPadDown => Some(Action::Move(0, 1)),
+                    Button::South => Some(Action::Reveal),
+                    Button::West => Some(Action::Flag),
+                    Button::LeftTrigger2 => Some(Action::Zoom(1. / ZOOM_STEP)),
+                    Button::RightTrigger2 => Some(Action::Zoom(ZOOM_STEP)),
+                    _ => None,
+                }),
+                EventType::AxisChanged(axis @ (Axis::LeftStickX | Axis::LeftStickY), value, _) => {
+                    let direction = if value > STICK_THRESHOLD {
+                        1
+                    } else if value < -STICK_THRESHOLD {
+                        -1
+                    } else {
+                        0
+                    };
+                    // Stick Y points up, the board's points down
+                    let (held, action) = if axis == Axis::LeftStickX {
+                        (&mut self.stick.0, Action::Move(direction, 0))
+                    } else {
+                        (&mut self.stick.1, Action::Move(0, -direction))
+                    };
+                    if *held != direction {
+                        *held = direction;
+                        if direction != 0 {
+                            actions.push(action);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        actions
+    }
+
+    /// Rumble every connected controller that supports it, `intensity` being between 0 and 1
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn rumble(&mut self, intensity: f32) {
+        if intensity <= 0. {
+            return;
+        }
+        let ids = self
+            .gilrs
+            .gamepads()
+            .filter(|(_, g)| g.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return;
+        }
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: (f32::from(u16::MAX) * intensity.min(1.)) as u16,
+                },
+                scheduling: Replay {
+                    play_for: Ticks::from_ms(400),
+                    ..Default::default()
+                },
+                envelope: Envelope::default(),
+            })
+            .gamepads(&ids)
+            .finish(&mut self.gilrs);
+        if let Err(e) = effect.and_then(|effect| effect.play()) {
+            eprintln!("Failed to rumble: {e}");
+        }
+    }
+}
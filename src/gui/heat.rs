@@ -0,0 +1,53 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use bastard_minesweeper::{Cell, Game};
+use eframe::egui::Color32;
+use ndarray::Array2;
+
+/// Tint unrevealed cells by how likely they are to be bombs
+#[derive(Default)]
+pub struct Heat {
+    pub enabled: bool,
+    /// Probabilities, and a hash of the revealed numbers they were computed from
+    cache: Option<(u64, Array2<f64>)>,
+}
+
+impl Heat {
+    /// Recompute the probabilities if the revealed numbers changed,
+    /// since the solver doesn't look at anything else
+    pub fn update(&mut self, game: &Game) {
+        if !self.enabled {
+            return;
+        }
+        let key = numbers_key(game);
+        if self.cache.as_ref().is_some_and(|(k, _)| *k == key) {
+            return;
+        }
+        self.cache = game
+            .solver()
+            .map(|solver| (key, solver.probabilities().clone()));
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn tint(&self, cell: (usize, usize)) -> Option<Color32> {
+        let (_, probabilities) = self.cache.as_ref().filter(|_| self.enabled)?;
+        let p = *probabilities.get(cell)?;
+        (p > 0.).then(|| Color32::from_rgba_unmultiplied(255, 0, 0, (p * 96.) as u8))
+    }
+}
+
+/// A hash of the numbers the player sees in `game`, and the ruleset's parameters that say
+/// how far to trust those numbers, to tell when what the solver knows changed
+pub fn numbers_key(game: &Game) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let board = game.seen();
+    for p in board.points() {
+        if let Cell::Discovered(n) = board[p] {
+            (p, n).hash(&mut hasher);
+        }
+    }
+    for (name, value) in game.ruleset.parameters() {
+        (name, value.to_bits()).hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,206 @@
+use bastard_minesweeper::parse_coord;
+use eframe::egui::{Context, Key, Rect, Response};
+
+/// Something the player wants to do, independent of the device it came from
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+    /// Move the focused cell
+    Move(isize, isize),
+    /// Reveal the focused cell
+    Reveal,
+    /// Toggle a flag on the focused cell
+    Flag,
+    /// Multiply the zoom level
+    Zoom(f32),
+    /// Remember the whole game state, in practice mode
+    QuickSave,
+    /// Go back to the remembered game state, in practice mode
+    QuickLoad,
+    /// Take back the last reveal
+    Undo,
+    /// Put back the last reveal taken back
+    Redo,
+}
+
+/// Which mouse gestures reveal and which flag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ButtonMap {
+    /// Left click reveals, right click flags
+    #[default]
+    Standard,
+    /// Right click reveals, left click flags
+    Swapped,
+    /// Click flags, shift or ctrl click reveals, for trackpads
+    FlagOnClick,
+    /// Clicks cycle what would happen to a cell, holding the button does it, for switch access
+    SingleButton,
+}
+
+impl ButtonMap {
+    pub const ALL: [Self; 4] = [
+        Self::Standard,
+        Self::Swapped,
+        Self::FlagOnClick,
+        Self::SingleButton,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ButtonMap::Standard => "Left reveals",
+            ButtonMap::Swapped => "Right reveals",
+            ButtonMap::FlagOnClick => "Click flags",
+            ButtonMap::SingleButton => "Single button",
+        }
+    }
+
+    /// What a click on `cell` means, if it was clicked at all
+    pub fn action(
+        self,
+        response: &Response,
+        cell: (usize, usize),
+        cycle: &mut Cycle,
+    ) -> Option<Action> {
+        if self == ButtonMap::SingleButton {
+            return cycle.update(response, cell);
+        }
+        let primary = response.clicked();
+        let secondary = response.secondary_clicked();
+        match self {
+            ButtonMap::Standard if primary => Some(Action::Reveal),
+            ButtonMap::Standard if secondary => Some(Action::Flag),
+            ButtonMap::Swapped if primary => Some(Action::Flag),
+            ButtonMap::Swapped if secondary => Some(Action::Reveal),
+            ButtonMap::FlagOnClick if primary => {
+                let modified = response
+                    .ctx
+                    .input(|i| i.modifiers.shift || i.modifiers.command);
+                Some(if modified {
+                    Action::Reveal
+                } else {
+                    Action::Flag
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How long the single button has to be held to confirm, longer than egui counts as a click
+pub const CONFIRM_HOLD: f64 = 1.;
+
+/// Single button state: clicking a cell cycles between intending to reveal it,
+/// intending to flag it, and nothing, and holding the button confirms the intent
+#[derive(Default)]
+pub struct Cycle {
+    /// The cell and what confirming would do to it
+    pub pending: Option<((usize, usize), Action)>,
+    /// Whether the current hold already confirmed, so it doesn't repeat
+    held: bool,
+}
+
+impl Cycle {
+    fn update(&mut self, response: &Response, cell: (usize, usize)) -> Option<Action> {
+        if response.clicked() {
+            self.pending = match self.pending {
+                Some((c, Action::Reveal)) if c == cell => Some((cell, Action::Flag)),
+                Some((c, Action::Flag)) if c == cell => None,
+                _ => Some((cell, Action::Reveal)),
+            };
+            return None;
+        }
+        if !response.is_pointer_button_down_on() {
+            if !response.ctx.input(|i| i.pointer.any_down()) {
+                self.held = false;
+            }
+            return None;
+        }
+        response.ctx.request_repaint();
+        let held_for = response
+            .ctx
+            .input(|i| i.pointer.press_start_time().map(|t| i.time - t));
+        if self.held || held_for.is_none_or(|t| t < CONFIRM_HOLD) {
+            return None;
+        }
+        self.held = true;
+        match self.pending.take() {
+            Some((c, action)) if c == cell => Some(action),
+            _ => None,
+        }
+    }
+
+    /// What to show on a cell with a pending intent
+    pub fn label(&self, cell: (usize, usize)) -> Option<&'static str> {
+        match self.pending {
+            Some((c, Action::Reveal)) if c == cell => Some("R?"),
+            Some((c, Action::Flag)) if c == cell => Some("F?"),
+            _ => None,
+        }
+    }
+}
+
+/// The cell targeted by keyboard and gamepad actions
+#[derive(Default)]
+pub struct Focus {
+    pub cell: Option<(usize, usize)>,
+    /// Where the focused cell was drawn last frame
+    pub rect: Option<Rect>,
+}
+
+impl Focus {
+    /// Move the focus, staying inside the board. Focus starts in the top left corner.
+    pub fn step(&mut self, dx: isize, dy: isize, (width, height): (usize, usize)) {
+        let Some((x, y)) = self.cell else {
+            self.cell = Some((0, 0));
+            return;
+        };
+        self.cell = Some((
+            x.saturating_add_signed(dx).min(width - 1),
+            y.saturating_add_signed(dy).min(height - 1),
+        ));
+    }
+}
+
+/// A typed move: a coordinate to focus, optionally prefixed with `r` to reveal it or `f` to flag it
+pub fn parse_command(command: &str) -> Option<((usize, usize), Option<Action>)> {
+    let command = command.trim();
+    let (action, coord) = match command.split_once(' ') {
+        Some(("r" | "R", coord)) => (Some(Action::Reveal), coord),
+        Some(("f" | "F", coord)) => (Some(Action::Flag), coord),
+        Some(_) => return None,
+        None => (None, command),
+    };
+    Some((parse_coord(coord)?, action))
+}
+
+/// Arrow keys move, space/enter reveals, F flags, +/- zoom, F5 quicksaves, F9 quickloads.
+/// Nothing while a text box has focus.
+pub fn keyboard_actions(ctx: &Context) -> Vec<Action> {
+    if ctx.wants_keyboard_input() {
+        return Vec::new();
+    }
+    ctx.input(|i| {
+        [
+            (Key::ArrowLeft, Action::Move(-1, 0)),
+            (Key::ArrowRight, Action::Move(1, 0)),
+            (Key::ArrowUp, Action::Move(0, -1)),
+            (Key::ArrowDown, Action::Move(0, 1)),
+            (Key::Space, Action::Reveal),
+            (Key::Enter, Action::Reveal),
+            (Key::F, Action::Flag),
+            (Key::Plus, Action::Zoom(1.1)),
+            (Key::Minus, Action::Zoom(1. / 1.1)),
+            (Key::F5, Action::QuickSave),
+            (Key::F9, Action::QuickLoad),
+        ]
+        .into_iter()
+        .filter(|(key, _)| i.key_pressed(*key))
+        .map(|(_, action)| action)
+        .chain(
+            [(Key::Z, Action::Undo), (Key::Y, Action::Redo)]
+                .into_iter()
+                .filter(|(key, _)| i.modifiers.command && i.key_pressed(*key))
+                .map(|(_, action)| action),
+        )
+        .collect()
+    })
+}
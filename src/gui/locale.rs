@@ -0,0 +1,125 @@
+use eframe::egui::{Align, Layout};
+
+/// How the system locale writes numbers and which way it lays out text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// Between the whole and fractional digits
+    pub decimal: char,
+    /// Between groups of three digits in big numbers
+    pub group: char,
+    /// Written right to left, so rows of controls start on the right
+    pub rtl: bool,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            group: ',',
+            rtl: false,
+        }
+    }
+}
+
+/// Languages writing `1.234,5`
+const DOT_GROUPS: &[&str] = &[
+    "ca", "da", "de", "el", "es", "hr", "id", "is", "it", "nl", "pt", "ro", "sl", "sr", "tr", "vi",
+];
+/// Languages writing `1 234,5`
+const SPACE_GROUPS: &[&str] = &[
+    "be", "bg", "cs", "et", "fi", "fr", "hu", "kk", "lt", "lv", "nb", "nn", "no", "pl", "ru", "sk",
+    "sv", "uk",
+];
+/// Languages written right to left
+const RTL: &[&str] = &["ar", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi"];
+
+impl Locale {
+    /// The locale the environment asks for, like a C program would read it: numbers from
+    /// `LC_ALL`, `LC_NUMERIC` or `LANG` and the direction from `LC_ALL`, `LC_MESSAGES` or
+    /// `LANG`, whichever is set first
+    pub fn from_env() -> Self {
+        let var = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty())
+                .unwrap_or_default()
+        };
+        Self {
+            rtl: Self::parse(&var(&["LC_ALL", "LC_MESSAGES", "LANG"])).rtl,
+            ..Self::parse(&var(&["LC_ALL", "LC_NUMERIC", "LANG"]))
+        }
+    }
+
+    /// A locale from its name, like `de_DE.UTF-8` or `ar-EG`. Anything unknown, including
+    /// `C`, gets the defaults.
+    pub fn parse(name: &str) -> Self {
+        let mut parts = name
+            .split(['.', '@'])
+            .next()
+            .unwrap_or("")
+            .split(['_', '-']);
+        let language = parts.next().unwrap_or("").to_ascii_lowercase();
+        let region = parts.next().unwrap_or("").to_ascii_uppercase();
+        let (decimal, group) = match (language.as_str(), region.as_str()) {
+            // Swiss German and Italian keep the point
+            ("de" | "it", "CH" | "LI") => ('.', '\''),
+            (language, _) if DOT_GROUPS.contains(&language) => (',', '.'),
+            // A no-break space, so numbers don't wrap
+            (language, _) if SPACE_GROUPS.contains(&language) => (',', '\u{a0}'),
+            _ => ('.', ','),
+        };
+        Self {
+            decimal,
+            group,
+            rtl: RTL.contains(&language.as_str()),
+        }
+    }
+
+    /// `value` with `decimals` digits after the separator. Whole parts of five digits or
+    /// more are grouped in threes, leaving years and ratings alone.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{value:.decimals$}");
+        // Rounded to nothing, a negative number loses its sign
+        let negative = text.starts_with('-') && text.bytes().any(|b| (b'1'..=b'9').contains(&b));
+        let text = text.trim_start_matches('-');
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let mut number = if negative { "-" } else { "" }.to_string();
+        for (i, digit) in whole.chars().enumerate() {
+            if whole.len() > 4 && i > 0 && (whole.len() - i).is_multiple_of(3) {
+                number.push(self.group);
+            }
+            number.push(digit);
+        }
+        if !fraction.is_empty() {
+            number.push(self.decimal);
+            number.push_str(fraction);
+        }
+        number
+    }
+
+    /// Like [`Locale::number`], with a plus in front of anything that isn't negative
+    pub fn signed(&self, value: f64, decimals: usize) -> String {
+        let number = self.number(value, decimals);
+        if number.starts_with('-') {
+            number
+        } else {
+            format!("+{number}")
+        }
+    }
+
+    /// A whole number
+    #[allow(clippy::cast_precision_loss)]
+    pub fn count(&self, n: usize) -> String {
+        self.number(n as f64, 0)
+    }
+
+    /// A column lined up on the right in right-to-left locales, where the rows in it start on
+    /// the right too
+    pub fn column(&self) -> Layout {
+        Layout::top_down(if self.rtl { Align::Max } else { Align::Min })
+    }
+}
@@ -0,0 +1,132 @@
+use bastard_minesweeper::{Board, CollapseTrace, format_coord, solver};
+use web_time::Instant;
+
+/// Something that happened to a cell
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Revealed,
+    Flagged,
+    Unflagged,
+    /// A collapse decided whether the cell is a bomb
+    Assigned {
+        /// Which collapse it was, counting from 1
+        collapse: usize,
+        bomb: bool,
+        /// How many consistent states the collapse chose between
+        states: usize,
+    },
+}
+
+impl Event {
+    /// A line of JSON for observers
+    pub fn to_json(self, (x, y): (usize, usize)) -> String {
+        match self {
+            Event::Revealed => format!("{{\"event\":\"revealed\",\"cell\":[{x},{y}]}}"),
+            Event::Flagged => format!("{{\"event\":\"flagged\",\"cell\":[{x},{y}]}}"),
+            Event::Unflagged => format!("{{\"event\":\"unflagged\",\"cell\":[{x},{y}]}}"),
+            Event::Assigned {
+                collapse,
+                bomb,
+                states,
+            } => format!(
+                "{{\"event\":\"assigned\",\"cell\":[{x},{y}],\"collapse\":{collapse},\"bomb\":{bomb},\"states\":{states}}}"
+            ),
+        }
+    }
+}
+
+/// Everything that happened to every cell this game
+#[derive(Default)]
+pub struct EventLog {
+    events: Vec<(Instant, (usize, usize), Event)>,
+    collapses: usize,
+    /// Programs watching the game from outside
+    #[cfg(unix)]
+    pub observer: Option<super::observer::Observer>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, cell: (usize, usize), event: Event) {
+        self.publish(&event.to_json(cell));
+        self.events.push((Instant::now(), cell, event));
+    }
+
+    /// Tell observers something that isn't about a single cell
+    #[cfg_attr(not(unix), allow(unused_variables, clippy::unused_self))]
+    pub fn publish(&self, line: &str) {
+        #[cfg(unix)]
+        if let Some(observer) = &self.observer {
+            observer.publish(line);
+        }
+    }
+
+    /// A point to come back to with [`EventLog::rewind`]
+    pub fn mark(&self) -> (usize, usize) {
+        (self.events.len(), self.collapses)
+    }
+
+    /// Forget everything since `mark`
+    pub fn rewind(&mut self, (events, collapses): (usize, usize)) {
+        self.events.truncate(events);
+        self.collapses = collapses;
+        self.publish(&format!("{{\"event\":\"rewound\",\"events\":{events}}}"));
+    }
+
+    /// Record the assignments a collapse made
+    pub fn collapsed(&mut self, trace: &CollapseTrace) {
+        self.collapses += 1;
+        for (cell, bomb) in trace.frontier.iter().zip(&trace.chosen) {
+            self.push(
+                *cell,
+                Event::Assigned {
+                    collapse: self.collapses,
+                    bomb: *bomb,
+                    states: trace.states,
+                },
+            );
+        }
+    }
+
+    /// One line per event for a cell, then the revealed numbers it borders.
+    /// Times are relative to `started`.
+    pub fn describe(
+        &self,
+        board: &Board,
+        cell: (usize, usize),
+        started: Option<Instant>,
+    ) -> String {
+        let mut lines = vec![format_coord(cell.0, cell.1)];
+        for (at, _, event) in self.events.iter().filter(|(_, c, _)| *c == cell) {
+            let seconds = started.map_or(0., |s| at.saturating_duration_since(s).as_secs_f64());
+            lines.push(match event {
+                Event::Revealed => format!("{seconds:.1}s: revealed"),
+                Event::Flagged => format!("{seconds:.1}s: flagged"),
+                Event::Unflagged => format!("{seconds:.1}s: unflagged"),
+                Event::Assigned {
+                    collapse,
+                    bomb,
+                    states,
+                } => format!(
+                    "{seconds:.1}s: collapse {collapse} made it {} out of {states} states",
+                    if *bomb { "a bomb" } else { "safe" }
+                ),
+            });
+        }
+        for constraint in solver::constraints(board)
+            .into_iter()
+            .filter(|c| c.cells.contains(&cell))
+        {
+            lines.push(format!(
+                "{} bombs among {}",
+                constraint.bombs,
+                constraint
+                    .cells
+                    .iter()
+                    .map(|(x, y)| format_coord(*x, *y))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
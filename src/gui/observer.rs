@@ -0,0 +1,48 @@
+use std::{
+    fs,
+    io::{self, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+};
+
+/// Sends game events to every program connected to a unix socket, one line of JSON each
+pub struct Observer {
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+impl Observer {
+    /// Listen on `path`, replacing a socket left over from an earlier run
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        if fs::metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let clients = Arc::<Mutex<Vec<UnixStream>>>::default();
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // Observers that fall behind get dropped rather than holding up the game
+                if stream.set_nonblocking(true).is_ok() {
+                    accepted
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push(stream);
+                }
+            }
+        });
+        Ok(Self { clients })
+    }
+
+    /// Send a line to every observer, forgetting the ones that hung up or fell behind
+    pub fn publish(&self, line: &str) {
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain_mut(|stream| writeln!(stream, "{line}").is_ok());
+    }
+}
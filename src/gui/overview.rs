@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use bastard_minesweeper::{Board, Cell};
+use eframe::egui::{Color32, Rect, ScrollArea, Sense, Ui, Vec2, vec2};
+
+/// Cells that would show up smaller than this many pixels get aggregated into blocks
+pub const MIN_CELL_PIXELS: f32 = 8.;
+
+/// Draw the board as blocks of `block` by `block` cells, each `side` points across, shaded by
+/// how much of the block is revealed and marked if it holds a flag.
+/// Returns the cell in the middle of a clicked block.
+pub fn show(
+    ui: &mut Ui,
+    board: &Board,
+    flags: &HashSet<(usize, usize)>,
+    block: usize,
+    side: f32,
+) -> Option<(usize, usize)> {
+    let (width, height) = board.dim();
+    let (columns, rows) = (width.div_ceil(block), height.div_ceil(block));
+    let mut clicked = None;
+    ScrollArea::both().show(ui, |ui| {
+        #[allow(clippy::cast_precision_loss)]
+        let extent = vec2(columns as f32, rows as f32) * side;
+        let (response, painter) = ui.allocate_painter(extent, Sense::click());
+        let origin = response.rect.min;
+        for bx in 0..columns {
+            for by in 0..rows {
+                let cells = (bx * block..((bx + 1) * block).min(width))
+                    .flat_map(|x| (by * block..((by + 1) * block).min(height)).map(move |y| (x, y)))
+                    .filter(|p| !matches!(board[*p], Cell::Void))
+                    .collect::<Vec<_>>();
+                if cells.is_empty() {
+                    continue;
+                }
+                let revealed = cells
+                    .iter()
+                    .filter(|p| matches!(board[**p], Cell::Discovered(_)))
+                    .count();
+                #[allow(
+                    clippy::cast_precision_loss,
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss
+                )]
+                let shade = (60. + 160. * revealed as f32 / cells.len() as f32) as u8;
+                #[allow(clippy::cast_precision_loss)]
+                let rect = Rect::from_min_size(
+                    origin + vec2(bx as f32, by as f32) * side,
+                    Vec2::splat(side),
+                );
+                painter.rect_filled(rect.shrink(0.5), 0., Color32::from_gray(shade));
+                if cells.iter().any(|p| flags.contains(p)) {
+                    painter.circle_filled(rect.center(), side / 4., Color32::ORANGE);
+                }
+            }
+        }
+        if response.clicked()
+            && let Some(pos) = response.interact_pointer_pos()
+        {
+            let at = pos - origin;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let (bx, by) = ((at.x / side) as usize, (at.y / side) as usize);
+            clicked = Some((
+                (bx * block + block / 2).min(width - 1),
+                (by * block + block / 2).min(height - 1),
+            ));
+        }
+    });
+    clicked
+}
@@ -0,0 +1,250 @@
+use std::{
+    fmt::Write,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use bastard_minesweeper::{Game, pack::Pack};
+use eframe::egui::{Button, CollapsingHeader, Context, ScrollArea, TextEdit, Window};
+use sha2::{Digest, Sha256};
+
+/// Longest a download may take before curl gives up on it
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Board packs installed in a directory, to pick a board from or install more into
+pub struct Packs {
+    dir: PathBuf,
+    /// Every `.bmpack` file found, by file name, or why it couldn't be read
+    installed: Vec<(String, Result<Pack, String>)>,
+    /// URL or path of a pack to install
+    source: String,
+    /// SHA-256 the pack to install should have, in hex
+    checksum: String,
+    /// Installing a pack, ending in its file name and checksum
+    installing: Option<JoinHandle<Result<String, String>>>,
+    /// How the last install went
+    message: Option<String>,
+}
+
+impl Packs {
+    /// Read every pack in `dir`
+    pub fn load(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            installed: read(dir),
+            source: String::new(),
+            checksum: String::new(),
+            installing: None,
+            message: None,
+        }
+    }
+
+    /// Show the packs and their boards, putting the game for a board the player picks in
+    /// `picked`. Returns false once the player closes the window.
+    pub fn show(&mut self, ctx: &Context, picked: &mut Option<Game>) -> bool {
+        if let Some(installing) = self.installing.take_if(|i| i.is_finished()) {
+            self.message = Some(match installing.join() {
+                Ok(Ok(file)) => {
+                    self.installed = read(&self.dir);
+                    self.source.clear();
+                    self.checksum.clear();
+                    format!("Installed {file}")
+                }
+                Ok(Err(e)) => e,
+                Err(_) => "Installing crashed".to_string(),
+            });
+        } else if self.installing.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+        let mut open = true;
+        Window::new("Board packs").open(&mut open).show(ctx, |ui| {
+            // Browsers can't run curl or start threads, so the web build can't install packs
+            if cfg!(not(target_arch = "wasm32")) {
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.source).hint_text("URL or file"));
+                    ui.add(TextEdit::singleline(&mut self.checksum).hint_text("SHA-256, for URLs"));
+                    let ready = self.installing.is_none() && !self.source.trim().is_empty();
+                    if ui.add_enabled(ready, Button::new("Install")).clicked() {
+                        let (source, checksum, dir) = (
+                            self.source.trim().to_string(),
+                            self.checksum.trim().to_string(),
+                            self.dir.clone(),
+                        );
+                        self.message = None;
+                        self.installing = Some(std::thread::spawn(move || {
+                            install(&source, &checksum, &dir)
+                        }));
+                    }
+                });
+            }
+            if self.installing.is_some() {
+                ui.label("Installing...");
+            } else if let Some(message) = &self.message {
+                ui.label(message);
+            }
+            ui.separator();
+            if self.installed.is_empty() {
+                ui.label(format!(
+                    "No packs yet, install one or put .bmpack files in {}",
+                    self.dir.display()
+                ));
+            }
+            ScrollArea::vertical().show(ui, |ui| {
+                for (file, pack) in &self.installed {
+                    let pack = match pack {
+                        Ok(pack) => pack,
+                        Err(e) => {
+                            ui.label(format!("{file}: {e}"));
+                            continue;
+                        }
+                    };
+                    let title = if pack.title.is_empty() {
+                        file
+                    } else {
+                        &pack.title
+                    };
+                    CollapsingHeader::new(title).id_salt(file).show(ui, |ui| {
+                        if !pack.author.is_empty() {
+                            ui.label(format!("By {}", pack.author));
+                        }
+                        if !pack.difficulty.is_empty() {
+                            ui.label(format!("Difficulty: {}", pack.difficulty));
+                        }
+                        for board in &pack.boards {
+                            ui.separator();
+                            let (width, height) = board.board.dim();
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}: {width}x{height}, {} bombs",
+                                    board.name,
+                                    board.bombs()
+                                ));
+                                if ui.button("Play").clicked() {
+                                    *picked = Some(board.game());
+                                }
+                            });
+                            if !board.note.is_empty() {
+                                ui.small(&board.note);
+                            }
+                        }
+                    });
+                }
+            });
+        });
+        open
+    }
+}
+
+/// Every `.bmpack` file in `dir`, sorted by name
+fn read(dir: &Path) -> Vec<(String, Result<Pack, String>)> {
+    let mut packs = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "bmpack"))
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let pack = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| Pack::parse(&text));
+            (name, pack)
+        })
+        .collect::<Vec<_>>();
+    packs.sort_by(|a, b| a.0.cmp(&b.0));
+    packs
+}
+
+/// Fetch the pack at `source`, a URL or a path, check it has the SHA-256 `checksum` and is a
+/// pack, and copy it into `dir`. URLs are downloaded with `curl` and need a checksum, local
+/// files are only checked against one if it's given. A different pack with the same name gets
+/// a number added to the new one's, and the same pack again is left as it is.
+fn install(source: &str, checksum: &str, dir: &Path) -> Result<String, String> {
+    let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+        if checksum.is_empty() {
+            return Err("Give the SHA-256 of a pack to download".to_string());
+        }
+        let output = Command::new("curl")
+            .args([
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--location",
+                "--max-time",
+            ])
+            .arg(DOWNLOAD_TIMEOUT.as_secs().to_string())
+            .arg(source)
+            .output()
+            .map_err(|e| format!("Couldn't run curl: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Couldn't download {source}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        output.stdout
+    } else {
+        std::fs::read(source).map_err(|e| format!("Couldn't read {source}: {e}"))?
+    };
+    let actual = Sha256::digest(&bytes)
+        .iter()
+        .fold(String::new(), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        });
+    if !checksum.is_empty() && !checksum.eq_ignore_ascii_case(&actual) {
+        return Err(format!("Checksum is {actual}, not {checksum}"));
+    }
+    let text = String::from_utf8(bytes).map_err(|_| "Not a pack: not text".to_string())?;
+    let pack = Pack::parse(&text).map_err(|e| format!("Not a pack: {e}"))?;
+    // Named after the file it came from, or its title if that doesn't say much
+    let stem = source
+        .split(['?', '#'])
+        .next()
+        .and_then(|s| s.rsplit(['/', '\\']).next())
+        .map(|s| s.strip_suffix(".bmpack").unwrap_or(s))
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&pack.title);
+    let stem = stem
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let stem = stem.trim_start_matches('.');
+    let stem = if stem.is_empty() { "pack" } else { stem };
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    // A different pack already installed under the name gets to keep it
+    for n in 1.. {
+        let file = if n == 1 {
+            format!("{stem}.bmpack")
+        } else {
+            format!("{stem}-{n}.bmpack")
+        };
+        let path = dir.join(&file);
+        match std::fs::read(&path) {
+            Ok(existing) if existing == text.as_bytes() => {
+                return Ok(format!(
+                    "{file} ({actual}), the same as the copy already there"
+                ));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                std::fs::write(&path, text).map_err(|e| e.to_string())?;
+                return Ok(format!("{file} ({actual})"));
+            }
+            Err(e) => return Err(format!("Couldn't read {file}: {e}")),
+        }
+    }
+    unreachable!("some numbered name is free")
+}
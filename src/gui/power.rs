@@ -0,0 +1,23 @@
+use std::fs;
+
+/// Whether the machine is running on battery, going by `/sys/class/power_supply`.
+/// False if there's no way to tell, or on anything but Linux.
+pub fn on_battery() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let read = |path: std::path::PathBuf| fs::read_to_string(path).unwrap_or_default();
+    let mut battery = false;
+    for supply in supplies.flatten() {
+        let path = supply.path();
+        match read(path.join("type")).trim() {
+            "Mains" if read(path.join("online")).trim() == "1" => return false,
+            "Battery" if read(path.join("status")).trim() == "Discharging" => battery = true,
+            _ => {}
+        }
+    }
+    battery
+}
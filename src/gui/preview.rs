@@ -0,0 +1,55 @@
+use bastard_minesweeper::{Game, format_coord};
+use eframe::egui::{Context, Window};
+
+/// What could happen if a cell were clicked, worked out without touching the game
+pub struct Preview {
+    cell: (usize, usize),
+    /// Whether the bastard would make it a bomb, in bastard mode
+    bastard: Option<bool>,
+    /// Chance of a bomb if every consistent board were equally likely
+    uniform: f64,
+    /// Chance of each number, given the cell is safe
+    outcomes: Vec<f64>,
+}
+
+impl Preview {
+    /// `None` if the solver can't read the game's numbers
+    pub fn new(game: &Game, (x, y): (usize, usize)) -> Option<Self> {
+        let solver = game.solver()?;
+        Some(Self {
+            cell: (x, y),
+            // Unassigned quantum cells count as bombs, so the bastard's choice is already made
+            bastard: game.bastard.then(|| game.board[(x, y)].is_bomb()),
+            uniform: solver.probability(x, y),
+            outcomes: solver.outcomes(&game.seen(), x, y),
+        })
+    }
+
+    /// Returns false once the player closes the window
+    pub fn show(&self, ctx: &Context) -> bool {
+        let mut open = true;
+        Window::new(format!(
+            "Preview {}",
+            format_coord(self.cell.0, self.cell.1)
+        ))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            if let Some(bomb) = self.bastard {
+                ui.label(if bomb {
+                    "The bastard would make it a bomb"
+                } else {
+                    "The bastard has to leave it safe"
+                });
+            }
+            ui.label(format!(
+                "{:.1}% chance of a bomb if every board is equally likely",
+                self.uniform * 100.
+            ));
+            ui.separator();
+            for (n, p) in self.outcomes.iter().enumerate().filter(|(_, p)| **p > 0.) {
+                ui.label(format!("Shows {n}: {:.1}%", p * 100.));
+            }
+        });
+        open
+    }
+}
@@ -0,0 +1,113 @@
+use bastard_minesweeper::{CancellationToken, CollapseObserver, CollapseOutcome, CollapseTrace};
+#[cfg(not(target_arch = "wasm32"))]
+use indicatif::{ProgressBar, ProgressStyle};
+
+/// Shows how collapses are going on the terminal, handing each finished one to `done`.
+/// Gives up once `cancel` is cancelled. The web build has no terminal for a progress bar.
+pub struct Progress<F> {
+    #[cfg(not(target_arch = "wasm32"))]
+    bar: Option<ProgressBar>,
+    cancel: CancellationToken,
+    done: F,
+}
+
+impl<F: FnMut(&CollapseTrace)> Progress<F> {
+    pub fn new(cancel: CancellationToken, done: F) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            bar: None,
+            cancel,
+            done,
+        }
+    }
+}
+
+impl<F: FnMut(&CollapseTrace)> CollapseObserver for Progress<F> {
+    fn on_start(&mut self, cells: usize, max_bombs: usize) {
+        eprintln!("Collapsing {cells} quantum cells, {max_bombs} bombs to place");
+    }
+
+    fn on_states_found(&mut self, states: usize, approximate: bool) {
+        if approximate {
+            eprintln!("Too many possible states to count, found {states} by searching");
+        } else {
+            eprintln!("{states} possible states");
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let bar = ProgressBar::no_length().with_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner} {pos}/{len} {per_sec}")
+                    .unwrap(),
+            );
+            self.bar = Some(bar);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn on_progress(&mut self, done: usize, total: usize) {
+        if let Some(bar) = &self.bar {
+            bar.set_length(total as u64);
+            bar.set_position(done as u64);
+        }
+    }
+
+    fn on_done(&mut self, trace: &CollapseTrace) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+        match trace.outcome {
+            CollapseOutcome::Cancelled => {
+                eprintln!("Collapse aborted");
+                return;
+            }
+            CollapseOutcome::DeadEnd => eprintln!("No placement of bombs fits the numbers"),
+            CollapseOutcome::Chosen => eprintln!(
+                "{} sets of numbers in {}s, chose one with {} bomb placements",
+                trace.unique_number_sets(),
+                trace.sample_time.as_secs_f32(),
+                trace.chosen_states,
+            ),
+            CollapseOutcome::NothingToAssign | CollapseOutcome::OutOfBombs => {}
+        }
+        (self.done)(trace);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+/// Counts a subcommand's work up to `len` on the terminal, from any thread
+pub struct Counter {
+    #[cfg(not(target_arch = "wasm32"))]
+    bar: ProgressBar,
+}
+
+impl Counter {
+    /// A bar for `len` of `what`, like `boards`
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn new(len: u64, what: &str) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            bar: ProgressBar::new(len).with_style(
+                ProgressStyle::default_bar()
+                    .template(&format!("{{bar}} {{pos}}/{{len}} {what} {{per_sec}}"))
+                    .unwrap(),
+            ),
+        }
+    }
+
+    /// One more done
+    pub fn inc(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.bar.inc(1);
+    }
+
+    /// Take the bar off the terminal
+    pub fn finish(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,93 @@
+use std::{collections::HashSet, time::Duration};
+
+use bastard_minesweeper::solver::Drill;
+use eframe::egui::Ui;
+use web_time::Instant;
+
+/// Find every provable cell in a position before time runs out
+pub struct Quiz {
+    safe: HashSet<(usize, usize)>,
+    mines: HashSet<(usize, usize)>,
+    /// Cells the player has marked as safe
+    pub marked: HashSet<(usize, usize)>,
+    deadline: Instant,
+    pub score: Option<Score>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Score {
+    /// Provable cells the player marked correctly
+    pub correct: usize,
+    /// Cells the player marked that weren't provable that way
+    pub wrong: usize,
+    /// Provable cells the player didn't mark
+    pub missed: usize,
+}
+
+impl Score {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn accuracy(&self) -> f64 {
+        let total = self.correct + self.wrong + self.missed;
+        if total == 0 {
+            1.
+        } else {
+            self.correct as f64 / total as f64
+        }
+    }
+}
+
+impl Quiz {
+    pub fn new(drill: &Drill, limit: Duration) -> Self {
+        Self {
+            safe: drill.answer.iter().copied().collect(),
+            mines: drill.mines.iter().copied().collect(),
+            marked: HashSet::new(),
+            deadline: Instant::now() + limit,
+            score: None,
+        }
+    }
+
+    pub fn toggle(&mut self, cell: (usize, usize)) {
+        if self.score.is_none() && !self.marked.remove(&cell) {
+            self.marked.insert(cell);
+        }
+    }
+
+    /// Score the marks and flags, if that hasn't happened yet
+    pub fn finish(&mut self, flags: &HashSet<(usize, usize)>) {
+        if self.score.is_some() {
+            return;
+        }
+        let correct =
+            self.marked.intersection(&self.safe).count() + flags.intersection(&self.mines).count();
+        let marks = self.marked.len() + flags.len();
+        self.score = Some(Score {
+            correct,
+            wrong: marks - correct,
+            missed: self.safe.len() + self.mines.len() - correct,
+        });
+    }
+
+    /// Show the countdown or the score, finishing the quiz when time is up
+    pub fn ui(&mut self, ui: &mut Ui, flags: &HashSet<(usize, usize)>) {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            self.finish(flags);
+        }
+        if let Some(score) = self.score {
+            ui.label(format!(
+                "{} right, {} wrong, {} missed ({:.0}%)",
+                score.correct,
+                score.wrong,
+                score.missed,
+                score.accuracy() * 100.
+            ));
+        } else {
+            ui.label(format!("{}s left", left.as_secs()));
+            if ui.button("Submit").clicked() {
+                self.finish(flags);
+            }
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+        }
+    }
+}
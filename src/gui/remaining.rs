@@ -0,0 +1,48 @@
+use std::ops::RangeInclusive;
+
+use bastard_minesweeper::Game;
+
+use super::{heat::numbers_key, locale::Locale};
+
+/// Counts above this are shown as a power of ten
+const EXACT_LIMIT: f64 = 1e6;
+
+/// How many ways of placing the bombs still fit what the player has uncovered, and how many
+/// bombs there can be
+#[derive(Default)]
+pub struct Remaining {
+    /// A hash of the revealed numbers, the natural log of the count found from them and the
+    /// fewest and most bombs they allow
+    cache: Option<(u64, f64, Option<RangeInclusive<usize>>)>,
+}
+
+impl Remaining {
+    /// Count again if the revealed numbers changed
+    pub fn update(&mut self, game: &Game) {
+        let key = numbers_key(game);
+        if self.cache.as_ref().is_some_and(|(k, ..)| *k == key) {
+            return;
+        }
+        self.cache = game
+            .solver()
+            .map(|solver| (key, solver.ln_configurations(), solver.bomb_range()));
+    }
+
+    /// The count, exact while it's small enough to read and as an order of magnitude after,
+    /// written the `locale`'s way
+    pub fn label(&self, locale: &Locale) -> Option<String> {
+        let &(_, ln, _) = self.cache.as_ref()?;
+        Some(if ln == f64::NEG_INFINITY {
+            "No layouts fit".to_string()
+        } else if ln < EXACT_LIMIT.ln() {
+            format!("{} layouts left", locale.number(ln.exp(), 0))
+        } else {
+            format!("~10^{:.0} layouts left", ln / std::f64::consts::LN_10)
+        })
+    }
+
+    /// Fewest and most bombs the board can hold, when anything fits
+    pub fn mines(&self) -> Option<RangeInclusive<usize>> {
+        self.cache.as_ref()?.2.clone()
+    }
+}
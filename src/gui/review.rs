@@ -0,0 +1,40 @@
+use bastard_minesweeper::solver::Verdict;
+use eframe::egui::{Color32, Context, RichText, ScrollArea, Window};
+
+/// List every move of a lost game with how it looks in hindsight.
+/// Returns false once the player closes the window.
+pub fn show(ctx: &Context, moves: &[((usize, usize), Verdict)]) -> bool {
+    let mut open = true;
+    Window::new("Review").open(&mut open).show(ctx, |ui| {
+        for verdict in [
+            Verdict::Forced,
+            Verdict::OptimalGuess,
+            Verdict::SuboptimalGuess,
+            Verdict::Blunder,
+        ] {
+            let count = moves.iter().filter(|(_, v)| *v == verdict).count();
+            ui.label(format!("{}: {count}", describe(verdict)));
+        }
+        ui.separator();
+        ScrollArea::vertical().show(ui, |ui| {
+            for (i, ((x, y), verdict)) in moves.iter().enumerate() {
+                let text = format!("{}. ({x}, {y}) {}", i + 1, describe(*verdict));
+                ui.label(match verdict {
+                    Verdict::Forced | Verdict::OptimalGuess => RichText::new(text),
+                    Verdict::SuboptimalGuess => RichText::new(text).color(Color32::YELLOW),
+                    Verdict::Blunder => RichText::new(text).color(Color32::RED),
+                });
+            }
+        });
+    });
+    open
+}
+
+fn describe(verdict: Verdict) -> &'static str {
+    match verdict {
+        Verdict::Forced => "Forced",
+        Verdict::OptimalGuess => "Best guess",
+        Verdict::SuboptimalGuess => "Worse guess",
+        Verdict::Blunder => "Blunder",
+    }
+}
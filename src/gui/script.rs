@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use bastard_minesweeper::GameState;
+use eframe::{
+    App, Frame,
+    egui::{Context, Event, Key, Modifiers, PointerButton, Pos2, RawInput, Rect, Vec2},
+};
+
+use super::input::{Action, parse_command};
+
+/// Size of the pretend window scripts run in
+const SCREEN: Vec2 = Vec2::new(1024., 768.);
+/// Time that passes in a frame
+const FRAME: f32 = 1. / 60.;
+
+/// One line of an input script
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Press and release a key
+    Key(Key),
+    /// Click somewhere on the screen
+    Click(Pos2, PointerButton),
+    /// A typed move, like in the coordinate box
+    Move((usize, usize), Option<Action>),
+    /// Let this much time pass
+    Wait(Duration),
+    /// Keep drawing frames until the collapse worker is done
+    Idle,
+    /// Fail the script unless the game is in this state
+    Expect(GameState),
+}
+
+/// Read a script, one step per line, with `#` starting a comment:
+///
+/// - `key Space` presses a key
+/// - `click 100 200` and `right-click 100 200` click a point on the screen
+/// - `r C7`, `f C7` and `C7` are typed moves
+/// - `wait 500` lets 500 milliseconds pass
+/// - `idle` waits for the collapse to finish
+/// - `expect playing`, `expect won` and `expect lost` check the game state
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| step(line).ok_or_else(|| format!("line {number}: {line:?}")))
+        .collect()
+}
+
+fn step(line: &str) -> Option<Step> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let point = |x: &str, y: &str| Some(Pos2::new(x.parse().ok()?, y.parse().ok()?));
+    Some(match words.as_slice() {
+        ["key", name] => Step::Key(Key::from_name(name)?),
+        ["click", x, y] => Step::Click(point(x, y)?, PointerButton::Primary),
+        ["right-click", x, y] => Step::Click(point(x, y)?, PointerButton::Secondary),
+        ["wait", ms] => Step::Wait(Duration::from_millis(ms.parse().ok()?)),
+        ["idle"] => Step::Idle,
+        ["expect", "playing"] => Step::Expect(GameState::Playing),
+        ["expect", "won"] => Step::Expect(GameState::Won),
+        // The losing cell isn't checked, just that there is one
+        ["expect", "lost"] => Step::Expect(GameState::Lost((0, 0))),
+        _ => {
+            let (cell, action) = parse_command(line)?;
+            Step::Move(cell, action)
+        }
+    })
+}
+
+/// Runs an app without a window, feeding it made up input
+pub struct Headless {
+    pub ctx: Context,
+    frame: Frame,
+    time: f64,
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self {
+            ctx: Context::default(),
+            frame: Frame::_new_kittest(),
+            time: 0.,
+        }
+    }
+}
+
+impl Headless {
+    /// Draw one frame with `events` as its input
+    pub fn frame(&mut self, app: &mut impl App, events: Vec<Event>) {
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, SCREEN)),
+            time: Some(self.time),
+            predicted_dt: FRAME,
+            events,
+            ..RawInput::default()
+        };
+        self.time += f64::from(FRAME);
+        // Nothing is drawn, so the output doesn't matter
+        let _ = self.ctx.run(input, |ctx| app.update(ctx, &mut self.frame));
+    }
+
+    /// Press and release `key` over two frames
+    pub fn key(&mut self, app: &mut impl App, key: Key) {
+        for pressed in [true, false] {
+            let event = Event::Key {
+                key,
+                physical_key: None,
+                pressed,
+                repeat: false,
+                modifiers: Modifiers::NONE,
+            };
+            self.frame(app, vec![event]);
+        }
+    }
+
+    /// Move the pointer to `pos`, then press and release `button` over two frames
+    pub fn click(&mut self, app: &mut impl App, pos: Pos2, button: PointerButton) {
+        self.frame(app, vec![Event::PointerMoved(pos)]);
+        for pressed in [true, false] {
+            let event = Event::PointerButton {
+                pos,
+                button,
+                pressed,
+                modifiers: Modifiers::NONE,
+            };
+            self.frame(app, vec![event]);
+        }
+    }
+
+    /// Draw frames until `duration` has passed on the script's clock
+    pub fn wait(&mut self, app: &mut impl App, duration: Duration) {
+        let until = self.time + duration.as_secs_f64();
+        while self.time < until {
+            self.frame(app, Vec::new());
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use eframe::egui::{RichText, Slider, Ui};
+
+use super::input::ButtonMap;
+
+/// User-facing toggles that don't affect the rules of the game
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Settings {
+    pub effects: bool,
+    /// Screen shake intensity on detonation, 0 disables it
+    pub shake: f32,
+    /// Low-vision mode: large cells with thick borders and bold glyphs
+    pub big_cells: bool,
+    pub buttons: ButtonMap,
+    /// Label rows and columns, and take moves typed as coordinates
+    pub coordinates: bool,
+    /// Explain each collapse in bastard mode
+    pub explain: bool,
+    /// Go easy on the battery: no animations, fewer repaints, cheaper collapses
+    pub energy_saver: bool,
+    /// Show what kind of board an honest game dealt before the first click
+    pub board_preview: bool,
+    /// Suggest where to click first on an honest board
+    pub opening_advice: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            effects: true,
+            shake: 0.5,
+            big_cells: false,
+            buttons: ButtonMap::default(),
+            coordinates: false,
+            explain: false,
+            energy_saver: false,
+            board_preview: true,
+            opening_advice: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether particle effects should run
+    pub fn animate(&self) -> bool {
+        self.effects && !self.energy_saver
+    }
+
+    /// Screen shake intensity, 0 in energy saver mode
+    pub fn shake_intensity(&self) -> f32 {
+        if self.energy_saver { 0. } else { self.shake }
+    }
+
+    /// Side length of a cell on screen
+    pub fn cell_size(&self) -> f32 {
+        if self.big_cells { 48. } else { 16. }
+    }
+
+    /// Text for a cell, made large and bold in big cell mode
+    pub fn glyph(&self, text: &str) -> RichText {
+        if self.big_cells {
+            RichText::new(text).size(32.).strong()
+        } else {
+            RichText::new(text)
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.effects, "Particle effects");
+        ui.add(Slider::new(&mut self.shake, 0.0..=1.0).text("Screen shake"));
+        ui.checkbox(&mut self.big_cells, "Big cells");
+        ui.checkbox(&mut self.coordinates, "Coordinates");
+        ui.checkbox(&mut self.explain, "Explain the bastard");
+        ui.checkbox(&mut self.energy_saver, "Energy saver");
+        ui.checkbox(&mut self.board_preview, "Board preview");
+        ui.checkbox(&mut self.opening_advice, "First click advice");
+        ui.separator();
+        for map in ButtonMap::ALL {
+            ui.radio_value(&mut self.buttons, map, map.name());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use bastard_minesweeper::{BoardStatistics, stats};
+use eframe::egui::{Button, Context, Window};
+
+use super::locale::Locale;
+
+/// Show what kind of board this is before the first click, without saying where anything is,
+/// next to the player's `rating`, with a button to deal another if there are `rerolls` left.
+/// Numbers are written the `locale`'s way. Returns whether the player re-rolled.
+pub fn show(
+    ctx: &Context,
+    locale: &Locale,
+    statistics: &BoardStatistics,
+    rating: f64,
+    rerolls: Option<usize>,
+) -> bool {
+    let mut reroll = false;
+    Window::new("Board")
+        .collapsible(true)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.with_layout(locale.column(), |ui| {
+                ui.label(format!("3BV {}", locale.count(statistics.three_bv)));
+                ui.label(format!(
+                    "{} openings, the biggest clearing {} cells",
+                    locale.count(statistics.openings),
+                    locale.count(statistics.largest_opening)
+                ));
+                ui.label(format!(
+                    "{} islands to clear cell by cell",
+                    locale.count(statistics.islands)
+                ));
+                ui.label(format!(
+                    "{}% of bombs on the edge",
+                    locale.number(statistics.edge_mines * 100., 0)
+                ));
+                ui.label(format!(
+                    "{} coin flips for a careful player",
+                    locale.count(statistics.fifty_fifties)
+                ));
+                ui.label(format!(
+                    "Rated {}, against your {}",
+                    locale.number(stats::board_rating(statistics), 0),
+                    locale.number(rating, 0)
+                ));
+                if let Some(rerolls) = rerolls {
+                    ui.separator();
+                    reroll = ui
+                        .add_enabled(
+                            rerolls > 0,
+                            Button::new(format!("Re-roll ({rerolls} left)")),
+                        )
+                        .clicked();
+                }
+            });
+        });
+    reroll
+}
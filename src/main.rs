@@ -1,18 +1,91 @@
 #![warn(clippy::pedantic)]
 
-use std::{collections::HashSet, thread::JoinHandle, usize};
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use bastard_minesweeper::{Board, Cell};
-use clap::Parser;
-use eframe::{
-    NativeOptions,
-    egui::{CentralPanel, TopBottomPanel},
+#[cfg(feature = "serde")]
+use bastard_minesweeper::save::SavedGame;
+use bastard_minesweeper::{
+    Board, BoardStatistics, Cell, CollapseConfig, CollapseTrace, Game, GameState, Hint,
+    Neighborhood, Reveal, column_name, format_coord,
+    guess::{GuessPolicy, Safest},
+    opening::{Advice, FirstClick},
+    policy::Policy,
+    render::{Glyphs, TextStyle},
+    rules::{AntiMines, Classic, FlagLimit, Liar, Multimines, Ruleset},
+    selfcheck,
+    simulate::{self, Player},
+    solver::{self, Grade, Symmetry, Verdict},
+    spread::Spread,
+    stats,
+    worker::Worker,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use cli::output::{
+    AnalyzeReport, BenchReport, Output, PackBoard, PackReport, PlayerResult, PolicyResult,
+    SeedReport, SelfcheckReport, SimulateReport, print_json,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use eframe::NativeOptions;
+use eframe::egui::{
+    Align2, Area, Button, CentralPanel, Color32, Context, Frame, Id, Key, Label, PointerButton,
+    Pos2, ScrollArea, Sense, Slider, StrokeKind, TextEdit, TopBottomPanel, Ui, ViewportCommand,
+    Window, show_tooltip_at_pointer,
+};
+use egui_extras::{Column, TableBody, TableBuilder};
+use gui::{
+    analysis::Analysis,
+    branch::{Branch, Snapshot},
+    clock::Waiting,
+    crash,
+    deal::{Dealer, honest_board},
+    effects::Effects,
+    frontier::FrontierView,
+    heat::Heat,
+    input::{Action, Cycle, Focus, keyboard_actions, parse_command},
+    locale::Locale,
+    log::{Event, EventLog},
+    overview,
+    packs::Packs,
+    preview::Preview,
+    progress::{Counter, Progress},
+    quiz::Quiz,
+    remaining::Remaining,
+    review,
+    script::{Headless, Step},
+    settings::Settings,
 };
-use egui_extras::{Column, TableBuilder};
 use itertools::Itertools;
-use rand::{Rng, rng};
+use rand::{Rng, SeedableRng, rng, rngs::StdRng};
+use rayon::prelude::*;
+use web_time::Instant;
+
+mod cli;
+mod gui;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+/// How long a collapse explanation stays on screen
+const EXPLANATION_SECS: f64 = 8.;
+/// Chance of a lie when liar mode is toggled on without `--liar`
+const DEFAULT_LIES: f64 = 0.1;
+/// Boards dealt to work out where the first click goes best
+const ADVICE_SAMPLES: usize = 200;
+/// How long a script's `idle` waits for a collapse before giving up
+const SCRIPT_IDLE_LIMIT: Duration = Duration::from_mins(1);
 
 #[derive(Parser)]
+// `-h` is the height, so help is only `--help`
+#[command(disable_help_flag = true)]
+#[allow(clippy::struct_excessive_bools)]
 struct Args {
     #[arg(short, long, default_value = "10")]
     pub width: usize,
@@ -24,257 +97,2338 @@ struct Args {
     /// Bastard mode: Use quantum cells to make the game as annoying as possible
     #[arg(short, long)]
     pub bastard: bool,
+    /// Angel mode: quantum cells like in bastard mode, but collapsing to tell you as much as possible
+    #[arg(long, conflicts_with = "bastard")]
+    pub angel: bool,
+    /// Quantum cells like in bastard mode, collapsing by this policy
+    #[arg(long, value_enum, conflicts_with_all = ["bastard", "angel"])]
+    pub policy: Option<Policy>,
+    /// Kaboom rules: quantum cells that are only bombs when you had to guess and every layout
+    /// agrees, or when you guessed while a safe cell was there to be found
+    #[arg(long)]
+    pub kaboom: bool,
+    /// Lazy collapses: only settle the hidden cells the numbers force, deciding the rest when
+    /// they're revealed
+    #[arg(long)]
+    pub lazy: bool,
+    /// How bombs sit relative to each other: dealt like this on honest boards, and favored by
+    /// collapses in bastard mode. Overrides the config file.
+    #[arg(long, value_enum, conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess"])]
+    pub spread: Option<Spread>,
+    /// Sudden collapses: after this many seconds without a move, every hidden cell collapses
+    /// again, flags or not
+    #[arg(long)]
+    pub sudden_collapse: Option<u64>,
+    /// Append a line of JSON describing every collapse to this file
+    #[arg(long)]
+    pub trace: Option<PathBuf>,
+    /// Start from a position whose easiest forced move needs this kind of reasoning
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse"])]
+    pub drill: Option<Grade>,
+    /// Lay the bombs out with this symmetry, in a board that can be cleared without guessing
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill"])]
+    pub symmetric: Option<Symmetry>,
+    /// Mark every provably safe cell and flag every provable mine in a position within this many seconds
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric"])]
+    pub quiz: Option<u64>,
+    /// Low-vision mode: large cells with thick borders and bold glyphs
+    #[arg(long)]
+    pub big_cells: bool,
+    /// Practice mode: F5 quicksaves and F9 quickloads, and the game doesn't count
+    #[arg(long)]
+    pub practice: bool,
+    /// Assist mode: allow hints like the mine probability heat map
+    #[arg(long)]
+    pub assist: bool,
+    /// Have hints suggest a guess once logic runs out by asking this ONNX model, instead of
+    /// picking the cell least likely to be a bomb
+    #[cfg(feature = "onnx")]
+    #[arg(long, requires = "assist")]
+    pub guess_model: Option<PathBuf>,
+    /// Threads to collapse on, overriding the config file
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Run collapses at low priority so the rest of the machine stays responsive
+    #[arg(long)]
+    pub low_priority: bool,
+    /// Go easy on the battery: no animations, fewer repaints, cheaper collapses
+    #[arg(long)]
+    pub energy_saver: bool,
+    /// Generate the board and make every collapse from this seed, so the game can be replayed
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Publish every game event as a line of JSON to programs connected to this unix socket
+    #[arg(long)]
+    pub observe: Option<PathBuf>,
+    /// Play this script of input events without opening a window, failing if an expectation isn't met
+    #[arg(long)]
+    pub script: Option<PathBuf>,
+    /// Play in the terminal instead of a window
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "script")]
+    pub tui: bool,
+    /// Deal a board that can be cleared from the middle without guessing, with the middle open
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric", "quiz"])]
+    pub no_guess: bool,
+    /// Deal honest boards again until a careful player would face at most this many coin flips
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric", "quiz", "no_guess"])]
+    pub max_fifty_fifties: Option<usize>,
+    /// Deal honest boards again until they have at least this many openings, areas with no
+    /// bombs around that clear in one click
+    #[arg(long, conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric", "quiz", "no_guess"])]
+    pub min_openings: Option<usize>,
+    /// Times an honest board can be dealt again before the first click
+    #[arg(long, default_value = "3")]
+    pub rerolls: usize,
+    /// How many 3BV a second you play at, which sets the par time of honest boards
+    #[arg(long, default_value_t = stats::DEFAULT_SKILL)]
+    pub skill: f64,
+    /// Deal honest boards rated close to your own rating
+    #[arg(long)]
+    pub adaptive: bool,
+    /// Don't allow taking reveals back with Ctrl+Z
+    #[arg(long)]
+    pub no_undo: bool,
+    /// Allow no more flags than there are bombs
+    #[arg(long)]
+    pub flag_limit: bool,
+    /// Wrap the edges around, so cells on one edge neighbor the cells on the other
+    #[arg(long, conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess"])]
+    pub wrap: bool,
+    /// Which cells numbers count: moore, von-neumann or knight
+    #[arg(long, default_value = "moore", conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess"])]
+    pub neighborhood: Neighborhood,
+    /// Play on the shape drawn in this file instead of a rectangle: `.` and spaces are holes,
+    /// anything else is a cell. Sets the width and height.
+    #[arg(long, conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess"])]
+    pub stencil: Option<PathBuf>,
+    /// Stack this many layers of the board on top of each other, so numbers count the cells
+    /// in the layers above and below too
+    #[arg(long, default_value = "1", conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess", "stencil"])]
+    pub layers: usize,
+    /// Multimines: cells can hold up to this many bombs, and numbers count every one. Honest
+    /// games only, since quantum cells and the solver hold one bomb a cell.
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=9), conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric", "quiz", "no_guess", "max_fifty_fifties", "spread", "assist", "adaptive"])]
+    pub multimines: u8,
+    /// Anti-mines: this many of the bombs take one off the numbers around them instead of
+    /// adding one, so numbers can go below zero. They still have to be avoided. Honest games
+    /// only, since quantum cells and the solver can't count a bomb as minus one.
+    #[arg(long, default_value = "0", conflicts_with_all = ["bastard", "angel", "policy", "kaboom", "sudden_collapse", "drill", "symmetric", "quiz", "no_guess", "max_fifty_fifties", "spread", "assist", "adaptive", "multimines"])]
+    pub anti_mines: usize,
+    /// Liar mode: each number has this chance, from 0 to 1, of being shown one off from
+    /// the truth, decided once when it's revealed
+    #[arg(long, conflicts_with_all = ["drill", "symmetric", "quiz", "no_guess", "multimines", "anti_mines"])]
+    pub liar: Option<f64>,
+    /// Print boards on the terminal without colors
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Characters to print boards on the terminal with
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub glyphs: Glyphs,
+    /// How subcommands print what they found
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: Output,
+    /// Print help
+    #[arg(long, global = true, action = clap::ArgAction::Help)]
+    pub help: Option<bool>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Subcommand)]
+enum Command {
+    /// Benchmark this machine and save collapse limits to the config file
+    Tune {
+        /// How long a single collapse should take, in seconds
+        #[arg(long, default_value = "2")]
+        target: f64,
+    },
+    /// Run internal consistency checks on the engine and print a report
+    Selfcheck {
+        /// How many games to play through
+        #[arg(long, default_value = "20")]
+        rounds: usize,
+    },
+    /// Play bastard games with synthetic players and print how each one fared
+    Simulate {
+        /// Only simulate this kind of player, instead of every kind
+        #[arg(long)]
+        player: Option<Player>,
+        /// Games per player
+        #[arg(long, default_value = "20")]
+        games: usize,
+        #[arg(long, default_value = "8")]
+        width: usize,
+        #[arg(long, default_value = "8")]
+        height: usize,
+        #[arg(long, default_value = "10")]
+        bombs: usize,
+    },
+    /// Play the same seeded bastard games under each collapse policy and print how a
+    /// synthetic player fared against each
+    BenchStrategies {
+        /// The kind of player to pit against the policies
+        #[arg(long, default_value = "logician")]
+        player: Player,
+        /// Games per policy
+        #[arg(long, default_value = "20")]
+        games: usize,
+        #[arg(long, default_value = "8")]
+        width: usize,
+        #[arg(long, default_value = "8")]
+        height: usize,
+        #[arg(long, default_value = "10")]
+        bombs: usize,
+        /// Seed of the first game, counting up from there for the rest
+        #[arg(long, default_value = "0")]
+        seed: u64,
+    },
+    /// Search for a seed whose opening is about as hard as asked, and print it
+    FindSeed {
+        #[arg(long, default_value = "expert")]
+        preset: Preset,
+        /// Look for a bastard game instead of an honest one
+        #[arg(short, long)]
+        bastard: bool,
+        /// From 0 when every cell by the opening follows from one number, to 1 when none can be proven
+        #[arg(long)]
+        target_difficulty: f64,
+        /// How far from the target is close enough
+        #[arg(long, default_value = "0.05")]
+        tolerance: f64,
+        /// Give up after this many seeds
+        #[arg(long, default_value = "10000")]
+        tries: u64,
+    },
+    /// Write a pack of honest boards to a directory, one text file each, with a manifest
+    Generate {
+        /// How many boards to write
+        #[arg(long, default_value = "100")]
+        count: usize,
+        #[arg(long, default_value = "expert")]
+        preset: Preset,
+        /// Only boards that can be cleared from the middle without guessing, which comes
+        /// already revealed
+        #[arg(long)]
+        no_guess: bool,
+        /// The directory to write to, made if it isn't there
+        #[arg(long)]
+        out: PathBuf,
+        /// Seed for the whole pack, so the same pack can be written again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Print where first clicks go best on honest boards of a preset's size and density
+    Analyze {
+        #[arg(long, default_value = "expert")]
+        preset: Preset,
+        /// Boards to deal for each cell's average opening
+        #[arg(long, default_value = "1000")]
+        samples: usize,
+    },
+}
+
+/// The usual board sizes
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Preset {
+    /// 9x9 with 10 bombs
+    Beginner,
+    /// 16x16 with 40 bombs
+    Intermediate,
+    /// 30x16 with 99 bombs
+    Expert,
+}
+
+impl Preset {
+    /// Width, height and bombs
+    fn size(self) -> (usize, usize, usize) {
+        match self {
+            Preset::Beginner => (9, 9, 10),
+            Preset::Intermediate => (16, 16, 40),
+            Preset::Expert => (30, 16, 99),
+        }
+    }
+}
+
+fn run_command(command: Command, style: TextStyle, output: Output) {
+    match command {
+        Command::Tune { target } => tune(Duration::from_secs_f64(target), output),
+        Command::Selfcheck { rounds } => {
+            if !selfcheck(rounds, output) {
+                std::process::exit(1);
+            }
+        }
+        Command::Simulate {
+            player,
+            games,
+            width,
+            height,
+            bombs,
+        } => simulate(player, games, (width, height), bombs, output),
+        Command::BenchStrategies {
+            player,
+            games,
+            width,
+            height,
+            bombs,
+            seed,
+        } => bench_strategies(player, games, (width, height), bombs, seed, output),
+        Command::FindSeed {
+            preset,
+            bastard,
+            target_difficulty,
+            tolerance,
+            tries,
+        } => {
+            if !find_seed(
+                preset,
+                bastard,
+                target_difficulty,
+                tolerance,
+                tries,
+                style,
+                output,
+            ) {
+                std::process::exit(1);
+            }
+        }
+        Command::Generate {
+            count,
+            preset,
+            no_guess,
+            out,
+            seed,
+        } => {
+            if let Err(e) = generate(count, preset, no_guess, &out, seed, output) {
+                eprintln!("Failed to write to {}: {e}", out.display());
+                std::process::exit(1);
+            }
+        }
+        Command::Analyze { preset, samples } => analyze(preset, samples, output),
+    }
 }
 
+/// An event log, publishing to a socket at `observe` if there is one
+fn event_log(observe: Option<&Path>) -> EventLog {
+    let Some(path) = observe else {
+        return EventLog::default();
+    };
+    #[cfg(unix)]
+    match gui::observer::Observer::bind(path) {
+        Ok(observer) => {
+            let mut log = EventLog::default();
+            log.observer = Some(observer);
+            log
+        }
+        Err(e) => {
+            eprintln!("Failed to listen on {}: {e}", path.display());
+            EventLog::default()
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        eprintln!(
+            "Can't publish to {}, observing needs unix sockets",
+            path.display()
+        );
+        EventLog::default()
+    }
+}
+
+/// Whether to start in energy saver mode without being asked
+fn on_battery() -> bool {
+    #[cfg(feature = "power-probe")]
+    return gui::power::on_battery();
+    #[cfg(not(feature = "power-probe"))]
+    false
+}
+
+#[allow(clippy::too_many_lines)]
 fn main() {
     let Args {
-        width,
-        height,
-        max_bombs,
+        mut width,
+        mut height,
+        mut max_bombs,
         bastard,
-    } = Args::parse();
+        angel,
+        policy,
+        kaboom,
+        lazy,
+        spread,
+        sudden_collapse,
+        trace,
+        drill,
+        symmetric,
+        quiz,
+        big_cells,
+        practice,
+        assist,
+        #[cfg(feature = "onnx")]
+        guess_model,
+        threads,
+        low_priority,
+        energy_saver,
+        seed,
+        observe,
+        script,
+        #[cfg(feature = "tui")]
+        tui,
+        max_fifty_fifties,
+        min_openings,
+        rerolls,
+        skill,
+        adaptive,
+        no_undo,
+        flag_limit,
+        no_guess,
+        wrap,
+        neighborhood,
+        stencil,
+        layers,
+        multimines,
+        anti_mines,
+        liar,
+        no_color,
+        glyphs,
+        output,
+        help: _,
+        command,
+    } = args();
 
-    let mut board = Board::new(width, height);
+    if let Some(command) = command {
+        // Boards go to stderr, and NO_COLOR turns colors off like in other tools
+        let style = TextStyle {
+            glyphs,
+            color: !no_color
+                && std::env::var_os("NO_COLOR").is_none()
+                && std::io::stderr().is_terminal(),
+            bombs: false,
+        };
+        run_command(command, style, output);
+        return;
+    }
+    crash::install_hook();
+    let mut collapse_config = load_config();
+    collapse_config.threads = threads.unwrap_or(collapse_config.threads);
+    collapse_config.low_priority |= low_priority;
+    collapse_config.lazy |= lazy;
+    collapse_config.spread = spread.unwrap_or(collapse_config.spread);
+    if angel {
+        collapse_config.policy = Policy::Angel;
+    }
+    if let Some(policy) = policy {
+        collapse_config.policy = policy;
+    }
+    // Every policy plays on quantum cells, and so do kaboom rules and sudden collapses
+    let bastard = bastard || angel || policy.is_some() || kaboom || sudden_collapse.is_some();
 
-    if !(bastard) {
-        let mut rng = rng();
-        let mut bombs_to_place = max_bombs;
-        for (x, y) in (0..width).cartesian_product(0..height) {
-            board[(x, y)] = Cell::Concrete(false);
+    let mut board_rng = seed.map_or_else(|| StdRng::from_rng(&mut rng()), StdRng::seed_from_u64);
+    if layers == 0 {
+        eprintln!("Boards need at least one layer");
+        return;
+    }
+    let shape = match stencil.map(std::fs::read_to_string).transpose() {
+        Ok(Some(text)) => {
+            let Some(shape) = Board::from_stencil(&text) else {
+                eprintln!("The stencil has no cells");
+                return;
+            };
+            (width, height) = shape.dim();
+            shape
         }
-        while bombs_to_place > 0 {
-            let x = rng.random_range(0..width);
-            let y = rng.random_range(0..height);
-            if !board[(x, y)].is_bomb() {
-                board[(x, y)] = Cell::Concrete(true);
-                bombs_to_place -= 1;
-            }
+        Ok(None) => Board::new(width, height * layers).with_layers(layers),
+        Err(e) => {
+            eprintln!("Couldn't read the stencil: {e}");
+            return;
         }
+    };
+    let cells = shape.points().count();
+    let ruleset: Arc<dyn Ruleset> = if multimines > 1 {
+        Arc::new(Multimines {
+            neighborhood,
+            capacity: multimines,
+        })
+    } else if anti_mines > 0 {
+        Arc::new(AntiMines {
+            neighborhood,
+            count: anti_mines,
+        })
+    } else if let Some(chance) = liar {
+        Arc::new(Liar {
+            neighborhood,
+            chance,
+        })
+    } else {
+        Arc::new(Classic { neighborhood })
+    };
+    if let Err(e) = ruleset.check(cells, max_bombs) {
+        eprintln!("{e}");
+        return;
+    }
+    let across = 2 * neighborhood.reach() + 1;
+    if wrap && (width < across || height < across) {
+        eprintln!("Wrapping boards with this neighborhood need to be at least {across}x{across}");
+        return;
+    }
+    let mut board = shape
+        .with_wrap(wrap)
+        .with_neighborhood(ruleset.neighborhood());
+    let mut answer = None;
+    let limit = quiz.map(Duration::from_secs);
+    let mut quiz = None;
+    let mut dealer = None;
+    let rating = load_rating();
+
+    if let Some(grade) = drill {
+        let drill = solver::drill(grade, width, height, max_bombs, &mut board_rng);
+        board = drill.board;
+        answer = Some(drill.answer);
+    } else if let Some(limit) = limit {
+        let drill = solver::midgame(width, height, max_bombs, &mut board_rng);
+        quiz = Some(Quiz::new(&drill, limit));
+        board = drill.board;
+    } else if let Some(symmetry) = symmetric {
+        let Some((puzzle, bombs)) =
+            solver::symmetric_puzzle(symmetry, width, height, max_bombs, &mut board_rng)
+        else {
+            eprintln!("Quarter turn symmetry needs a square board");
+            return;
+        };
+        board = puzzle;
+        max_bombs = bombs;
+    } else if no_guess {
+        board = solver::no_guess_board(width, height, max_bombs, &mut board_rng);
+    } else if !(bastard) {
+        let mut honest = Dealer::new(
+            board.clone(),
+            max_bombs,
+            max_fifty_fifties,
+            rerolls,
+            board_rng,
+        );
+        honest.target = adaptive.then_some(rating);
+        honest.min_openings = min_openings;
+        honest.spread = collapse_config.spread;
+        honest.ruleset = ruleset.clone();
+        board = honest.deal();
+        dealer = Some(honest);
     }
 
+    let mut game = Game::new(board, max_bombs, bastard).with_ruleset(ruleset.clone());
+    if let Some(seed) = seed {
+        game = game.with_seed(seed);
+    }
+    game.first_click = answer.is_none() && quiz.is_none() && symmetric.is_none() && !no_guess;
+    game.undo_enabled = !no_undo;
+    game.kaboom = kaboom;
+    game.sudden_collapse = sudden_collapse.map(Duration::from_secs);
+    game.config = collapse_config;
+    if flag_limit {
+        game.rules.push(Arc::new(FlagLimit));
+    }
+    // The statistics' solver has to be able to read the variant's numbers
+    let statistics =
+        (game.first_click && !bastard && game.solver().is_some()).then(|| game.board.statistics());
+    #[cfg(feature = "onnx")]
+    let guess: Box<dyn GuessPolicy> = match guess_model
+        .as_deref()
+        .map(bastard_minesweeper::guess::Onnx::load)
+    {
+        Some(Ok(model)) => Box::new(model),
+        Some(Err(e)) => {
+            eprintln!("Couldn't load the guess model: {e}");
+            return;
+        }
+        None => Box::new(Safest),
+    };
+    #[cfg(not(feature = "onnx"))]
+    let guess: Box<dyn GuessPolicy> = Box::new(Safest);
+    // Puzzles promise a way through by deduction, and only some rulesets can lie
+    let puzzle = answer.is_some() || quiz.is_some() || symmetric.is_some() || no_guess;
+    let liar = (!puzzle && ruleset.lying(0.).is_some()).then_some(liar.unwrap_or(DEFAULT_LIES));
+    #[cfg(feature = "tui")]
+    if tui {
+        if let Err(e) = tui::run(game) {
+            eprintln!("The terminal failed: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
     let app = App {
-        board,
+        game,
         worker: None,
-        max_bombs,
-        bastard,
-        first_click: true,
-        win: false,
-        lose: None,
+        before_reveal: None,
         cheat: false,
-        flags: HashSet::new(),
+        settings: Settings {
+            big_cells,
+            energy_saver: energy_saver || on_battery(),
+            ..Settings::default()
+        },
+        effects: Effects::default(),
+        focus: Focus::default(),
+        cycle: Cycle::default(),
+        command: String::new(),
+        started: answer.as_ref().map(|_| Instant::now()),
+        waiting: Waiting::default(),
+        ended: None,
+        title: String::new(),
+        trace,
+        drill: answer,
+        drill_result: None,
+        quiz,
+        history: Vec::new(),
+        redo_history: Vec::new(),
+        undone: false,
+        log: event_log(observe.as_deref()),
+        previewing: false,
+        preview: None,
+        branch: None,
+        practice,
+        quicksave: None,
+        explanation: None,
+        hint: None,
+        assist,
+        heat: Heat::default(),
+        frontier: FrontierView::default(),
+        remaining: Remaining::default(),
+        collapse_config,
+        analysis: None,
+        packs: None,
+        review: None,
+        crashed: false,
+        crash_report: None,
+        statistics,
+        dealer,
+        skill,
+        rating,
+        rating_change: None,
+        #[cfg(feature = "serde")]
+        save_path: save_path().display().to_string(),
+        #[cfg(feature = "gamepad")]
+        gamepad: gui::gamepad::Gamepad::new(),
+        layer: 0,
+        liar,
+        guess,
+        advice: None,
+        locale: Locale::from_env(),
     };
+    if let Some(path) = script {
+        if !play_script(app, &path) {
+            std::process::exit(1);
+        }
+        return;
+    }
 
+    #[cfg(not(target_arch = "wasm32"))]
     eframe::run_native(
-        if bastard {
-            "Bastard Minesweeper"
-        } else {
-            "Minesweeper"
-        },
+        title(bastard, collapse_config.policy),
         NativeOptions::default(),
         Box::new(move |_| Ok(Box::new(app))),
     )
     .unwrap();
+    #[cfg(target_arch = "wasm32")]
+    web::start(app);
+}
+
+/// The command line, or the defaults on the web, which doesn't have one
+fn args() -> Args {
+    if cfg!(target_arch = "wasm32") {
+        Args::parse_from([env!("CARGO_PKG_NAME")])
+    } else {
+        Args::parse()
+    }
 }
 
 #[allow(clippy::struct_excessive_bools)]
 struct App {
-    pub board: Board,
-    pub worker: Option<JoinHandle<Board>>,
-    pub max_bombs: usize,
-    pub bastard: bool,
-    pub first_click: bool,
-    pub win: bool,
+    pub game: Game,
+    /// Computes the board after a reveal, with every collapse it took
+    /// `None` from it means the player aborted
+    pub worker: Option<Worker>,
+    /// How things were before the reveal the worker is busy with, to go back to if it's aborted
+    pub before_reveal: Option<Snapshot>,
     pub cheat: bool,
-    pub lose: Option<(usize, usize)>,
-    pub flags: HashSet<(usize, usize)>,
+    pub settings: Settings,
+    pub effects: Effects,
+    pub focus: Focus,
+    pub cycle: Cycle,
+    /// Move typed into the coordinate box
+    pub command: String,
+    pub started: Option<Instant>,
+    /// Time the player spent away from the window during the game
+    pub waiting: Waiting,
+    pub ended: Option<Instant>,
+    /// Last title sent to the window, so it's only updated on change
+    pub title: String,
+    pub trace: Option<PathBuf>,
+    /// Cells that could be proven safe at the start of a drill
+    pub drill: Option<Vec<(usize, usize)>>,
+    /// Whether the first move of the drill was a forced one
+    pub drill_result: Option<bool>,
+    pub quiz: Option<Quiz>,
+    /// Every click after the first, with the board as it was before it and how many moves
+    /// the game could undo before it. A chord adds a click for each cell it opens.
+    pub history: Vec<((usize, usize), Board, usize)>,
+    /// Clicks taken out of `history` by undo, for redo to put back
+    pub redo_history: Vec<((usize, usize), Board, usize)>,
+    /// A reveal was taken back, so the game doesn't count
+    pub undone: bool,
+    pub log: EventLog,
+    /// Clicks pick a cell to preview instead of revealing it
+    pub previewing: bool,
+    pub preview: Option<Preview>,
+    /// A sandbox the player is trying moves in, if they forked one
+    pub branch: Option<Branch>,
+    /// Quicksaves are allowed, so the game shouldn't count towards anything
+    pub practice: bool,
+    pub quicksave: Option<Snapshot>,
+    /// What the last collapse did, or why there's no hint, and when to stop showing it
+    pub explanation: Option<(String, f64)>,
+    pub assist: bool,
+    /// The move the Hint button suggested, highlighted until it's made
+    pub hint: Option<Hint>,
+    pub heat: Heat,
+    pub frontier: FrontierView,
+    pub remaining: Remaining,
+    pub collapse_config: CollapseConfig,
+    pub analysis: Option<Analysis>,
+    /// The board pack browser, while it's open
+    pub packs: Option<Packs>,
+    /// Hindsight on every move in `history`, shown after a loss
+    pub review: Option<Vec<((usize, usize), Verdict)>>,
+    /// A collapse panicked, so the game can't go on
+    pub crashed: bool,
+    /// The last crash report written, until dismissed
+    pub crash_report: Option<PathBuf>,
+    /// What kind of board an honest game dealt, shown until the first click
+    pub statistics: Option<BoardStatistics>,
+    /// Deals the next board if the player re-rolls this one
+    pub dealer: Option<Dealer>,
+    /// 3BV a second that par times are set for
+    pub skill: f64,
+    pub rating: f64,
+    /// How much the game that just ended moved `rating`, if it was rated
+    pub rating_change: Option<f64>,
+    /// Where the File menu saves and loads
+    #[cfg(feature = "serde")]
+    pub save_path: String,
+    #[cfg(feature = "gamepad")]
+    pub gamepad: Option<gui::gamepad::Gamepad>,
+    /// Layer of a layered board on show
+    pub layer: usize,
+    /// Chance of a lie when liar mode is toggled on, if this game can have it
+    pub liar: Option<f64>,
+    /// Picks the cell a hint suggests guessing
+    pub guess: Box<dyn GuessPolicy>,
+    /// Where first clicks go best on boards like this one, once worked out
+    pub advice: Option<Advice>,
+    /// How to write numbers and lay out panels
+    pub locale: Locale,
+}
+
+impl App {
+    /// Reveal a cell, kicking off a worker to compute the new numbers.
+    /// `at` is where on screen the cell is, for effects.
+    /// During a quiz this marks the cell as safe instead, and while previewing it previews the cell.
+    fn reveal(&mut self, x: usize, y: usize, at: Pos2, now: f64) {
+        if self.previewing {
+            self.preview = Preview::new(&self.game, (x, y));
+            return;
+        }
+        if let Some(quiz) = &mut self.quiz {
+            if !self.game.flags.contains(&(x, y)) {
+                quiz.toggle((x, y));
+            }
+            return;
+        }
+        if self.worker.is_some()
+            || self.crashed
+            || self.game.state() != GameState::Playing
+            || self.game.flags.contains(&(x, y))
+        {
+            return;
+        }
+        self.before_reveal = Some(self.snapshot());
+        self.save_undo();
+        // Only hidden cells make a move, which is also what the game's undo counts
+        let hidden = matches!(
+            self.game.board[(x, y)],
+            Cell::Quantum(_) | Cell::Concrete(_)
+        );
+        if hidden {
+            self.redo_history.clear();
+        }
+        if !self.game.first_click && hidden {
+            self.history
+                .push(((x, y), self.game.board.clone(), self.game.undo_depth()));
+        }
+        self.log.push((x, y), Event::Revealed);
+        if let Some(answer) = &self.drill {
+            self.drill_result.get_or_insert(answer.contains(&(x, y)));
+        }
+        if self.game.first_click {
+            self.started = Some(Instant::now());
+        }
+        let job = self.game.start_reveal(x, y);
+        self.after_move(job, at, now);
+    }
+
+    /// Open every unflagged cell around a number with all its bombs flagged, as one move
+    fn chord(&mut self, x: usize, y: usize, at: Pos2, now: f64) {
+        if self.previewing
+            || self.quiz.is_some()
+            || self.worker.is_some()
+            || self.crashed
+            || self.game.state() != GameState::Playing
+        {
+            return;
+        }
+        let cells = self.game.chord_cells(x, y);
+        if cells.is_empty() {
+            return;
+        }
+        self.before_reveal = Some(self.snapshot());
+        self.save_undo();
+        self.redo_history.clear();
+        for &cell in &cells {
+            self.history
+                .push((cell, self.game.board.clone(), self.game.undo_depth()));
+            self.log.push(cell, Event::Revealed);
+        }
+        let job = self.game.start_chord(x, y);
+        // The cells after a bomb never got opened
+        if let Some(lost) = self.game.lose
+            && let Some(i) = cells.iter().position(|&cell| cell == lost)
+        {
+            self.history
+                .truncate(self.history.len() - cells.len() + i + 1);
+        }
+        self.after_move(job, at, now);
+    }
+
+    /// Handle a loss from a move, then work out the numbers it left behind
+    fn after_move(&mut self, job: Option<Reveal>, at: Pos2, now: f64) {
+        if let Some((x, y)) = self.game.lose {
+            self.ended = Some(Instant::now());
+            self.rate(false);
+            println!("Lose!");
+            self.log
+                .publish(&format!("{{\"event\":\"lost\",\"cell\":[{x},{y}]}}"));
+            // The history keeps the real numbers, which are only the ones the player saw if
+            // none were off
+            self.review = (self.game.solver().is_some()
+                && self.game.lies.values().all(|l| *l == 0))
+            .then(|| {
+                self.history
+                    .iter()
+                    .filter_map(|(cell, board, _)| {
+                        let solver = self.game.ruleset.solver(board, self.game.max_bombs)?;
+                        Some((*cell, solver::review(&solver, board, *cell)))
+                    })
+                    .collect()
+            });
+            if self.settings.animate() {
+                self.effects.explode(at, now);
+            }
+            self.effects.shake(self.settings.shake_intensity(), now);
+            #[cfg(feature = "gamepad")]
+            if let Some(gamepad) = &mut self.gamepad {
+                gamepad.rumble(self.settings.shake_intensity());
+            }
+        }
+        if let Some(job) = job {
+            self.run(job);
+        }
+    }
+
+    /// Work out the numbers for a reveal on a worker thread
+    fn run(&mut self, job: Reveal) {
+        let trace = self.trace.clone();
+        let config = self.collapse_settings();
+        self.record_for_crash(&config);
+        self.game.collapse_started();
+        self.worker = Some(Worker::spawn(job, config, move |cancel| {
+            Progress::new(cancel, move |t: &CollapseTrace| {
+                write_trace(trace.as_deref(), t);
+                crash::record_trace(t);
+            })
+        }));
+    }
+
+    fn toggle_flag(&mut self, x: usize, y: usize) {
+        if !matches!(
+            self.game.board[(x, y)],
+            Cell::Quantum(_) | Cell::Concrete(_)
+        ) || self
+            .quiz
+            .as_ref()
+            .is_some_and(|q| q.score.is_some() || q.marked.contains(&(x, y)))
+        {
+            return;
+        }
+        self.save_undo();
+        match self.game.toggle_flag(x, y) {
+            Some(true) => self.log.push((x, y), Event::Flagged),
+            Some(false) => self.log.push((x, y), Event::Unflagged),
+            None => {}
+        }
+    }
+
+    /// Collapse limits, cut down in energy saver mode
+    fn collapse_settings(&self) -> CollapseConfig {
+        if self.settings.energy_saver {
+            self.collapse_config.energy_saver()
+        } else {
+            self.collapse_config
+        }
+    }
+
+    /// Keep what a crash report needs, before a collapse starts
+    fn record_for_crash(&self, config: &CollapseConfig) {
+        crash::record(
+            &self.game.board,
+            format!(
+                "seed: {:?}\nbastard: {}\nmax_bombs: {}\nfirst_click: {}\n{:?}\n{}",
+                self.game.seed,
+                self.game.bastard,
+                self.game.max_bombs,
+                self.game.first_click,
+                self.settings,
+                config.to_config_string().trim_end()
+            ),
+        );
+    }
+
+    /// Take back the last reveal, which stops the game counting towards medals and rating
+    fn undo(&mut self) {
+        if self.worker.is_some() || !self.game.undo() {
+            return;
+        }
+        self.undone = true;
+        self.hint = None;
+        // Every click made since the game was this deep in its undo, a whole chord at once
+        while let Some(entry) = self
+            .history
+            .pop_if(|(_, _, depth)| *depth >= self.game.undo_depth())
+        {
+            self.redo_history.push(entry);
+        }
+        if self.game.state() == GameState::Playing {
+            self.ended = None;
+        }
+        self.review = None;
+    }
+
+    /// Put back the last reveal taken back
+    fn redo(&mut self) {
+        if self.worker.is_some() || !self.game.redo() {
+            return;
+        }
+        self.hint = None;
+        while let Some(entry) = self
+            .redo_history
+            .pop_if(|(_, _, depth)| *depth < self.game.undo_depth())
+        {
+            self.history.push(entry);
+        }
+        if self.game.state() != GameState::Playing {
+            self.ended = Some(Instant::now());
+        }
+    }
+
+    /// Move the player's rating after a game ends, if the game was rated: an honest board,
+    /// outside practice and sandboxes
+    fn rate(&mut self, won: bool) {
+        let Some(statistics) = &self.statistics else {
+            return;
+        };
+        if self.practice || self.undone || self.branch.is_some() {
+            return;
+        }
+        let rating = stats::rate(self.rating, stats::board_rating(statistics), won);
+        self.rating_change = Some(rating - self.rating);
+        self.rating = rating;
+        save_rating(rating);
+    }
+
+    /// Swap the board for a freshly dealt one, before the first click
+    fn reroll(&mut self) {
+        let Some(board) = self.dealer.as_mut().and_then(Dealer::reroll) else {
+            return;
+        };
+        if self.statistics.is_some() {
+            self.statistics = Some(board.statistics());
+        }
+        self.game.board = board;
+        self.game.flags.clear();
+        self.quicksave = None;
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            game: self.game.clone(),
+            started: self.started,
+            ended: self.ended,
+            waiting: self.waiting,
+            history: self.history.len(),
+            log: self.log.mark(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.game = snapshot.game;
+        self.started = snapshot.started;
+        self.ended = snapshot.ended;
+        self.waiting = snapshot.waiting;
+        self.history.truncate(snapshot.history);
+        self.redo_history.clear();
+        self.hint = None;
+        self.log.rewind(snapshot.log);
+        self.review = None;
+    }
+
+    /// Remember the state before a move, if it's made in a branch
+    fn save_undo(&mut self) {
+        if self.branch.is_none() {
+            return;
+        }
+        let snapshot = self.snapshot();
+        if let Some(branch) = &mut self.branch {
+            branch.undo.push(snapshot);
+        }
+    }
+
+    /// The top bar: what the engine is doing, the controls and how the game is going
+    #[allow(clippy::too_many_lines)]
+    fn status_ui(&mut self, ui: &mut Ui, ctx: &Context, advice: Option<FirstClick>) {
+        #[cfg(feature = "serde")]
+        {
+            ui.menu_button("File", |ui| self.file_ui(ui));
+            ui.separator();
+        }
+        if self.worker.is_some() {
+            // The spinner repaints every frame
+            if !self.settings.energy_saver {
+                ui.spinner();
+            }
+            ui.label("Busy");
+            if ui.button("Abort").clicked()
+                && let Some(worker) = &self.worker
+            {
+                worker.cancel();
+            }
+        } else {
+            ui.label("Idle");
+        }
+        ui.separator();
+        ui.checkbox(&mut self.cheat, "Cheat");
+        if self.assist {
+            ui.checkbox(&mut self.heat.enabled, "Heat map");
+            ui.checkbox(&mut self.frontier.enabled, "Frontier only");
+            if ui
+                .add_enabled(self.worker.is_none(), Button::new("Hint"))
+                .clicked()
+            {
+                let explanation = match self.game.hint_or_guess(self.guess.as_ref()) {
+                    Ok(hint) => {
+                        self.hint = hint;
+                        match hint {
+                            Some(Hint::Guess(_)) => {
+                                Some("Nothing is certain, try the outlined cell".to_owned())
+                            }
+                            None => Some("Nothing is certain, only guesses are left".to_owned()),
+                            Some(_) => None,
+                        }
+                    }
+                    Err(e) => Some(format!("Couldn't pick a guess: {e}")),
+                };
+                if let Some(text) = explanation {
+                    self.explanation = Some((text, ctx.input(|i| i.time) + EXPLANATION_SECS));
+                }
+            }
+        }
+        if let Some(chance) = self.liar.filter(|_| self.game.ruleset.lying(0.).is_some()) {
+            let mut liar = self
+                .game
+                .ruleset
+                .parameters()
+                .iter()
+                .any(|&(name, chance)| name == "chance" && chance > 0.);
+            if ui
+                .checkbox(&mut liar, "Liar")
+                .on_hover_text("Numbers revealed from now on may be one off")
+                .changed()
+                && let Some(ruleset) = self.game.ruleset.lying(if liar { chance } else { 0. })
+            {
+                self.game.ruleset = ruleset;
+            }
+        }
+        if let Some(description) = self.game.ruleset.describe() {
+            ui.colored_label(
+                Color32::from_rgb(230, 140, 40),
+                format!("{}: {description}", self.game.ruleset.name()),
+            );
+        }
+        if let Some(advice) = advice {
+            ui.label(format!(
+                "Try {} first: it opens {}% of the time",
+                format_coord(advice.cell.0, advice.cell.1),
+                self.locale.number(advice.opens * 100., 0)
+            ))
+            .on_hover_text(format!(
+                "Revealing {} cells on average. Cells with fewer neighbors open more \
+                 often, cells in the middle open bigger areas when they do.",
+                self.locale.number(advice.revealed, 1)
+            ));
+        }
+        let layers = self.game.board.layers();
+        if layers > 1 {
+            ui.add(
+                Slider::new(&mut self.layer, 0..=layers - 1)
+                    .text("Layer")
+                    .custom_formatter(|n, _| (n + 1.).to_string()),
+            );
+        }
+        ui.menu_button("Settings", |ui| self.settings.ui(ui));
+        if ui.button("Packs").clicked() {
+            self.packs = Some(Packs::load(&packs_dir()));
+        }
+        if self.settings.coordinates {
+            let response = ui.add(
+                TextEdit::singleline(&mut self.command)
+                    .hint_text("C7, r C7, f C7")
+                    .desired_width(80.),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                if let Some((cell, action)) = parse_command(&self.command)
+                    && self.game.board.get(cell).is_some()
+                {
+                    self.focus.cell = Some(cell);
+                    if let Some(action) = action {
+                        self.apply(ctx, action);
+                    }
+                    self.command.clear();
+                }
+                response.request_focus();
+            }
+        }
+        ui.separator();
+        self.branch_ui(ui);
+        ui.separator();
+        ui.menu_button("Debug", |ui| {
+            ui.checkbox(&mut self.previewing, "Preview clicks");
+            if ui.button("Analyze components").clicked() {
+                self.analysis = Analysis::new(&self.game);
+                ui.close_menu();
+            }
+            if ui.button("Dump constraint graph").clicked() {
+                match std::fs::write("constraints.dot", self.game.board.constraint_graph_dot()) {
+                    Ok(()) => eprintln!("Wrote constraints.dot"),
+                    Err(e) => eprintln!("Failed to write constraints.dot: {e}"),
+                }
+                ui.close_menu();
+            }
+        });
+        if let Some(quiz) = &mut self.quiz {
+            ui.separator();
+            quiz.ui(ui, &self.game.flags);
+        }
+        match self.drill_result {
+            Some(true) => {
+                ui.separator();
+                ui.label("Found the forced move!");
+            }
+            Some(false) => {
+                ui.separator();
+                ui.label("That was a guess");
+            }
+            None => {}
+        }
+        if let Some(par) = self.par() {
+            ui.separator();
+            ui.label(format!("Par {}", clock(par)));
+        }
+        if self.game.lose.is_some() {
+            ui.separator();
+            ui.label("You lose!");
+        } else if self.game.win {
+            ui.separator();
+            ui.label("You win!");
+            // Practice games don't count
+            if let Some(par) = self.par().filter(|_| !self.practice && !self.undone) {
+                ui.label(match stats::Medal::award(self.active(), par) {
+                    Some(medal) => format!("{medal:?} medal"),
+                    None => "Over twice par".to_string(),
+                });
+            }
+        }
+        if self.ended.is_some() && self.started.is_some() {
+            ui.separator();
+            ui.label(format!(
+                "{} playing, {} in all",
+                clock(self.active()),
+                clock(self.elapsed())
+            ));
+        }
+        if self.game.bastard
+            && let Some(remaining) = self.remaining.label(&self.locale)
+        {
+            ui.separator();
+            ui.label(remaining).on_hover_text(
+                "Ways the bombs could still be laid out given your numbers. \
+                 Every number you uncover rules some out, and a collapse keeps one of them.",
+            );
+        }
+        if self.game.bastard && self.started.is_some() {
+            ui.separator();
+            ui.label(format!("Thinking {}", clock(self.game.thinking_time())))
+                .on_hover_text("Time spent on collapses, which doesn't count against you");
+        }
+        if let Some(change) = self.rating_change {
+            ui.separator();
+            ui.label(format!(
+                "Rating {} ({})",
+                self.locale.number(self.rating, 0),
+                self.locale.signed(change, 0)
+            ));
+        }
+    }
+
+    /// File menu: save the game to the path typed in, or load one from it
+    #[cfg(feature = "serde")]
+    fn file_ui(&mut self, ui: &mut Ui) {
+        ui.add(TextEdit::singleline(&mut self.save_path).hint_text("Save file"));
+        // Mid-reveal the numbers aren't all there yet
+        let idle = self.worker.is_none();
+        if ui.add_enabled(idle, Button::new("Save")).clicked() {
+            let json = SavedGame::from(&self.game).to_json();
+            match std::fs::write(&self.save_path, json) {
+                Ok(()) => eprintln!("Saved to {}", self.save_path),
+                Err(e) => eprintln!("Failed to save to {}: {e}", self.save_path),
+            }
+            ui.close_menu();
+        }
+        if ui.add_enabled(idle, Button::new("Load")).clicked() {
+            let loaded = std::fs::read_to_string(&self.save_path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| SavedGame::from_json(&text));
+            match loaded {
+                Ok(saved) => self.load(saved.into()),
+                Err(e) => eprintln!("Failed to load {}: {e}", self.save_path),
+            }
+            ui.close_menu();
+        }
+    }
+
+    /// Where to click first, if the player asked and it's the first click of an honest game.
+    /// Bastard games open an area wherever it is.
+    fn opening_advice(&mut self) -> Option<FirstClick> {
+        if !self.settings.opening_advice
+            || !self.game.first_click
+            || self.game.bastard
+            || self.game.solver().is_none()
+        {
+            return None;
+        }
+        let (board, bombs) = (&self.game.board, self.game.max_bombs);
+        self.advice
+            .get_or_insert_with(|| Advice::new(board, bombs, ADVICE_SAMPLES, &mut rng()))
+            .best()
+            .copied()
+    }
+
+    /// Carry on with a loaded game, forgetting everything about this one
+    fn load(&mut self, mut game: Game) {
+        self.started = (!game.first_click).then(Instant::now);
+        self.ended = (game.state() != GameState::Playing).then(Instant::now);
+        self.waiting = Waiting::default();
+        // Saves keep the ruleset but not the rules reacting to moves or how collapses run, so
+        // those carry on from this game
+        game.rules = std::mem::take(&mut self.game.rules);
+        game.kaboom = self.game.kaboom;
+        game.sudden_collapse = self.game.sudden_collapse;
+        game.config = self.game.config;
+        self.game = game;
+        self.history.clear();
+        self.redo_history.clear();
+        self.undone = false;
+        self.hint = None;
+        self.advice = None;
+        self.log.rewind((0, 0));
+        self.branch = None;
+        self.quicksave = None;
+        self.review = None;
+        self.drill = None;
+        self.drill_result = None;
+        self.quiz = None;
+        self.statistics = None;
+        self.dealer = None;
+        self.preview = None;
+        self.analysis = None;
+        self.explanation = None;
+    }
+
+    /// Branch controls: fork, undo and discard
+    fn branch_ui(&mut self, ui: &mut Ui) {
+        let idle = self.worker.is_none();
+        if self.branch.is_none() {
+            if ui.add_enabled(idle, Button::new("Fork")).clicked() {
+                self.branch = Some(Branch {
+                    real: self.snapshot(),
+                    undo: Vec::new(),
+                });
+            }
+            return;
+        }
+        ui.label("Sandbox");
+        let can_undo = self.branch.as_ref().is_some_and(|b| !b.undo.is_empty());
+        if ui
+            .add_enabled(idle && can_undo, Button::new("Undo"))
+            .clicked()
+            && let Some(snapshot) = self.branch.as_mut().and_then(|b| b.undo.pop())
+        {
+            self.restore(snapshot);
+        }
+        if ui.add_enabled(idle, Button::new("Discard")).clicked()
+            && let Some(branch) = self.branch.take()
+        {
+            self.restore(branch.real);
+        }
+    }
+
+    /// Time spent on the game so far, or in total once it's over
+    fn elapsed(&self) -> Duration {
+        match (self.started, self.ended) {
+            (Some(started), Some(ended)) => ended - started,
+            (Some(started), None) => started.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// [`App::elapsed`] without the time spent waiting on collapses or away from the window,
+    /// which is what the player is judged on
+    fn active(&self) -> Duration {
+        let now = self.ended.unwrap_or_else(Instant::now);
+        self.elapsed()
+            .saturating_sub(self.waiting.total(now))
+            .saturating_sub(self.game.thinking_time())
+    }
+
+    /// Time to beat on an honest board, from its 3BV
+    fn par(&self) -> Option<Duration> {
+        self.statistics
+            .map(|statistics| stats::par_time(statistics.three_bv, self.skill))
+    }
+
+    /// Show elapsed time and remaining mines in the window title. Quantum cells haven't
+    /// settled how many mines there are, so bastard mode shows as many as the numbers allow.
+    fn update_title(&mut self, ctx: &Context) {
+        if self.started.is_some() && self.ended.is_none() {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        let flags = self.game.flags.len();
+        let mines = match self.remaining.mines().filter(|_| self.game.bastard) {
+            Some(range) if range.start() != range.end() => format!(
+                "{}\u{2013}{}",
+                self.locale.count(range.start().saturating_sub(flags)),
+                self.locale.count(range.end().saturating_sub(flags))
+            ),
+            Some(range) => self.locale.count(range.end().saturating_sub(flags)),
+            None => self.locale.count(self.game.max_bombs.saturating_sub(flags)),
+        };
+        let title = format!(
+            "{}{} - {} - {mines} mines left",
+            title(self.game.bastard, self.collapse_config.policy),
+            if self.practice { " (practice)" } else { "" },
+            clock(self.active()),
+        );
+        if title != self.title {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+    }
+
+    fn apply(&mut self, ctx: &Context, action: Action) {
+        match action {
+            Action::Move(dx, dy) => {
+                self.focus.step(dx, dy, self.game.board.dim());
+                // Moving off the top or bottom of a layer shows the next one
+                if let Some((_, y)) = self.focus.cell {
+                    self.layer = y / self.game.board.layer_height();
+                }
+            }
+            Action::Reveal => {
+                if let Some((x, y)) = self.focus.cell {
+                    let at = self.focus.rect.map_or(Pos2::ZERO, |r| r.center());
+                    let now = ctx.input(|i| i.time);
+                    // Revealing a number chords on it
+                    if let Cell::Discovered(Some(_)) = self.game.board[(x, y)] {
+                        self.chord(x, y, at, now);
+                    } else {
+                        self.reveal(x, y, at, now);
+                    }
+                }
+            }
+            Action::Flag => {
+                if let Some((x, y)) = self.focus.cell {
+                    self.toggle_flag(x, y);
+                }
+            }
+            Action::Zoom(factor) => ctx.set_zoom_factor(ctx.zoom_factor() * factor),
+            Action::QuickSave => {
+                if self.practice && self.worker.is_none() {
+                    self.quicksave = Some(self.snapshot());
+                }
+            }
+            Action::QuickLoad => {
+                if self.practice
+                    && self.worker.is_none()
+                    && let Some(snapshot) = self.quicksave.clone()
+                {
+                    self.restore(snapshot);
+                }
+            }
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+        }
+    }
 }
 
 impl eframe::App for App {
     #[allow(clippy::too_many_lines)]
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        if self.board.iter().all(|c| {
-            matches!(
-                c,
-                Cell::Quantum(Some(true)) | Cell::Discovered(_) | Cell::Concrete(true)
-            )
-        }) {
-            self.win = true;
-        }
+        let won = self.game.win;
+        // The clock only runs while the player could be making a move, and the game keeps
+        // track of collapses itself
+        let playing = self.started.is_some() && self.ended.is_none();
+        let focused = ctx.input(|i| i.focused);
+        self.waiting.set(
+            playing && self.worker.is_none() && !focused,
+            self.ended.unwrap_or_else(Instant::now),
+        );
         // Join worker if we have one
         if let Some(worker) = std::mem::take(&mut self.worker) {
             if worker.is_finished() {
                 self.worker = None;
-                self.board = worker.join().unwrap();
+                self.game.collapse_finished();
+                let result = worker.join();
+                if let Ok(None) = result {
+                    if let Some(snapshot) = self.before_reveal.take() {
+                        // The wait happened even though the reveal is taken back
+                        let thinking = self.game.thinking;
+                        self.restore(snapshot);
+                        self.game.thinking = thinking;
+                    }
+                } else if let Ok(Some((board, traces))) = result {
+                    if traces.iter().any(|t| {
+                        !t.frontier.is_empty() && t.max_bombs > 0 && !t.approximate && t.states == 0
+                    }) {
+                        self.crash_report = crash::write("a collapse found no consistent states");
+                    }
+                    self.game.finish(board);
+                    if !won && self.game.win {
+                        self.log.publish("{\"event\":\"won\"}");
+                        self.ended = Some(Instant::now());
+                        self.rate(true);
+                        if self.settings.animate() {
+                            self.effects
+                                .confetti(ctx.screen_rect(), ctx.input(|i| i.time));
+                        }
+                    }
+                    for trace in &traces {
+                        self.log.collapsed(trace);
+                    }
+                    if self.settings.explain && !traces.is_empty() {
+                        self.explanation = Some((
+                            traces.iter().map(CollapseTrace::explain).join("\n"),
+                            ctx.input(|i| i.time) + EXPLANATION_SECS,
+                        ));
+                    }
+                } else {
+                    self.crashed = true;
+                    self.crash_report = crash::take_written();
+                }
             } else {
                 self.worker = Some(worker);
-                ctx.request_repaint();
+                if self.settings.energy_saver {
+                    ctx.request_repaint_after(Duration::from_millis(250));
+                } else {
+                    ctx.request_repaint();
+                }
             }
         }
-        if self.worker.is_none() {
-            let clearable_cells = self
-                .board
-                .points()
-                .filter(|p| matches!(self.board[*p], Cell::Discovered(Some(0))))
-                .flat_map(|(x, y)| {
-                    self.board
-                        .neighbors(x, y)
-                        .map(|(x, y, _)| (x, y))
-                        .filter(|p| matches!(self.board[*p], Cell::Quantum(_) | Cell::Concrete(_)))
-                        .collect::<Vec<_>>()
-                })
-                .collect::<HashSet<_>>();
-            if !clearable_cells.is_empty() {
-                let allowed_range =
-                    clearable_cells
-                        .iter()
-                        .fold((usize::MAX, usize::MAX)..(0, 0), |acc, el| {
-                            (
-                                acc.start.0.min(el.0.saturating_sub(2)),
-                                acc.start.1.min(el.1.saturating_sub(2)),
-                            )
-                                ..(acc.end.0.max(el.0 + 3), acc.end.1.max(el.1 + 3))
-                        });
-                for (x, y) in clearable_cells {
-                    self.board.clear_cell(x, y);
-                }
-                let mut new_board = self.board.clone();
-                let bastard = self.bastard;
-                let max_bombs = self.max_bombs;
-                self.worker = Some(std::thread::spawn(move || {
-                    if bastard {
-                        while new_board
-                            .iter()
-                            .any(|c| matches!(c, Cell::Discovered(None)))
-                        {
-                            new_board.collapse(max_bombs, Some(allowed_range.clone()));
-                            new_board.fill_discovered();
-                        }
-                    } else {
-                        new_board.fill_discovered();
+        if self.worker.is_none()
+            && !self.crashed
+            && let Some(job) = self.game.cascade()
+        {
+            self.run(job);
+        }
+        if self.worker.is_none() && !self.crashed {
+            match self.game.idle_left() {
+                Some(left) if left.is_zero() => {
+                    self.before_reveal = Some(self.snapshot());
+                    if let Some(job) = self.game.idle_collapse() {
+                        self.run(job);
                     }
-                    new_board
-                }));
+                }
+                Some(left) => ctx.request_repaint_after(left),
+                None => {}
             }
         }
+        if self.worker.is_none() {
+            self.before_reveal = None;
+        }
+        #[cfg_attr(not(feature = "gamepad"), allow(unused_mut))]
+        let mut actions = keyboard_actions(ctx);
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = &mut self.gamepad {
+            actions.extend(gamepad.actions());
+            ctx.request_repaint_after(gui::gamepad::POLL_INTERVAL);
+        }
+        for action in actions {
+            self.apply(ctx, action);
+        }
+        if self.game.bastard && self.worker.is_none() {
+            self.remaining.update(&self.game);
+        }
+        let advice = self.opening_advice();
         TopBottomPanel::top("status").show(ctx, |ui| {
-            ui.horizontal_centered(|ui| {
-                if self.worker.is_some() {
-                    ui.spinner();
-                    ui.label("Busy");
-                } else {
-                    ui.label("Idle");
-                }
-                ui.separator();
-                ui.checkbox(&mut self.cheat, "Cheat");
-                if self.lose.is_some() {
-                    ui.separator();
-                    ui.label("You lose!");
-                } else if self.win {
-                    ui.separator();
-                    ui.label("You win!");
-                }
+            ui.with_layout(self.locale.column(), |ui| {
+                ui.horizontal_centered(|ui| self.status_ui(ui, ctx, advice));
             });
         });
         CentralPanel::default().show(ctx, |ui| {
-            let (width, height) = self.board.dim();
-            TableBuilder::new(ui)
-                .columns(Column::exact(16.), width)
-                .body(|body| {
-                    body.rows(16., height, |mut row| {
-                        let y = row.index();
+            let (width, height) = (self.game.board.dim().0, self.game.board.layer_height());
+            // Layered boards show a layer at a time
+            let top = self.layer.min(self.game.board.layers() - 1) * height;
+            let mut focus_rect = None;
+            let cell_size = self.settings.cell_size();
+            // Far enough out that cells would be specks, aggregate them instead
+            let pixels = cell_size * ctx.zoom_factor() * ctx.pixels_per_point();
+            if pixels < overview::MIN_CELL_PIXELS {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let block = (overview::MIN_CELL_PIXELS / pixels).ceil() as usize;
+                #[allow(clippy::cast_precision_loss)]
+                let side = block as f32 * cell_size;
+                if let Some(cell) =
+                    overview::show(ui, &self.game.board, &self.game.flags, block, side)
+                {
+                    self.focus.cell = Some(cell);
+                    ctx.set_zoom_factor(1.);
+                }
+                return;
+            }
+            if self.worker.is_none() {
+                self.heat.update(&self.game);
+                self.frontier.update(&self.game);
+            }
+            let coordinates = self.settings.coordinates;
+            let column_names = (0..width)
+                .map(|x| self.settings.glyph(&column_name(x)))
+                .collect_vec();
+            // Boards wider than the window pan sideways as well as down
+            ScrollArea::horizontal().show(ui, |ui| {
+                let mut builder = TableBuilder::new(ui);
+                if coordinates {
+                    builder = builder.column(Column::exact(cell_size));
+                }
+                let builder = builder.columns(Column::exact(cell_size), width);
+                let body = |body: TableBody| {
+                    body.rows(cell_size, height, |mut row| {
+                        let y = top + row.index();
+                        if coordinates {
+                            row.col(|ui| {
+                                ui.label(self.settings.glyph(&(y + 1).to_string()));
+                            });
+                        }
                         for x in 0..width {
-                            let cell = self.board[(x, y)];
-                            row.col(|ui| match cell {
-                                Cell::Discovered(Some(n)) => {
-                                    ui.label(n.to_string());
+                            let cell = self.game.board[(x, y)];
+                            let focused = self.focus.cell == Some((x, y));
+                            let (rect, _) = row.col(|ui| {
+                                // Holes in a shaped board are left empty
+                                if let Cell::Void = cell {
+                                    return;
                                 }
-                                Cell::Quantum(_) | Cell::Concrete(_)
-                                    if self.lose.is_none() && !self.win =>
-                                {
-                                    if self.flags.contains(&(x, y)) {
-                                        if ui.button("F").secondary_clicked() {
-                                            self.flags.remove(&(x, y));
+                                match cell {
+                                    Cell::Discovered(Some(n)) => {
+                                        let n = self.game.shown((x, y)).unwrap_or(n);
+                                        let label = ui.add(
+                                            Label::new(self.settings.glyph(&n.to_string()))
+                                                .sense(Sense::click()),
+                                        );
+                                        // Middle click, or left click with the right button held
+                                        if label.middle_clicked()
+                                            || (label.clicked()
+                                                && ui.input(|i| {
+                                                    i.pointer.button_down(PointerButton::Secondary)
+                                                }))
+                                        {
+                                            self.focus.cell = Some((x, y));
+                                            self.chord(
+                                                x,
+                                                y,
+                                                label.rect.center(),
+                                                ui.input(|i| i.time),
+                                            );
                                         }
-                                    } else {
-                                        let button = ui.button(match cell {
-                                            Cell::Quantum(Some(true)) | Cell::Concrete(true)
-                                                if self.cheat =>
+                                    }
+                                    Cell::Quantum(_) | Cell::Concrete(_)
+                                        if self.game.lose.is_none() && !self.game.win =>
+                                    {
+                                        if self.game.flags.contains(&(x, y)) {
+                                            let button = ui
+                                                .button(self.settings.glyph(
+                                                    self.cycle.label((x, y)).unwrap_or("F"),
+                                                ));
+                                            if let Some(Action::Flag) = self
+                                                .settings
+                                                .buttons
+                                                .action(&button, (x, y), &mut self.cycle)
                                             {
-                                                "B"
+                                                self.toggle_flag(x, y);
                                             }
-                                            _ => " ",
-                                        });
-                                        if self.worker.is_none() && button.clicked() {
-                                            if self.first_click {
-                                                if self.bastard {
-                                                    for dy in -2..=2 {
-                                                        let y = y.saturating_add_signed(dy);
-                                                        for dx in -2..=2 {
-                                                            let x = x.saturating_add_signed(dx);
-                                                            let Some(cell) =
-                                                                self.board.get_mut((x, y))
-                                                            else {
-                                                                continue;
-                                                            };
-                                                            *cell = Cell::Discovered(None);
-                                                        }
-                                                    }
-                                                } else {
-                                                    self.board[(x, y)] = Cell::Discovered(None);
+                                        } else {
+                                            let label = match cell {
+                                                _ if let Some(label) = self.cycle.label((x, y)) => {
+                                                    label.to_string()
                                                 }
-                                            }
-                                            if !self.board.clear_cell(x, y) {
-                                                self.lose = Some((x, y));
-                                                println!("Lose!");
-                                                return;
-                                            }
-                                            let mut new_board = self.board.clone();
-                                            let bastard = self.bastard;
-                                            let max_bombs =
-                                                if self.first_click { 8 } else { self.max_bombs };
-                                            self.worker = Some(std::thread::spawn(move || {
-                                                if bastard {
-                                                    while new_board.iter().any(|c| {
-                                                        matches!(c, Cell::Discovered(None))
-                                                    }) {
-                                                        new_board.collapse(
-                                                            max_bombs,
-                                                            Some(
-                                                                (
-                                                                    x.saturating_sub(5),
-                                                                    y.saturating_sub(5),
-                                                                )
-                                                                    ..(x + 5, y + 5),
-                                                            ),
-                                                        );
-                                                        new_board.fill_discovered();
-                                                    }
-                                                } else {
-                                                    new_board.fill_discovered();
+                                                Cell::Quantum(Some(true))
+                                                | Cell::Concrete(..=-1 | 1..)
+                                                    if self.cheat =>
+                                                {
+                                                    bomb_glyph("B", *cell.bomb_count().start())
                                                 }
-                                                new_board
-                                            }));
-                                            self.first_click = false;
-                                        }
-                                        if button.secondary_clicked() {
-                                            self.flags.insert((x, y));
+                                                _ if self.quiz.as_ref().is_some_and(|q| {
+                                                    q.marked.contains(&(x, y))
+                                                }) =>
+                                                {
+                                                    "S".to_string()
+                                                }
+                                                _ => " ".to_string(),
+                                            };
+                                            let button = ui.button(self.settings.glyph(&label));
+                                            match self.settings.buttons.action(
+                                                &button,
+                                                (x, y),
+                                                &mut self.cycle,
+                                            ) {
+                                                Some(Action::Reveal) => {
+                                                    self.focus.cell = Some((x, y));
+                                                    self.reveal(
+                                                        x,
+                                                        y,
+                                                        button.rect.center(),
+                                                        ui.input(|i| i.time),
+                                                    );
+                                                }
+                                                Some(Action::Flag) => self.toggle_flag(x, y),
+                                                _ => {}
+                                            }
                                         }
                                     }
+                                    Cell::Quantum(Some(_)) | Cell::Concrete(_) => {
+                                        let glyph = match *cell.bomb_count().start() {
+                                            0 => " ".to_string(),
+                                            bombs if self.game.lose == Some((x, y)) => {
+                                                bomb_glyph("B", bombs)
+                                            }
+                                            bombs => bomb_glyph("b", bombs),
+                                        };
+                                        ui.label(self.settings.glyph(&glyph));
+                                    }
+                                    _ => {
+                                        ui.label(self.settings.glyph("?"));
+                                    }
+                                }
+                                for tint in [self.heat.tint((x, y)), self.frontier.tint((x, y))]
+                                    .into_iter()
+                                    .flatten()
+                                {
+                                    ui.painter().rect_filled(ui.max_rect(), 0., tint);
+                                }
+                                if let Some(hint) = self.hint {
+                                    show_hint(ui, &self.game, hint, (x, y));
                                 }
-                                Cell::Quantum(Some(b)) | Cell::Concrete(b) => {
-                                    ui.label(if b {
-                                        if self.lose == Some((x, y)) { "B" } else { "b" }
-                                    } else {
-                                        " "
-                                    });
+                                if advice.is_some_and(|a| a.cell == (x, y)) {
+                                    ui.painter().rect_stroke(
+                                        ui.max_rect(),
+                                        2.,
+                                        (2., Color32::LIGHT_GREEN),
+                                        StrokeKind::Inside,
+                                    );
                                 }
-                                _ => {
-                                    ui.label("?");
+                                if self.settings.big_cells {
+                                    ui.painter().rect_stroke(
+                                        ui.max_rect(),
+                                        0.,
+                                        (3., Color32::WHITE),
+                                        StrokeKind::Inside,
+                                    );
+                                }
+                                if focused {
+                                    show_focus(ui, self.focus.rect != Some(ui.max_rect()));
+                                }
+                                if self.cheat && ui.rect_contains_pointer(ui.max_rect()) {
+                                    show_tooltip_at_pointer(
+                                        ui.ctx(),
+                                        ui.layer_id(),
+                                        Id::new("cell history"),
+                                        |ui| {
+                                            ui.label(self.log.describe(
+                                                &self.game.seen(),
+                                                (x, y),
+                                                self.started,
+                                            ))
+                                        },
+                                    );
                                 }
                             });
+                            if focused {
+                                focus_rect = Some(rect);
+                            }
                         }
                     });
+                };
+                if coordinates {
+                    builder
+                        .header(cell_size, |mut header| {
+                            header.col(|_| {});
+                            for name in column_names {
+                                header.col(|ui| {
+                                    ui.label(name);
+                                });
+                            }
+                        })
+                        .body(body);
+                } else {
+                    builder.body(body);
+                }
+            });
+            self.focus.rect = focus_rect;
+        });
+        if !self.settings.animate() {
+            self.effects.clear();
+        }
+        if let Some((text, until)) = &self.explanation {
+            let now = ctx.input(|i| i.time);
+            if now < *until {
+                Area::new(Id::new("explanation"))
+                    .anchor(Align2::CENTER_BOTTOM, (0., -16.))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_max_width(320.);
+                            ui.label(text.as_str());
+                        });
+                    });
+                ctx.request_repaint_after(Duration::from_secs_f64(until - now));
+            } else {
+                self.explanation = None;
+            }
+        }
+        if let Some(analysis) = &self.analysis
+            && !analysis.show(ctx)
+        {
+            self.analysis = None;
+        }
+        let mut picked = None;
+        if let Some(packs) = &mut self.packs
+            && !packs.show(ctx, &mut picked)
+        {
+            self.packs = None;
+        }
+        if let Some(game) = picked
+            && self.worker.is_none()
+        {
+            self.load(game);
+        }
+        if let Some(preview) = &self.preview
+            && !preview.show(ctx)
+        {
+            self.preview = None;
+        }
+        if self.game.first_click
+            && self.settings.board_preview
+            && let Some(statistics) = &self.statistics
+        {
+            // Undo could bring the old board back
+            let rerolls = self
+                .dealer
+                .as_ref()
+                .filter(|_| self.branch.is_none())
+                .map(|dealer| dealer.rerolls);
+            if gui::statistics::show(ctx, &self.locale, statistics, self.rating, rerolls) {
+                self.reroll();
+            }
+        }
+        if let Some(path) = &self.crash_report {
+            let mut open = true;
+            Window::new("Crash report")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    if self.crashed {
+                        ui.label("A collapse crashed, so this game can't go on.");
+                    } else {
+                        ui.label("A collapse went wrong.");
+                    }
+                    ui.label("Attach this file to a bug report:");
+                    ui.monospace(path.display().to_string());
+                    if ui.button("Copy path").clicked() {
+                        ui.ctx().copy_text(path.display().to_string());
+                    }
                 });
+            if !open {
+                self.crash_report = None;
+            }
+        }
+        if let Some(moves) = &self.review
+            && !review::show(ctx, moves)
+        {
+            self.review = None;
+        }
+        self.effects.show(ctx);
+        self.update_title(ctx);
+    }
+}
+
+/// Where the player's own data lives: `$XDG_DATA_HOME`, falling back to `~/.local/share`,
+/// or the working directory
+fn data_dir() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .unwrap_or_default()
+}
+
+/// Where games are saved unless the player picks somewhere else
+#[cfg(feature = "serde")]
+fn save_path() -> PathBuf {
+    data_dir().join("bastard-minesweeper.save.json")
+}
+
+/// Where the board pack browser looks for `.bmpack` files
+fn packs_dir() -> PathBuf {
+    data_dir().join("bastard-minesweeper").join("packs")
+}
+
+fn rating_path() -> PathBuf {
+    data_dir().join("bastard-minesweeper.rating")
+}
+
+/// The player's rating from the last rated game, or a new player's
+fn load_rating() -> f64 {
+    std::fs::read_to_string(rating_path())
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(stats::INITIAL_RATING)
+}
+
+fn save_rating(rating: f64) {
+    let path = rating_path();
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, format!("{rating}\n")));
+    if let Err(e) = result {
+        eprintln!("Failed to write {}: {e}", path.display());
+    }
+}
+
+/// Where tuned collapse limits live: `$XDG_CONFIG_HOME`, falling back to `~/.config`
+fn config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("bastard-minesweeper.conf"))
+}
+
+/// Collapse limits from the config file, or the defaults if there isn't one
+fn load_config() -> CollapseConfig {
+    config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map_or_else(CollapseConfig::default, |text| {
+            let (config, ignored) = CollapseConfig::parse(&text);
+            for line in ignored {
+                eprintln!("Ignoring config line {line:?}");
+            }
+            config
+        })
+}
+
+fn tune(target: Duration, output: Output) {
+    let tuning = CollapseConfig::tune(target);
+    if output == Output::Json {
+        print_json(&tuning);
+    } else {
+        eprintln!("{:.0} partial assignments/s", tuning.assignments_per_sec);
+        eprintln!("{:.0} samples/s", tuning.samples_per_sec);
+        print!("{}", tuning.config.to_config_string());
+    }
+    let config = tuning.config;
+    let Some(path) = config_path() else {
+        eprintln!("Nowhere to save the config, set HOME or XDG_CONFIG_HOME");
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, config.to_config_string()));
+    match result {
+        Ok(()) => eprintln!("Wrote {}", path.display()),
+        Err(e) => eprintln!("Failed to write {}: {e}", path.display()),
+    }
+}
+
+/// Print a pass/fail line per check, and the first few failures of each.
+/// Returns whether everything passed.
+fn selfcheck(rounds: usize, output: Output) -> bool {
+    let checks = selfcheck::run(rounds, &mut rng());
+    let report = SelfcheckReport {
+        passed: checks.iter().all(selfcheck::Check::passed),
+        checks,
+    };
+    if output == Output::Json {
+        print_json(&report);
+        return report.passed;
+    }
+    for check in &report.checks {
+        println!(
+            "{} {} ({} runs, {} failed)",
+            if check.passed() { "PASS" } else { "FAIL" },
+            check.name,
+            check.runs,
+            check.failures.len()
+        );
+        for failure in check.failures.iter().take(5) {
+            println!("    {failure}");
+        }
+    }
+    report.passed
+}
+
+/// Drive `app` headlessly through the script at `path`.
+/// Returns whether the script could be read and every expectation held.
+fn play_script(mut app: App, path: &Path) -> bool {
+    let steps = match std::fs::read_to_string(path) {
+        Ok(text) => gui::script::parse(&text),
+        Err(e) => Err(e.to_string()),
+    };
+    let steps = match steps {
+        Ok(steps) => steps,
+        Err(e) => {
+            eprintln!("Failed to read script {}: {e}", path.display());
+            return false;
+        }
+    };
+    let mut headless = Headless::default();
+    // Lay the window out once so clicks land on something
+    headless.frame(&mut app, Vec::new());
+    for (i, step) in steps.into_iter().enumerate() {
+        match step {
+            Step::Key(key) => headless.key(&mut app, key),
+            Step::Click(pos, button) => headless.click(&mut app, pos, button),
+            Step::Move(cell, action) => {
+                if app.game.board.get(cell).is_some() {
+                    app.focus.cell = Some(cell);
+                    if let Some(action) = action {
+                        app.apply(&headless.ctx.clone(), action);
+                    }
+                }
+                headless.frame(&mut app, Vec::new());
+            }
+            Step::Wait(duration) => headless.wait(&mut app, duration),
+            Step::Idle => {
+                let began = Instant::now();
+                while app.worker.is_some() {
+                    if began.elapsed() > SCRIPT_IDLE_LIMIT {
+                        eprintln!("Step {}: still busy after {SCRIPT_IDLE_LIMIT:?}", i + 1);
+                        return false;
+                    }
+                    std::thread::sleep(Duration::from_millis(5));
+                    headless.frame(&mut app, Vec::new());
+                }
+            }
+            Step::Expect(state) => {
+                let actual = app.game.state();
+                if std::mem::discriminant(&actual) != std::mem::discriminant(&state) {
+                    eprintln!("Step {}: expected {state:?}, game is {actual:?}", i + 1);
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Print a line per kind of player: games won, and moves and guesses per game
+#[allow(clippy::cast_precision_loss)]
+fn simulate(
+    player: Option<Player>,
+    games: usize,
+    (width, height): (usize, usize),
+    bombs: usize,
+    output: Output,
+) {
+    let players = player.map_or(Player::ALL.to_vec(), |p| vec![p]);
+    let per_game = |n: usize| n as f64 / games.max(1) as f64;
+    let report = SimulateReport {
+        width,
+        height,
+        bombs,
+        games,
+        players: players
+            .into_iter()
+            .map(|player| {
+                let outcomes = (0..games)
+                    .map(|_| simulate::play(player, (width, height), bombs, &mut rng()))
+                    .collect_vec();
+                PlayerResult {
+                    player,
+                    won: outcomes.iter().filter(|o| o.won).count(),
+                    moves_per_game: per_game(outcomes.iter().map(|o| o.moves).sum()),
+                    guesses_per_game: per_game(outcomes.iter().map(|o| o.guesses).sum()),
+                }
+            })
+            .collect(),
+    };
+    if output == Output::Json {
+        print_json(&report);
+        return;
+    }
+    for result in &report.players {
+        println!(
+            "{:?}: won {}/{games}, {:.1} moves and {:.1} guesses per game",
+            result.player, result.won, result.moves_per_game, result.guesses_per_game,
+        );
+    }
+}
+
+/// Print a table of how `player` fared against each policy over the same seeded games: games
+/// won, forced guesses per game and time per collapse
+#[allow(clippy::cast_precision_loss)]
+fn bench_strategies(
+    player: Player,
+    games: usize,
+    (width, height): (usize, usize),
+    bombs: usize,
+    seed: u64,
+    output: Output,
+) {
+    let policies = Policy::value_variants()
+        .iter()
+        .map(|&policy| {
+            let config = CollapseConfig {
+                policy,
+                ..CollapseConfig::default()
+            };
+            let outcomes = (0..games as u64)
+                .map(|i| {
+                    let seed = seed.wrapping_add(i);
+                    simulate::play_seeded(player, (width, height), bombs, config, seed)
+                })
+                .collect_vec();
+            let collapses = outcomes.iter().map(|o| o.collapses).sum::<usize>();
+            let collapse_time = outcomes.iter().map(|o| o.collapse_time).sum::<Duration>();
+            PolicyResult {
+                policy,
+                won: outcomes.iter().filter(|o| o.won).count(),
+                guesses_per_game: outcomes.iter().map(|o| o.guesses).sum::<usize>() as f64
+                    / games.max(1) as f64,
+                ms_per_collapse: collapse_time.as_secs_f64() * 1000. / collapses.max(1) as f64,
+            }
+        })
+        .collect();
+    let report = BenchReport {
+        player,
+        width,
+        height,
+        bombs,
+        games,
+        seed,
+        policies,
+    };
+    if output == Output::Json {
+        print_json(&report);
+        return;
+    }
+    println!(
+        "{:<8} {:>9} {:>14} {:>12}",
+        "policy", "won", "guesses/game", "ms/collapse"
+    );
+    for result in &report.policies {
+        println!(
+            "{:<8} {:>9} {:>14.1} {:>12.1}",
+            result
+                .policy
+                .to_possible_value()
+                .map_or_else(String::new, |v| v.get_name().to_string()),
+            format!("{}/{games}", result.won),
+            result.guesses_per_game,
+            result.ms_per_collapse,
+        );
+    }
+}
+
+/// Print how first clicks go on each kind of cell of a board of `preset`'s size, and where
+/// to make one
+#[allow(clippy::cast_precision_loss)]
+fn analyze(preset: Preset, samples: usize, output: Output) {
+    let (width, height, bombs) = preset.size();
+    let advice = Advice::new(&Board::new(width, height), bombs, samples, &mut rng());
+    let report = AnalyzeReport {
+        width,
+        height,
+        bombs,
+        samples,
+        spots: advice.spots(),
+        best: advice.best().copied(),
+    };
+    if output == Output::Json {
+        print_json(&report);
+        return;
+    }
+    println!(
+        "{width}x{height} with {bombs} bombs, {:.1}% of the cells, over {samples} deals:",
+        bombs as f64 / (width * height) as f64 * 100.
+    );
+    let spots = &report.spots;
+    for (i, spot) in spots.iter().enumerate() {
+        let name = match i {
+            0 if spots.len() > 1 => "Corners",
+            i if i + 1 == spots.len() => "Middle",
+            _ => "Edges",
+        };
+        println!(
+            "{name} ({} cells, {} neighbors): opens {:.1}% of the time, revealing {:.1} cells on average",
+            spot.cells,
+            spot.neighbors,
+            spot.opens * 100.,
+            spot.revealed
+        );
+    }
+    if let Some(best) = report.best {
+        println!(
+            "Best first click: {}, opening {:.1}% of the time and revealing {:.1} cells on average",
+            format_coord(best.cell.0, best.cell.1),
+            best.opens * 100.,
+            best.revealed
+        );
+    }
+}
+
+/// Try seeds in parallel until one opens within `tolerance` of `target` difficulty, and print it
+/// along with its opening drawn in `style`. Returns whether one was found.
+fn find_seed(
+    preset: Preset,
+    bastard: bool,
+    target: f64,
+    tolerance: f64,
+    tries: u64,
+    style: TextStyle,
+    output: Output,
+) -> bool {
+    let (width, height, bombs) = preset.size();
+    let start = rng().random::<u64>();
+    let mut config = load_config();
+    // The seeds are already spread over the cores
+    config.threads = 1;
+    let progress = Counter::new(tries, "seeds");
+    let found = (0..tries)
+        .into_par_iter()
+        .map(|i| {
+            let seed = start.wrapping_add(i);
+            let board = seeded_opening(seed, (width, height), bombs, bastard, config);
+            progress.inc();
+            let difficulty = solver::opening_difficulty(&board, bombs);
+            (seed, difficulty, board)
+        })
+        .find_any(|(_, difficulty, _)| (difficulty - target).abs() <= tolerance);
+    progress.finish();
+    let Some((seed, difficulty, board)) = found else {
+        eprintln!("No seed within {tolerance} of {target} in {tries} tries");
+        return false;
+    };
+    let opening = (width / 2, height / 2);
+    if output == Output::Json {
+        print_json(&SeedReport {
+            seed,
+            difficulty,
+            width,
+            height,
+            bombs,
+            bastard,
+            opening,
+            board: board.to_text(),
         });
+        return true;
+    }
+    println!("{seed}");
+    eprintln!(
+        "Difficulty {difficulty:.2}, play it with --seed {seed} --width {width} --height {height} --max-bombs {bombs}{}, opening at {}",
+        if bastard { " --bastard" } else { "" },
+        format_coord(opening.0, opening.1),
+    );
+    eprint!("{}", style.render(&board, &HashSet::new()));
+    true
+}
+
+/// Write `count` boards to `out` in parallel, as `0001.txt` and so on in [`Board::to_text`]'s
+/// format, and a tab separated `manifest.tsv` saying how each was made and how big a job it is
+fn generate(
+    count: usize,
+    preset: Preset,
+    no_guess: bool,
+    out: &Path,
+    seed: Option<u64>,
+    output: Output,
+) -> std::io::Result<()> {
+    let (width, height, bombs) = preset.size();
+    let pack = seed.unwrap_or_else(|| rng().random());
+    std::fs::create_dir_all(out)?;
+    let progress = Counter::new(count as u64, "boards");
+    let digits = count.to_string().len().max(4);
+    let boards = (0..count)
+        .into_par_iter()
+        .map(|i| {
+            // Each board has its own seed, so one can be made again without the rest
+            let seed = pack.wrapping_add(i as u64);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let board = if no_guess {
+                solver::no_guess_board(width, height, bombs, &mut rng)
+            } else {
+                honest_board(width, height, bombs, &mut rng)
+            };
+            let name = format!("{:0digits$}.txt", i + 1);
+            std::fs::write(out.join(&name), board.to_text())?;
+            progress.inc();
+            Ok(PackBoard {
+                file: name,
+                width,
+                height,
+                bombs,
+                seed,
+                three_bv: board.statistics().three_bv,
+                opened: no_guess.then_some((width / 2, height / 2)),
+            })
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    progress.finish();
+    let rows = boards
+        .iter()
+        .map(|b| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                b.file,
+                b.width,
+                b.height,
+                b.bombs,
+                b.seed,
+                b.three_bv,
+                b.opened
+                    .map_or_else(String::new, |(x, y)| format_coord(x, y)),
+            )
+        })
+        .collect_vec();
+    let manifest = format!(
+        "file\twidth\theight\tbombs\tseed\t3bv\topened\n{}",
+        rows.concat()
+    );
+    std::fs::write(out.join("manifest.tsv"), manifest)?;
+    if output == Output::Json {
+        print_json(&PackReport {
+            out: out.to_path_buf(),
+            boards,
+        });
+    } else {
+        eprintln!("Wrote {count} boards to {}", out.display());
+    }
+    Ok(())
+}
+
+/// The board after opening the middle cell of a game started from `seed`
+fn seeded_opening(
+    seed: u64,
+    (width, height): (usize, usize),
+    bombs: usize,
+    bastard: bool,
+    config: CollapseConfig,
+) -> Board {
+    let board = if bastard {
+        Board::new(width, height)
+    } else {
+        honest_board(width, height, bombs, &mut StdRng::seed_from_u64(seed))
+    };
+    let mut game = Game::new(board, bombs, bastard).with_seed(seed);
+    game.config = config;
+    game.reveal(width / 2, height / 2);
+    game.board
+}
+
+/// Append a collapse to the trace file, if there is one
+fn write_trace(path: Option<&Path>, trace: &CollapseTrace) {
+    let Some(path) = path else {
+        return;
+    };
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| writeln!(f, "{}", trace.to_json()));
+    if let Err(e) = result {
+        eprintln!("Failed to write trace to {}: {e}", path.display());
+    }
+}
+
+/// Minutes and seconds
+fn clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// The name of the game, which depends on how quantum cells collapse
+fn title(quantum: bool, policy: Policy) -> &'static str {
+    match policy {
+        _ if !quantum => "Minesweeper",
+        Policy::Bastard => "Bastard Minesweeper",
+        Policy::Random => "Quantum Minesweeper",
+        Policy::Angel => "Angel Minesweeper",
+        Policy::Entropy => "Entropic Minesweeper",
+    }
+}
+
+/// `letter` marking a cell with `bombs` bombs, followed by how many if a multimines cell
+/// holds more than one or it's an anti-mine
+fn bomb_glyph(letter: &str, bombs: i16) -> String {
+    if bombs == 1 {
+        letter.to_string()
+    } else {
+        format!("{letter}{bombs}")
+    }
+}
+
+/// Outline `cell` if `hint` is about it and hasn't been followed yet: green to reveal it,
+/// red to flag it and yellow to guess it
+fn show_hint(ui: &Ui, game: &Game, hint: Hint, cell: (usize, usize)) {
+    let hidden = matches!(game.board[cell], Cell::Quantum(_) | Cell::Concrete(_));
+    let color = match hint {
+        Hint::Reveal(at) if at == cell && hidden => Color32::GREEN,
+        Hint::Flag(at) if at == cell && hidden && !game.flags.contains(&cell) => Color32::RED,
+        Hint::Guess(at) if at == cell && hidden => Color32::YELLOW,
+        _ => return,
+    };
+    ui.painter()
+        .rect_stroke(ui.max_rect(), 2., (2., color), StrokeKind::Inside);
+}
+
+/// Outline the focused cell, scrolling it into view if it moved
+fn show_focus(ui: &Ui, moved: bool) {
+    let rect = ui.max_rect();
+    ui.painter()
+        .rect_stroke(rect, 2., (2., Color32::LIGHT_BLUE), StrokeKind::Inside);
+    if moved {
+        ui.scroll_to_rect(rect, None);
     }
 }
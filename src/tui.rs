@@ -0,0 +1,193 @@
+//! Playing in a terminal instead of a window
+
+use std::{io, time::Duration};
+
+use bastard_minesweeper::{Cell, Game, GameState, Reveal, render::TextStyle, worker::Worker};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+/// How often the screen is redrawn while nothing happens, to notice a collapse finishing
+const TICK: Duration = Duration::from_millis(100);
+
+struct Tui {
+    game: Game,
+    worker: Option<Worker>,
+    cursor: (usize, usize),
+    /// A collapse panicked, so the game can't go on
+    crashed: bool,
+}
+
+/// Play `game` in the terminal until the player quits: arrow keys move, space reveals, `f`
+/// flags and `q` quits
+///
+/// # Errors
+/// If the terminal can't be drawn on or read from
+pub fn run(game: Game) -> io::Result<()> {
+    let (width, height) = game.board.dim();
+    let mut tui = Tui {
+        game,
+        worker: None,
+        cursor: (width / 2, height / 2),
+        crashed: false,
+    };
+    let mut terminal = ratatui::init();
+    let result = tui.play(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Tui {
+    fn play(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            self.poll_worker();
+            terminal.draw(|frame| self.draw(frame))?;
+            let timeout = match self.game.idle_left() {
+                Some(left) if self.worker.is_none() => left.min(TICK),
+                _ => TICK,
+            };
+            if !event::poll(timeout)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let (width, height) = self.game.board.dim();
+            let (x, y) = self.cursor;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left => self.cursor.0 = x.saturating_sub(1),
+                KeyCode::Right => self.cursor.0 = (x + 1).min(width - 1),
+                KeyCode::Up => self.cursor.1 = y.saturating_sub(1),
+                KeyCode::Down => self.cursor.1 = (y + 1).min(height - 1),
+                KeyCode::Char(' ') if self.ready() => {
+                    let job = self.game.start_reveal(x, y);
+                    self.spawn(job);
+                }
+                KeyCode::Char('f') if self.ready() => {
+                    self.game.toggle_flag(x, y);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the player can make a move
+    fn ready(&self) -> bool {
+        self.worker.is_none() && !self.crashed && self.game.state() == GameState::Playing
+    }
+
+    fn spawn(&mut self, job: Option<Reveal>) {
+        if let Some(job) = job {
+            self.game.collapse_started();
+            // Nothing can cancel it, but the worker wants an observer that could
+            self.worker = Some(Worker::spawn(job, self.game.config, |cancel| cancel));
+        }
+    }
+
+    /// Take the board from a finished worker, and start the next collapse the game needs
+    fn poll_worker(&mut self) {
+        if let Some(worker) = self.worker.take_if(|w| w.is_finished()) {
+            self.game.collapse_finished();
+            match worker.join() {
+                Ok(Some((board, _))) => self.game.finish(board),
+                Ok(None) => {}
+                Err(_) => self.crashed = true,
+            }
+        }
+        if self.worker.is_some() || self.crashed {
+            return;
+        }
+        if let Some(job) = self.game.cascade() {
+            self.spawn(Some(job));
+        } else if self.game.idle_left().is_some_and(|left| left.is_zero()) {
+            let job = self.game.idle_collapse();
+            self.spawn(job);
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let seen = self.game.seen();
+        let (width, height) = seen.dim();
+        let style = TextStyle {
+            bombs: matches!(self.game.state(), GameState::Lost(_)),
+            ..TextStyle::default()
+        };
+        let mut lines = (0..height)
+            .map(|y| {
+                Line::from(
+                    (0..width)
+                        .flat_map(|x| {
+                            let cell = seen[(x, y)];
+                            let flagged = self.game.flags.contains(&(x, y));
+                            let mut glyph = Span::styled(
+                                style.glyph(cell, flagged),
+                                look(style, cell, flagged),
+                            );
+                            if (x, y) == self.cursor {
+                                glyph = glyph.add_modifier(Modifier::REVERSED);
+                            }
+                            [glyph, Span::raw(" ")]
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::default());
+        lines.push(Line::from(self.status()));
+        lines.push(Line::from(
+            "Arrows move, space reveals, f flags, q quits".dark_gray(),
+        ));
+        frame.render_widget(Paragraph::new(lines), frame.area());
+    }
+
+    /// Busy or idle, as in the window, and how the game is going
+    fn status(&self) -> String {
+        let activity = if self.crashed {
+            "Crashed"
+        } else if self.worker.is_some() {
+            "Busy"
+        } else {
+            "Idle"
+        };
+        let state = match self.game.state() {
+            GameState::Playing => format!(
+                "{} bombs left",
+                self.game.max_bombs.saturating_sub(self.game.flags.len())
+            ),
+            GameState::Won => "Win!".to_string(),
+            GameState::Lost(_) => "Lose!".to_string(),
+        };
+        format!("{activity} | {state}")
+    }
+}
+
+/// Colors for a cell drawn in `style`, after the usual minesweeper colors
+fn look(style: TextStyle, cell: Cell, flagged: bool) -> Style {
+    let color = match cell {
+        Cell::Discovered(Some(n)) => match n {
+            ..=-1 => Color::LightMagenta,
+            0 => Color::Reset,
+            1 => Color::LightBlue,
+            2 => Color::Green,
+            3 => Color::LightRed,
+            4 => Color::Blue,
+            5 => Color::Red,
+            6 => Color::Cyan,
+            7 => Color::Magenta,
+            _ => Color::Gray,
+        },
+        Cell::Discovered(None) | Cell::Void => Color::Reset,
+        _ if flagged => Color::Yellow,
+        Cell::Quantum(Some(true)) | Cell::Concrete(..=-1 | 1..) if style.bombs => Color::Red,
+        _ => Color::DarkGray,
+    };
+    Style::new().fg(color)
+}
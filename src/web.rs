@@ -0,0 +1,29 @@
+//! Running in a browser, drawing on a canvas the page provides
+
+use eframe::{WebOptions, WebRunner, wasm_bindgen::JsCast, web_sys};
+
+use crate::App;
+
+/// Id of the canvas in `index.html`
+const CANVAS: &str = "game";
+
+/// Play `app` on the page's canvas
+pub fn start(app: App) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(CANVAS))
+            .and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .expect("the page should have a canvas with the id \"game\"");
+        if let Err(e) = WebRunner::new()
+            .start(
+                canvas,
+                WebOptions::default(),
+                Box::new(|_| Ok(Box::new(app))),
+            )
+            .await
+        {
+            web_sys::console::error_1(&e);
+        }
+    });
+}
@@ -0,0 +1,37 @@
+//! Play the input scripts in `tests/scripts` headlessly, through the whole window and its
+//! collapse worker
+
+use std::{path::Path, process::Command};
+
+/// Run `script` on a game started with `args`, failing if any of its expectations don't hold
+fn play(script: &str, args: &[&str]) {
+    // Away from the player's own config and stats
+    let home = std::env::temp_dir().join("bastard-minesweeper-scripts");
+    let output = Command::new(env!("CARGO_BIN_EXE_bastard-minesweeper"))
+        .args(args)
+        .arg("--script")
+        .arg(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/scripts")
+                .join(script),
+        )
+        .env("XDG_CONFIG_HOME", &home)
+        .env("XDG_DATA_HOME", &home)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{script}: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn win() {
+    play("win.txt", &["-w", "2", "-h", "1", "-m", "1", "-b"]);
+}
+
+#[test]
+fn lose() {
+    play("lose.txt", &["-w", "16", "-h", "16", "-m", "40", "-b"]);
+}